@@ -7,7 +7,8 @@ set -ex
 
 build_without_clippy() {
     (cd yaml;
-        cargo build --verbose)
+        cargo build --verbose;
+        cargo build --features chrono --verbose)
 }
 
 build_with_clippy() {
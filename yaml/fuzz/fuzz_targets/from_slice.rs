@@ -0,0 +1,20 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Feeds arbitrary bytes to `from_slice`, which must return `Ok` or `Err`
+//! for every input and never panic.
+
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate serde_yaml;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = serde_yaml::from_slice::<serde_yaml::Value>(data);
+});
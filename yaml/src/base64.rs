@@ -0,0 +1,73 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The base64 encoding of `!!binary` scalars.
+
+const ALPHABET: &[u8] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes `bytes` as standard, padded base64.
+pub fn encode(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let buf = chunk.iter()
+            .enumerate()
+            .fold(0u32, |buf, (i, &b)| buf | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let sextet = buf >> (18 - 6 * i) & 0x3f;
+                s.push(ALPHABET[sextet as usize] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+/// Decodes standard base64, skipping whitespace so that a payload may be
+/// wrapped across lines. Returns `None` if `s` is not valid base64.
+pub fn decode(s: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(s.len() * 3 / 4);
+    let mut buf = 0u32;
+    let mut bits = 0;
+    let mut padding = 0;
+    for c in s.bytes() {
+        let sextet = match c {
+            b' ' | b'\t' | b'\r' | b'\n' => continue,
+            b'=' => {
+                padding += 1;
+                continue;
+            }
+            // nothing may follow the padding
+            _ if padding > 0 => return None,
+            b'A'...b'Z' => c - b'A',
+            b'a'...b'z' => c - b'a' + 26,
+            b'0'...b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buf = buf << 6 | sextet as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buf >> bits) as u8);
+        }
+    }
+    // The leftover bits must be padded out to a multiple of 4 characters.
+    let complete = match (bits, padding) {
+        (0, 0) | (4, 2) | (2, 1) => true,
+        _ => false,
+    };
+    if complete && buf & ((1 << bits) - 1) == 0 {
+        Some(bytes)
+    } else {
+        None
+    }
+}
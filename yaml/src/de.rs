@@ -10,42 +10,164 @@
 //!
 //! This module provides YAML deserialization with the type `Deserializer`.
 
-use std::io;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
 use std::iter;
+use std::path::Path;
+use std::result;
 use std::slice;
 use std::str;
+use std::sync::Arc;
 
-use yaml_rust::{Yaml, YamlLoader};
+use yaml_rust::Yaml;
 use yaml_rust::yaml;
 
 use serde::de::{self, Deserialize};
+use serde::de::value::{SeqDeserializer, ValueDeserializer};
 
+use super::base64;
 use super::error::{Error, Result};
+use super::event::{self, Events};
+use super::loader;
+use super::style::{self, ScalarStyles};
+use super::tagged::{self, BINARY_TAG, SET_TAG, TAGGED_TOKEN};
+use super::value::Value;
 
 /// A structure for deserializing a YAML value into a Rust value.
 pub struct Deserializer<'a> {
     /// YAML value being deserialized.
     doc: &'a Yaml,
+    config: Config,
 }
 
 impl<'a> Deserializer<'a> {
     /// Creates the YAML deserializer from an in-memory `Yaml`.
     pub fn new(doc: &'a Yaml) -> Self {
+        Deserializer::with_config(doc, Config::default())
+    }
+
+    fn with_config(doc: &'a Yaml, config: Config) -> Self {
         Deserializer {
             doc: doc,
+            config: config,
+        }
+    }
+
+    /// The node being deserialized, looking past any tag it carries.
+    fn untagged(&self) -> &'a Yaml {
+        match tagged::split(self.doc) {
+            Some((_, inner)) => inner,
+            None => self.doc,
+        }
+    }
+
+    /// Fails if the node is a scalar, which cannot be read as the `expected`
+    /// kind of collection. This gives a clearer error than the visitor would.
+    fn not_scalar(&self, expected: &'static str) -> Result<()> {
+        let found = match *self.untagged() {
+            Yaml::String(ref s) |
+            Yaml::Real(ref s) => s.clone(),
+            Yaml::Integer(i) => i.to_string(),
+            Yaml::Boolean(b) => b.to_string(),
+            _ => return Ok(()),
+        };
+        Err(Error::ScalarNotACollection(expected, found))
+    }
+
+    /// Fails if the node is a sequence of other than `len` elements, which
+    /// a tuple or fixed-size array would otherwise report as a premature end
+    /// or not report at all.
+    fn check_len(&self, len: usize) -> Result<()> {
+        match *self.untagged() {
+            Yaml::Array(ref seq) if seq.len() != len => {
+                Err(Error::SeqWrongSize(len, seq.len()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails if the node is a float, which the visitor of an integer type
+    /// would otherwise truncate.
+    fn not_real(&self, ty: &'static str) -> Result<()> {
+        match *self.untagged() {
+            Yaml::Real(ref s) if !huge_integer(s) => {
+                Err(Error::TypeMismatch(ty, "real"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Rejects an integer outside `min..=max`, which the target type named
+    /// `ty` cannot hold.
+    fn check_range(&self, ty: &'static str, min: i64, max: u64) -> Result<()> {
+        match *self.untagged() {
+            Yaml::Integer(i) if i < min || (i > 0 && i as u64 > max) => {
+                Err(Error::IntegerOutOfRange(i.to_string(), ty))
+            }
+            // An integer too big even for `u64` is loaded as a real too.
+            Yaml::Real(ref s) if huge_integer(s) &&
+                                 big_integer(s).map_or(true, |u| u > max) => {
+                Err(Error::IntegerOutOfRange(s.clone(), ty))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Deserializes a node that the visitor expects to be a scalar of the
+    /// kind named `expected`. A collection, or a scalar that the visitor
+    /// rejects, is reported as a `TypeMismatch` naming what was found.
+    fn visit_scalar<V>(&mut self,
+                       expected: &'static str,
+                       visitor: V)
+                       -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        let found = type_name(self.untagged());
+        if let Yaml::Array(_) | Yaml::Hash(_) = *self.untagged() {
+            return Err(Error::TypeMismatch(expected, found));
+        }
+        match de::Deserializer::deserialize(self, visitor) {
+            Err(Error::InvalidType(_)) => {
+                Err(Error::TypeMismatch(expected, found))
+            }
+            result => result,
+        }
+    }
+
+    /// The bytes of a `!!binary` scalar, or `None` if the node is not one.
+    fn binary(&self) -> Result<Option<Vec<u8>>> {
+        match tagged::split(self.doc) {
+            Some((BINARY_TAG, &Yaml::String(ref s))) => {
+                base64::decode(s).map(Some).ok_or(Error::InvalidBase64)
+            }
+            _ => Ok(None),
         }
     }
+
+    /// The config for the nodes of a collection, one level deeper than this
+    /// node.
+    fn nested(&self) -> Result<Config> {
+        if self.config.remaining_depth == 0 {
+            return Err(Error::RecursionLimitExceeded);
+        }
+        let mut config = self.config;
+        config.remaining_depth -= 1;
+        Ok(config)
+    }
 }
 
 struct SeqVisitor<'a> {
     /// Iterator over the YAML array being visited.
     iter: slice::Iter<'a, Yaml>,
+    config: Config,
 }
 
 impl<'a> SeqVisitor<'a> {
-    fn new(seq: &'a [Yaml]) -> Self {
+    fn new(seq: &'a [Yaml], config: Config) -> Self {
         SeqVisitor {
             iter: seq.iter(),
+            config: config,
         }
     }
 }
@@ -59,7 +181,34 @@ impl<'a> de::SeqVisitor for SeqVisitor<'a> {
         match self.iter.next() {
             None => Ok(None),
             Some(t) => {
-                Deserialize::deserialize(&mut Deserializer::new(t)).map(Some)
+                let mut de = Deserializer::with_config(t, self.config);
+                Deserialize::deserialize(&mut de).map(Some)
+            }
+        }
+    }
+
+    fn end(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Visits the members of a `!!set`, which are the keys of its mapping.
+struct SetVisitor<'a> {
+    iter: <&'a yaml::Hash as iter::IntoIterator>::IntoIter,
+    config: Config,
+}
+
+impl<'a> de::SeqVisitor for SetVisitor<'a> {
+    type Error = Error;
+
+    fn visit<T>(&mut self) -> Result<Option<T>>
+        where T: Deserialize,
+    {
+        match self.iter.next() {
+            None => Ok(None),
+            Some((k, _)) => {
+                let mut de = Deserializer::with_config(k, self.config);
+                Deserialize::deserialize(&mut de).map(Some)
             }
         }
     }
@@ -69,18 +218,116 @@ impl<'a> de::SeqVisitor for SeqVisitor<'a> {
     }
 }
 
+/// Visits the entries of a mapping read as a sequence of key-value pairs.
+struct PairsVisitor<'a> {
+    iter: <&'a yaml::Hash as iter::IntoIterator>::IntoIter,
+    config: Config,
+}
+
+impl<'a> de::SeqVisitor for PairsVisitor<'a> {
+    type Error = Error;
+
+    fn visit<T>(&mut self) -> Result<Option<T>>
+        where T: Deserialize,
+    {
+        match self.iter.next() {
+            None => Ok(None),
+            Some((k, v)) => {
+                let mut de = PairDeserializer {
+                    pair: [k, v],
+                    visited: 0,
+                    config: self.config,
+                };
+                Deserialize::deserialize(&mut de).map(Some)
+            }
+        }
+    }
+
+    fn end(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A mapping entry seen as a sequence of its key and its value.
+struct PairDeserializer<'a> {
+    pair: [&'a Yaml; 2],
+    visited: usize,
+    config: Config,
+}
+
+impl<'a> de::SeqVisitor for PairDeserializer<'a> {
+    type Error = Error;
+
+    fn visit<T>(&mut self) -> Result<Option<T>>
+        where T: Deserialize,
+    {
+        if self.visited == self.pair.len() {
+            return Ok(None);
+        }
+        let node = self.pair[self.visited];
+        self.visited += 1;
+        let mut de = Deserializer::with_config(node, self.config);
+        Deserialize::deserialize(&mut de).map(Some)
+    }
+
+    fn end(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> de::Deserializer for PairDeserializer<'a> {
+    type Error = Error;
+
+    fn deserialize<V>(&mut self, mut visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        visitor.visit_seq(self)
+    }
+
+    fn deserialize_seq_fixed_size<V>(
+        &mut self,
+        len: usize,
+        visitor: V
+    ) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple<V>(
+        &mut self,
+        len: usize,
+        visitor: V
+    ) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        if len != self.pair.len() {
+            return Err(Error::SeqWrongSize(len, self.pair.len()));
+        }
+        self.deserialize(visitor)
+    }
+
+    forward_to_deserialize!{
+        bool usize u8 u16 u32 u64 isize i8 i16 i32 i64 f32 f64 char str string
+        unit option seq bytes map unit_struct newtype_struct tuple_struct
+        struct struct_field enum ignored_any
+    }
+}
+
 struct MapVisitor<'a> {
     /// Iterator over the YAML hash being visited.
     iter: <&'a yaml::Hash as iter::IntoIterator>::IntoIter,
     /// Value associated with the most recently visited key.
     v: Option<&'a Yaml>,
+    config: Config,
 }
 
 impl<'a> MapVisitor<'a> {
-    fn new(hash: &'a yaml::Hash) -> Self {
+    fn new(hash: &'a yaml::Hash, config: Config) -> Self {
         MapVisitor {
             iter: hash.into_iter(),
             v: None,
+            config: config,
         }
     }
 }
@@ -95,7 +342,8 @@ impl<'a> de::MapVisitor for MapVisitor<'a> {
             None => Ok(None),
             Some((k, v)) => {
                 self.v = Some(v);
-                Deserialize::deserialize(&mut Deserializer::new(k)).map(Some)
+                let mut de = Deserializer::with_config(k, self.config);
+                Deserialize::deserialize(&mut de).map(Some)
             }
         }
     }
@@ -104,7 +352,9 @@ impl<'a> de::MapVisitor for MapVisitor<'a> {
         where V: Deserialize,
     {
         if let Some(v) = self.v {
-            Deserialize::deserialize(&mut Deserializer::new(v))
+            let config = self.config.without_names();
+            let mut de = Deserializer::with_config(v, config);
+            Deserialize::deserialize(&mut de)
         } else {
             panic!("must call visit_key before visit_value")
         }
@@ -150,18 +400,48 @@ impl<'a> de::MapVisitor for MapVisitor<'a> {
     }
 }
 
+/// Visits a tagged node as the sequence of its tag and its inner node.
+struct TagVisitor<'a> {
+    tag: Option<&'a str>,
+    inner: Option<&'a Yaml>,
+    config: Config,
+}
+
+impl<'a> de::SeqVisitor for TagVisitor<'a> {
+    type Error = Error;
+
+    fn visit<T>(&mut self) -> Result<Option<T>>
+        where T: Deserialize,
+    {
+        if let Some(tag) = self.tag.take() {
+            Deserialize::deserialize(&mut tag.into_deserializer()).map(Some)
+        } else if let Some(inner) = self.inner.take() {
+            let mut de = Deserializer::with_config(inner, self.config);
+            Deserialize::deserialize(&mut de).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn end(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
 struct VariantVisitor<'a> {
     /// Representation of which variant it is.
     variant: &'a Yaml,
     /// Representation of the content of the variant.
     content: &'a Yaml,
+    config: Config,
 }
 
 impl<'a> VariantVisitor<'a> {
-    fn new(variant: &'a Yaml, content: &'a Yaml) -> Self {
+    fn new(variant: &'a Yaml, content: &'a Yaml, config: Config) -> Self {
         VariantVisitor {
             variant: variant,
             content: content,
+            config: config,
         }
     }
 }
@@ -172,7 +452,17 @@ impl<'a> de::VariantVisitor for VariantVisitor<'a> {
     fn visit_variant<V>(&mut self) -> Result<V>
         where V: Deserialize,
     {
-        Deserialize::deserialize(&mut Deserializer::new(self.variant))
+        match *self.variant {
+            // A unit variant written as its index.
+            Yaml::Integer(i) if i >= 0 => {
+                let index = i as usize;
+                Deserialize::deserialize(&mut index.into_deserializer())
+            }
+            ref variant => {
+                let mut de = Deserializer::with_config(variant, self.config);
+                Deserialize::deserialize(&mut de)
+            }
+        }
     }
 
     fn visit_unit(&mut self) -> Result<()> {
@@ -182,25 +472,30 @@ impl<'a> de::VariantVisitor for VariantVisitor<'a> {
     fn visit_newtype<T>(&mut self) -> Result<T>
         where T: Deserialize,
     {
-        Deserialize::deserialize(&mut Deserializer::new(self.content))
+        let config = self.config.without_names();
+        let mut de = Deserializer::with_config(self.content, config);
+        Deserialize::deserialize(&mut de)
     }
 
     fn visit_tuple<V>(&mut self, _len: usize, visitor: V) -> Result<V::Value>
         where V: de::Visitor,
     {
-        de::Deserializer::deserialize(&mut Deserializer::new(self.content),
-                                      visitor)
+        let config = self.config.without_names();
+        let mut de = Deserializer::with_config(self.content, config);
+        de::Deserializer::deserialize(&mut de, visitor)
     }
 
     fn visit_struct<V>(
         &mut self,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V
     ) -> Result<V::Value>
         where V: de::Visitor,
     {
-        de::Deserializer::deserialize(&mut Deserializer::new(self.content),
-                                      visitor)
+        let mut config = self.config;
+        config.names = fields;
+        let mut de = Deserializer::with_config(self.content, config);
+        de::Deserializer::deserialize(&mut de, visitor)
     }
 }
 
@@ -210,8 +505,17 @@ impl<'a> de::Deserializer for Deserializer<'a> {
     fn deserialize<V>(&mut self, mut visitor: V) -> Result<V::Value>
         where V: de::Visitor,
     {
-        match *self.doc {
+        match *self.untagged() {
             Yaml::Real(ref s) => {
+                if let Some(u) = big_integer(s) {
+                    return visitor.visit_u64(u);
+                }
+                if float_out_of_range(s, f64::MAX) {
+                    return Err(Error::FloatOutOfRange(s.clone(), "f64"));
+                }
+                if let Some(f) = loader::special_float(s) {
+                    return visitor.visit_f64(f);
+                }
                 match s.parse() {
                     Ok(f) => visitor.visit_f64(f),
                     Err(_) => visitor.visit_str(s),
@@ -220,21 +524,217 @@ impl<'a> de::Deserializer for Deserializer<'a> {
             Yaml::Integer(i) => visitor.visit_i64(i),
             Yaml::String(ref s) => visitor.visit_str(s),
             Yaml::Boolean(b) => visitor.visit_bool(b),
-            Yaml::Array(ref seq) => visitor.visit_seq(SeqVisitor::new(seq)),
-            Yaml::Hash(ref hash) => visitor.visit_map(MapVisitor::new(hash)),
+            Yaml::Array(ref seq) => {
+                let config = try!(self.nested());
+                visitor.visit_seq(SeqVisitor::new(seq, config))
+            }
+            Yaml::Hash(ref hash) => {
+                let config = try!(self.nested());
+                visitor.visit_map(MapVisitor::new(hash, config))
+            }
             Yaml::Alias(_) => Err(Error::AliasUnsupported),
             Yaml::Null => visitor.visit_unit(),
             Yaml::BadValue => {
-                // The yaml-rust library produces BadValue when a nonexistent
-                // node is accessed by the Index trait, and when a type
-                // conversion is invalid. Both of these are unexpected in our
-                // usage.
-                panic!("bad value")
+                // The loader leaves a BadValue for a document with no node
+                // and for a forward alias whose anchor is never defined. In
+                // both there is no value to read.
+                Err(Error::EndOfStream)
+            }
+        }
+    }
+
+    /// Parses `true` and `false` as booleans. With lenient booleans
+    /// enabled, the other YAML 1.1 spellings such as `yes` and `off` are
+    /// accepted too.
+    fn deserialize_bool<V>(&mut self, mut visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        if let Yaml::String(ref s) = *self.untagged() {
+            if self.config.lenient_bools {
+                if let Some(b) = lenient_bool(s) {
+                    return visitor.visit_bool(b);
+                }
             }
         }
+        self.visit_scalar("bool", visitor)
+    }
+
+    /// Parses an integer. A float is an error even if it has no fractional
+    /// part, rather than being truncated, and so is an integer that does not
+    /// fit. The other integer types behave the same way.
+    fn deserialize_isize<V>(&mut self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        try!(self.not_real("isize"));
+        try!(self.check_range("isize", isize::MIN as i64, isize::MAX as u64));
+        self.visit_scalar("isize", visitor)
+    }
+
+    fn deserialize_i8<V>(&mut self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        try!(self.not_real("i8"));
+        try!(self.check_range("i8", i8::MIN as i64, i8::MAX as u64));
+        self.visit_scalar("i8", visitor)
+    }
+
+    fn deserialize_i16<V>(&mut self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        try!(self.not_real("i16"));
+        try!(self.check_range("i16", i16::MIN as i64, i16::MAX as u64));
+        self.visit_scalar("i16", visitor)
+    }
+
+    fn deserialize_i32<V>(&mut self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        try!(self.not_real("i32"));
+        try!(self.check_range("i32", i32::MIN as i64, i32::MAX as u64));
+        self.visit_scalar("i32", visitor)
+    }
+
+    fn deserialize_i64<V>(&mut self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        try!(self.not_real("i64"));
+        try!(self.check_range("i64", i64::MIN, i64::MAX as u64));
+        self.visit_scalar("i64", visitor)
+    }
+
+    fn deserialize_usize<V>(&mut self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        try!(self.not_real("usize"));
+        try!(self.check_range("usize", 0, usize::MAX as u64));
+        self.visit_scalar("usize", visitor)
+    }
+
+    fn deserialize_u8<V>(&mut self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        try!(self.not_real("u8"));
+        try!(self.check_range("u8", 0, u8::MAX as u64));
+        self.visit_scalar("u8", visitor)
+    }
+
+    fn deserialize_u16<V>(&mut self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        try!(self.not_real("u16"));
+        try!(self.check_range("u16", 0, u16::MAX as u64));
+        self.visit_scalar("u16", visitor)
+    }
+
+    fn deserialize_u32<V>(&mut self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        try!(self.not_real("u32"));
+        try!(self.check_range("u32", 0, u32::MAX as u64));
+        self.visit_scalar("u32", visitor)
+    }
+
+    fn deserialize_u64<V>(&mut self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        try!(self.not_real("u64"));
+        try!(self.check_range("u64", 0, u64::MAX));
+        self.visit_scalar("u64", visitor)
+    }
+
+    /// Parses a sequence, or the members of a `!!set` mapping. Any other
+    /// mapping is read as a sequence of its entries, each a sequence of the
+    /// key and the value, so that a `Vec<(K, V)>` can be written either as
+    /// `[[a, 1], [b, 2]]` or as `{a: 1, b: 2}`. A `!!binary` scalar is read
+    /// as the sequence of its bytes. A null, as in `items:` with nothing
+    /// after it, is read as an empty sequence.
+    fn deserialize_seq<V>(&mut self, mut visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        if let Some(bytes) = try!(self.binary()) {
+            let len = bytes.len();
+            return visitor.visit_seq(SeqDeserializer::new(bytes.into_iter(),
+                                                          len));
+        }
+        let set = tagged::split(self.doc);
+        if let Some((SET_TAG, &Yaml::Hash(ref hash))) = set {
+            return visitor.visit_seq(SetVisitor {
+                iter: hash.into_iter(),
+                config: try!(self.nested()),
+            });
+        }
+        if let Yaml::Hash(ref hash) = *self.untagged() {
+            return visitor.visit_seq(PairsVisitor {
+                iter: hash.into_iter(),
+                config: try!(self.nested()),
+            });
+        }
+        try!(self.not_scalar("sequence"));
+        self.deserialize(visitor)
+    }
+
+    fn deserialize_seq_fixed_size<V>(
+        &mut self,
+        len: usize,
+        visitor: V
+    ) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        try!(self.check_len(len));
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple<V>(
+        &mut self,
+        len: usize,
+        visitor: V
+    ) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        try!(self.check_len(len));
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        &mut self,
+        _name: &'static str,
+        len: usize,
+        visitor: V
+    ) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        try!(self.check_len(len));
+        self.deserialize_seq(visitor)
+    }
+
+    /// Parses a mapping. A null is read as an empty mapping.
+    fn deserialize_map<V>(&mut self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        try!(self.not_scalar("mapping"));
+        self.deserialize(visitor)
+    }
+
+    /// Parses a mapping into a struct. Keys that are numbers or booleans
+    /// are matched against the field names as strings, so `2024: x` sets a
+    /// field renamed to `"2024"`.
+    fn deserialize_struct<V>(
+        &mut self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V
+    ) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        self.config.names = fields;
+        self.deserialize_map(visitor)
     }
 
-    /// Parses `null` as None and any other values as `Some(...)`.
+    /// Parses a null, whether written as `~`, `null` or nothing at all, as
+    /// `None`, and anything else, including a quoted empty string, as
+    /// `Some`. A struct field whose key is present with a null value is
+    /// therefore `None` even if the field has a default, which serde applies
+    /// only when the key is missing.
     fn deserialize_option<V>(&mut self, mut visitor: V) -> Result<V::Value>
         where V: de::Visitor,
     {
@@ -244,85 +744,925 @@ impl<'a> de::Deserializer for Deserializer<'a> {
         }
     }
 
-    /// Parses a newtype struct as the underlying value.
+    /// Parses a newtype struct as the underlying value, except that a
+    /// `TaggedValue` is given the tag along with the value.
     fn deserialize_newtype_struct<V>(
         &mut self,
-        _name: &str,
+        name: &str,
+        mut visitor: V
+    ) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        if name != TAGGED_TOKEN {
+            return visitor.visit_newtype_struct(self);
+        }
+        match tagged::split(self.doc) {
+            Some((tag, inner)) => {
+                visitor.visit_seq(TagVisitor {
+                    tag: Some(tag),
+                    inner: Some(inner),
+                    config: self.config,
+                })
+            }
+            None => Err(Error::MissingTag),
+        }
+    }
+
+    /// Parses the key naming a struct field, or the name of an enum variant.
+    /// A key like `1` or `true` is read as it is written, so that it can name
+    /// a field renamed to `"1"`. With `case_insensitive_names`, a name that
+    /// matches none exactly is read as the one it matches regardless of case.
+    fn deserialize_struct_field<V>(
+        &mut self,
         mut visitor: V
     ) -> Result<V::Value>
         where V: de::Visitor,
     {
-        visitor.visit_newtype_struct(self)
+        if self.config.case_insensitive_names {
+            if let Yaml::String(ref s) = *self.untagged() {
+                let names = self.config.names;
+                if !names.contains(&s.as_str()) {
+                    let name = names.iter()
+                        .find(|name| name.eq_ignore_ascii_case(s));
+                    if let Some(name) = name {
+                        return visitor.visit_str(name);
+                    }
+                }
+            }
+        }
+        match *self.untagged() {
+            Yaml::Integer(i) => visitor.visit_str(&i.to_string()),
+            Yaml::Real(ref s) => visitor.visit_str(s),
+            Yaml::Boolean(b) => {
+                visitor.visit_str(if b { "true" } else { "false" })
+            }
+            _ => self.deserialize(visitor),
+        }
     }
 
     /// Parses an enum as a single key:value pair where the key identifies the
     /// variant and the value gives the content. A String will also parse correctly
-    /// to a unit enum value.
+    /// to a unit enum value, as will the integer index of the variant.
     fn deserialize_enum<V>(
         &mut self,
         name: &str,
-        _variants: &'static [&'static str],
+        variants: &'static [&'static str],
         mut visitor: V
     ) -> Result<V::Value>
         where V: de::EnumVisitor,
     {
-        match *self.doc {
+        self.config.names = variants;
+        // A variant with content may be written as its content tagged with
+        // the name of the variant.
+        if let Some((variant, content)) = tagged::split_node(self.doc) {
+            let config = try!(self.nested());
+            return visitor.visit(VariantVisitor::new(variant, content, config));
+        }
+        match *self.untagged() {
             Yaml::Hash(ref hash) => {
                 let mut iter = hash.iter();
                 if let (Some(entry), None) = (iter.next(), iter.next()) {
                     let (variant, content) = entry;
-                    visitor.visit(VariantVisitor::new(variant, content))
+                    let config = try!(self.nested());
+                    visitor.visit(VariantVisitor::new(variant, content, config))
                 } else {
                     Err(Error::VariantMapWrongSize(String::from(name),
                                                    hash.len()))
                 }
             }
             ref ystr @ Yaml::String(_) => {
-                visitor.visit(VariantVisitor::new(ystr, &Yaml::Null))
+                visitor.visit(VariantVisitor::new(ystr,
+                                                  &Yaml::Null,
+                                                  self.config))
+            }
+            ref yint @ Yaml::Integer(_) => {
+                visitor.visit(VariantVisitor::new(yint,
+                                                  &Yaml::Null,
+                                                  self.config))
             }
             _ => Err(Error::VariantNotAMapOrString(String::from(name))),
         }
     }
 
+    /// Decodes a `!!binary` scalar from base64, or with `hex_bytes` a string
+    /// from hexadecimal. Any other node is read as usual, so a sequence of
+    /// integers, as `BytesEncoding::IntArray` writes, gives one byte per
+    /// element and fails on an element outside of `0..256`.
+    fn deserialize_bytes<V>(&mut self, mut visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        if let Some(bytes) = try!(self.binary()) {
+            return visitor.visit_byte_buf(bytes);
+        }
+        if self.config.hex_bytes {
+            if let Yaml::String(ref s) = *self.doc {
+                let bytes = try!(decode_hex(s).ok_or(Error::InvalidHex));
+                return visitor.visit_byte_buf(bytes);
+            }
+        }
+        self.deserialize(visitor)
+    }
+
+    /// Parses a float as `deserialize_f64` does.
+    fn deserialize_f32<V>(&mut self, mut visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        if let Yaml::Real(ref s) = *self.untagged() {
+            if float_out_of_range(s, f32::MAX as f64) {
+                return Err(Error::FloatOutOfRange(s.clone(), "f32"));
+            }
+            // serde would reject these on the way from `f64`
+            if let Some(f) = loader::special_float(s) {
+                return visitor.visit_f32(f as f32);
+            }
+        }
+        self.visit_scalar("f32", visitor)
+    }
+
+    /// Parses a float, including `.inf`, `-.inf` and `.nan` in any of the
+    /// capitalizations YAML allows. One too big for the type is an error
+    /// rather than infinity, while one too small to tell from zero is zero.
+    fn deserialize_f64<V>(&mut self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        self.visit_scalar("f64", visitor)
+    }
+
+    fn deserialize_char<V>(&mut self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        self.visit_scalar("char", visitor)
+    }
+
+    fn deserialize_str<V>(&mut self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    /// Parses a string, or with `coerce_scalars` a null, a boolean or a number
+    /// in the form that `coerce_scalars` describes.
+    fn deserialize_string<V>(&mut self, mut visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        if self.config.coerce_scalars {
+            match *self.untagged() {
+                Yaml::Null => return visitor.visit_str("~"),
+                Yaml::Boolean(b) => return visitor.visit_str(&b.to_string()),
+                Yaml::Integer(i) => return visitor.visit_str(&i.to_string()),
+                Yaml::Real(ref s) => return visitor.visit_str(s),
+                _ => {}
+            }
+        } else if let Yaml::Null = *self.untagged() {
+            // The visitor of `String` would take a null as an empty string.
+            return Err(Error::TypeMismatch("string", "null"));
+        }
+        self.visit_scalar("string", visitor)
+    }
+
     forward_to_deserialize!{
-        bool usize u8 u16 u32 u64 isize i8 i16 i32 i64 f32 f64 char str string
-        unit seq seq_fixed_size bytes map unit_struct tuple_struct struct
-        struct_field tuple ignored_any
+        unit unit_struct ignored_any
+    }
+}
+
+/// The kind of YAML node, as named by `Value::type_name`.
+fn type_name(node: &Yaml) -> &'static str {
+    match *node {
+        Yaml::Null => "null",
+        Yaml::Boolean(_) => "bool",
+        Yaml::Real(ref s) if huge_integer(s) => "integer",
+        Yaml::Integer(_) => "integer",
+        Yaml::Real(_) => "real",
+        Yaml::String(_) => "string",
+        Yaml::Array(_) => "sequence",
+        Yaml::Hash(_) => "mapping",
+        Yaml::Alias(_) | Yaml::BadValue => "alias",
+    }
+}
+
+/// The value of an integer too big for `i64`, which is loaded as a real. A
+/// real written as a whole number, like `!!float 1`, is still a float.
+fn big_integer(s: &str) -> Option<u64> {
+    match s.parse() {
+        Ok(u) if u > i64::MAX as u64 => Some(u),
+        _ => None,
+    }
+}
+
+/// Decodes two hexadecimal digits per byte, in either case.
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 || !s.is_ascii() {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Whether a real is an integer too big for `i64`, which is loaded as a real
+/// whether or not it fits in `u64`. A real written as a whole number that
+/// fits, like `!!float 1`, is still a float.
+fn huge_integer(s: &str) -> bool {
+    let digits = s.trim_left_matches(|c| c == '+' || c == '-');
+    digits.len() + 1 >= s.len() && !digits.is_empty() &&
+    digits.bytes().all(|b| b.is_ascii_digit()) && s.parse::<i64>().is_err()
+}
+
+/// Whether a real is too big for a float type whose largest value is `max`.
+/// Only a real written with digits counts, so `inf` is still infinite.
+fn float_out_of_range(s: &str, max: f64) -> bool {
+    s.bytes().any(|b| b.is_ascii_digit()) &&
+    s.parse::<f64>().map_or(false, |f| f.abs() > max)
+}
+
+/// The YAML 1.1 booleans that YAML 1.2 reads as strings.
+pub fn lenient_bool(s: &str) -> Option<bool> {
+    match s {
+        "y" | "Y" | "True" | "TRUE" | "yes" | "Yes" | "YES" | "on" | "On" |
+        "ON" => Some(true),
+        "n" | "N" | "False" | "FALSE" | "no" | "No" | "NO" | "off" | "Off" |
+        "OFF" => Some(false),
+        _ => None,
+    }
+}
+
+/// What happens when a key appears more than once in the same mapping.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DuplicateKey {
+    /// Fail to load the document. This is the default, since a repeated key
+    /// is more often a mistake than intended.
+    Error,
+    /// Keep the value of the first occurrence and ignore the rest.
+    FirstWins,
+    /// Keep the value of the last occurrence, in the position of the first.
+    LastWins,
+}
+
+/// The rules that give a type to a plain scalar, one written without quotes
+/// or a tag. A quoted scalar is always a string.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScalarResolution {
+    /// YAML 1.1, where `yes`, `no`, `on` and `off` are booleans, `1:02` is
+    /// the integer 62 in base 60, `017` is octal, `0b101` is binary and
+    /// numbers may have `_` between their digits.
+    Yaml11,
+    /// The core schema of YAML 1.2, where those are all strings except for
+    /// `017`, which is the integer 17. This is the default.
+    Yaml12,
+}
+
+/// Options that control how a YAML stream is loaded and deserialized.
+#[derive(Copy, Clone, Debug)]
+struct Config {
+    forward_aliases: bool,
+    duplicate_key: DuplicateKey,
+    lenient_bools: bool,
+    coerce_scalars: bool,
+    hex_bytes: bool,
+    case_insensitive_names: bool,
+    /// The fields of the struct or the variants of the enum being
+    /// deserialized, which a key or variant written in another case may
+    /// name.
+    names: &'static [&'static str],
+    /// How many more levels of collections may be nested below the current
+    /// node.
+    remaining_depth: usize,
+    alias_limit: usize,
+    max_scalar_bytes: Option<usize>,
+    max_total_nodes: Option<usize>,
+    max_collection_len: Option<usize>,
+    scalar_resolution: ScalarResolution,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            forward_aliases: false,
+            duplicate_key: DuplicateKey::Error,
+            lenient_bools: false,
+            coerce_scalars: false,
+            hex_bytes: false,
+            case_insensitive_names: false,
+            names: &[],
+            remaining_depth: 128,
+            alias_limit: 100_000,
+            max_scalar_bytes: None,
+            max_total_nodes: None,
+            max_collection_len: None,
+            scalar_resolution: ScalarResolution::Yaml12,
+        }
     }
 }
 
-/// Decodes a YAML value from a `&str`.
+impl Config {
+    /// The config for the content of a field or variant, which has no names
+    /// of its own until it is known to be a struct or an enum.
+    fn without_names(mut self) -> Config {
+        self.names = &[];
+        self
+    }
+
+    fn loader_options(&self) -> loader::Options {
+        loader::Options {
+            forward_aliases: self.forward_aliases,
+            duplicate_key: self.duplicate_key,
+            max_depth: self.remaining_depth,
+            alias_limit: self.alias_limit,
+            max_scalar_bytes: self.max_scalar_bytes,
+            max_total_nodes: self.max_total_nodes,
+            max_collection_len: self.max_collection_len,
+            scalar_resolution: self.scalar_resolution,
+            only_document: None,
+        }
+    }
+}
+
+/// Builds a deserializer with non-default options.
+///
+/// ```rust
+/// use std::collections::BTreeMap;
+/// use serde_yaml::DeserializerBuilder;
+/// let builder = DeserializerBuilder::new().forward_aliases(true);
+/// let map: BTreeMap<String, i32> =
+///     builder.from_str("a: *n\nb: &n 1").unwrap();
+/// assert_eq!(map["a"], 1);
+/// ```
+#[derive(Clone, Default)]
+pub struct DeserializerBuilder {
+    config: Config,
+    tag_resolver: Option<Arc<TagResolver>>,
+}
+
+type TagResolver = Fn(&str, Value) -> Result<Value> + Send + Sync;
+
+impl fmt::Debug for DeserializerBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DeserializerBuilder")
+            .field("config", &self.config)
+            .field("tag_resolver", &self.tag_resolver.is_some())
+            .finish()
+    }
+}
+
+impl DeserializerBuilder {
+    pub fn new() -> Self {
+        DeserializerBuilder::default()
+    }
+
+    /// Allows an alias to appear before the anchor it refers to, as long as
+    /// the anchor is in the same document. Strict YAML forbids this, so by
+    /// default such an alias is an error.
+    pub fn forward_aliases(mut self, enabled: bool) -> Self {
+        self.config.forward_aliases = enabled;
+        self
+    }
+
+    /// Controls what happens when a key appears more than once in the same
+    /// mapping. By default the document fails to load.
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    /// use serde_yaml::{DeserializerBuilder, DuplicateKey};
+    /// let yaml = "a: 1\na: 2";
+    /// assert!(serde_yaml::from_str::<BTreeMap<String, i32>>(yaml).is_err());
+    /// let builder = DeserializerBuilder::new()
+    ///     .on_duplicate_key(DuplicateKey::FirstWins);
+    /// let map: BTreeMap<String, i32> = builder.from_str(yaml).unwrap();
+    /// assert_eq!(map["a"], 1);
+    /// ```
+    pub fn on_duplicate_key(mut self, policy: DuplicateKey) -> Self {
+        self.config.duplicate_key = policy;
+        self
+    }
+
+    /// Accepts the YAML 1.1 booleans `yes`, `no`, `on` and `off`, in any of
+    /// their capitalizations, and the single letters `y` and `n`, when
+    /// deserializing a `bool`. By default only `true` and `false` are
+    /// booleans.
+    pub fn lenient_bools(mut self, enabled: bool) -> Self {
+        self.config.lenient_bools = enabled;
+        self
+    }
+
+    /// Sets the rules that give a type to a plain scalar, which matter most
+    /// when deserializing a `Value`. YAML 1.2 is the default.
+    ///
+    /// ```rust
+    /// use serde_yaml::{DeserializerBuilder, ScalarResolution, Value};
+    /// let builder = DeserializerBuilder::new()
+    ///     .scalar_resolution(ScalarResolution::Yaml11);
+    /// let value: Value = builder.from_str("- yes\n- '1:02'\n- 1:02").unwrap();
+    /// assert_eq!(value.get(0).and_then(Value::as_bool), Some(true));
+    /// assert_eq!(value.get(1).and_then(Value::as_str), Some("1:02"));
+    /// assert_eq!(value.get(2).and_then(Value::as_i64), Some(62));
+    /// ```
+    pub fn scalar_resolution(mut self, resolution: ScalarResolution) -> Self {
+        self.config.scalar_resolution = resolution;
+        self
+    }
+
+    /// Accepts a null, a boolean or a number where a string is expected, so
+    /// that `version: 1.0` can be read into a `String`. By default this is
+    /// an error.
+    ///
+    /// The string is the scalar's value in a standard form rather than its
+    /// text: a float as written, an integer in decimal, so `0x1F` gives
+    /// `31`, a boolean as `true` or `false`, and a null, whether written as
+    /// `~`, `null` or nothing at all, as `~`.
+    pub fn coerce_scalars(mut self, enabled: bool) -> Self {
+        self.config.coerce_scalars = enabled;
+        self
+    }
+
+    /// Reads a string into bytes as two hexadecimal digits per byte, as
+    /// `BytesEncoding::Hex` writes them, rather than as its UTF-8 encoding.
+    pub fn hex_bytes(mut self, enabled: bool) -> Self {
+        self.config.hex_bytes = enabled;
+        self
+    }
+
+    /// Matches keys to struct fields, and names to enum variants, regardless
+    /// of ASCII case when nothing matches exactly, so that `Enabled: true`
+    /// sets a field named `enabled`. Names are matched as the struct or enum
+    /// lists them, which for a derived implementation is as they are
+    /// written in Rust, before any `#[serde(rename)]`.
+    pub fn case_insensitive_names(mut self, enabled: bool) -> Self {
+        self.config.case_insensitive_names = enabled;
+        self
+    }
+
+    /// Sets how deeply sequences, mappings and enums may be nested before
+    /// deserialization fails, which keeps untrusted input from overflowing the
+    /// stack. The default is 128.
+    pub fn recursion_limit(mut self, limit: usize) -> Self {
+        self.config.remaining_depth = limit;
+        self
+    }
+
+    /// Sets how many nodes aliases may copy in total across a document, so
+    /// that a small document with aliases of aliases cannot expand into an
+    /// enormous one. The default is 100,000.
+    pub fn alias_limit(mut self, limit: usize) -> Self {
+        self.config.alias_limit = limit;
+        self
+    }
+
+    /// Sets how many bytes a single scalar may hold, keys included. There is
+    /// no limit by default.
+    pub fn max_scalar_bytes(mut self, limit: usize) -> Self {
+        self.config.max_scalar_bytes = Some(limit);
+        self
+    }
+
+    /// Sets how many nodes a document may hold in total, counting every node
+    /// that an alias copies. There is no limit by default.
+    pub fn max_total_nodes(mut self, limit: usize) -> Self {
+        self.config.max_total_nodes = Some(limit);
+        self
+    }
+
+    /// Sets how many elements a sequence, or entries a mapping, may hold.
+    /// There is no limit by default.
+    pub fn max_collection_len(mut self, limit: usize) -> Self {
+        self.config.max_collection_len = Some(limit);
+        self
+    }
+
+    /// Calls `resolver` with the tag and the value of each node that has an
+    /// application-specific tag, like `!env HOME`, and deserializes what it
+    /// returns in place of the node. The tag is given without its leading
+    /// `!`, and the nodes inside a tagged node are resolved before it. A
+    /// resolver that does not know a tag may return the value unchanged.
+    ///
+    /// ```rust
+    /// use serde_yaml::{DeserializerBuilder, Value};
+    /// let builder = DeserializerBuilder::new().tag_resolver(|tag, value| {
+    ///     match (tag, value) {
+    ///         ("double", Value::Number(n)) => {
+    ///             Ok(Value::Number((n.as_f64().unwrap() * 2.0).into()))
+    ///         }
+    ///         (_, value) => Ok(value),
+    ///     }
+    /// });
+    /// let n: f64 = builder.from_str("!double 1.5").unwrap();
+    /// assert_eq!(n, 3.0);
+    /// ```
+    pub fn tag_resolver<F>(mut self, resolver: F) -> Self
+        where F: Fn(&str, Value) -> Result<Value> + Send + Sync + 'static,
+    {
+        self.tag_resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Applies the tag resolver, if there is one, to a loaded document.
+    fn resolve_tags(&self, doc: Yaml) -> Result<Yaml> {
+        match self.tag_resolver {
+            Some(ref resolver) => tagged::resolve(doc, &**resolver),
+            None => Ok(doc),
+        }
+    }
+
+    /// Deserializes a single document. Input with no document at all, such
+    /// as an empty string or only comments, reads the same as `~`, so it
+    /// gives `None`, `()`, an empty `Vec` or an empty map. A type that cannot
+    /// be read from null, such as a number or a `String`, fails with an end
+    /// of stream error.
+    pub fn from_str<T>(&self, s: &str) -> Result<T>
+        where T: Deserialize,
+    {
+        let mut docs = try!(loader::load(s, self.config.loader_options()));
+        match docs.len() {
+            0 => {
+                let mut de = Deserializer::with_config(&Yaml::Null,
+                                                       self.config);
+                Deserialize::deserialize(&mut de)
+                    .map_err(|_| Error::EndOfStream)
+            }
+            1 => {
+                let doc = try!(self.resolve_tags(docs.pop().unwrap()));
+                let mut de = Deserializer::with_config(&doc, self.config);
+                Deserialize::deserialize(&mut de)
+            }
+            n => Err(Error::TooManyDocuments(n)),
+        }
+    }
+
+    /// Deserializes a single document as `from_str` does, and also finds the
+    /// style of each scalar in it that is quoted or a block scalar. Passing
+    /// the styles to `SerializerBuilder::scalar_styles` writes the strings at
+    /// the same paths in the same styles, so that a document read into a
+    /// `Value` and changed is written back looking the way it did.
+    ///
+    /// ```rust
+    /// use serde_yaml::{DeserializerBuilder, SerializerBuilder, Value};
+    /// let yaml = "---\nname: 'app'\nscript: |-\n  make\n  make install";
+    /// let (value, styles): (Value, _) =
+    ///     DeserializerBuilder::new().from_str_with_styles(yaml).unwrap();
+    /// let builder = SerializerBuilder::new().scalar_styles(styles);
+    /// assert_eq!(builder.to_string(&value).unwrap(), yaml);
+    /// ```
+    pub fn from_str_with_styles<T>(&self,
+                                   s: &str)
+                                   -> Result<(T, ScalarStyles)>
+        where T: Deserialize,
+    {
+        let value = try!(self.from_str(s));
+        let styles = try!(style::collect(self.events(s)));
+        Ok((value, styles))
+    }
+
+    /// Parses a stream into events without loading it, for tools such as
+    /// linters that care about how the YAML is written.
+    pub fn events(&self, s: &str) -> Events {
+        event::parse(s, self.config.remaining_depth)
+    }
+
+    /// Loads a single document into a yaml-rust `Yaml` tree without
+    /// deserializing it. Tags other than those of the core schema are dropped,
+    /// as yaml-rust has no way to represent them.
+    pub fn parse<S>(&self, s: S) -> Result<Yaml>
+        where S: AsRef<str>,
+    {
+        let mut docs = try!(loader::load(s.as_ref(),
+                                         self.config.loader_options()));
+        match docs.len() {
+            0 => Err(Error::EndOfStream),
+            1 => {
+                let doc = try!(self.resolve_tags(docs.pop().unwrap()));
+                Ok(tagged::strip(doc))
+            }
+            n => Err(Error::TooManyDocuments(n)),
+        }
+    }
+
+    pub fn from_yaml<T>(&self, yaml: &Yaml) -> Result<T>
+        where T: Deserialize,
+    {
+        let mut de = Deserializer::with_config(yaml, self.config);
+        Deserialize::deserialize(&mut de)
+    }
+
+    /// Deserializes each document of a multi-document stream separately, so
+    /// that a malformed document does not prevent reading the others.
+    pub fn from_str_multi<T>(&self, s: &str) -> Vec<Result<T>>
+        where T: Deserialize,
+    {
+        loader::load_multi(s, self.config.loader_options())
+            .into_iter()
+            .map(|doc| {
+                doc.and_then(|doc| {
+                    let doc = try!(self.resolve_tags(doc));
+                    let mut de = Deserializer::with_config(&doc, self.config);
+                    Deserialize::deserialize(&mut de)
+                })
+            })
+            .collect()
+    }
+
+    /// Deserializes only the document at index `n`, counting from 0, of a
+    /// multi-document stream. The other documents are parsed but not kept.
+    pub fn from_str_nth<T>(&self, s: &str, n: usize) -> Result<T>
+        where T: Deserialize,
+    {
+        let doc = try!(loader::load_nth(s, n, self.config.loader_options()));
+        let doc = try!(self.resolve_tags(doc));
+        let mut de = Deserializer::with_config(&doc, self.config);
+        Deserialize::deserialize(&mut de)
+    }
+
+    /// Loads a single document and passes it to `validate` as a `Value`
+    /// before deserializing it, so that a document can be checked and
+    /// rejected with a clearer error than `T` would give. An error from
+    /// `validate` is returned as it is.
+    pub fn validated_from_str<T, F>(&self, s: &str, validate: F) -> Result<T>
+        where T: Deserialize,
+              F: Fn(&Value) -> Result<()>,
+    {
+        let mut docs = try!(loader::load(s, self.config.loader_options()));
+        let doc = match docs.len() {
+            0 => Yaml::Null,
+            1 => try!(self.resolve_tags(docs.pop().unwrap())),
+            n => return Err(Error::TooManyDocuments(n)),
+        };
+        try!(validate(&doc.clone().into()));
+        let mut de = Deserializer::with_config(&doc, self.config);
+        Deserialize::deserialize(&mut de)
+    }
+
+    /// Loads a single document and passes it to `check` as a `Value`, which
+    /// pushes every problem it finds onto the given `Vec`, before
+    /// deserializing it. All the problems are returned together, so that
+    /// they can be fixed at once. A document that fails to load, or that
+    /// passes `check` but not deserialization, gives a single error.
+    pub fn collect_errors_from_str<T, F>(&self,
+                                         s: &str,
+                                         check: F)
+                                         -> result::Result<T, Vec<Error>>
+        where T: Deserialize,
+              F: Fn(&Value, &mut Vec<Error>),
+    {
+        let mut docs = try!(loader::load(s, self.config.loader_options())
+            .map_err(|err| vec![err]));
+        let doc = match docs.len() {
+            0 => Yaml::Null,
+            1 => try!(self.resolve_tags(docs.pop().unwrap())
+                .map_err(|err| vec![err])),
+            n => return Err(vec![Error::TooManyDocuments(n)]),
+        };
+        let mut errors = Vec::new();
+        check(&doc.clone().into(), &mut errors);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let mut de = Deserializer::with_config(&doc, self.config);
+        Deserialize::deserialize(&mut de).map_err(|err| vec![err])
+    }
+
+    pub fn from_iter<I, T>(&self, iter: I) -> Result<T>
+        where I: Iterator<Item = io::Result<u8>>,
+              T: Deserialize,
+    {
+        let bytes: Vec<u8> = try!(iter.collect());
+        self.from_str(try!(str::from_utf8(&bytes)))
+    }
+
+    pub fn from_reader<R, T>(&self, rdr: R) -> Result<T>
+        where R: io::Read,
+              T: Deserialize,
+    {
+        self.from_iter(rdr.bytes())
+    }
+
+    /// Reads and deserializes the file at `path`, which may be in UTF-8 or
+    /// UTF-16 as for `from_slice`. An error opening, reading or parsing the
+    /// file is reported as `Error::File`, which names the file.
+    pub fn from_path<P, T>(&self, path: P) -> Result<T>
+        where P: AsRef<Path>,
+              T: Deserialize,
+    {
+        let path = path.as_ref();
+        let mut bytes = Vec::new();
+        let result = File::open(path)
+            .and_then(|mut file| file.read_to_end(&mut bytes))
+            .map_err(Error::Io)
+            .and_then(|_| self.from_slice(&bytes));
+        result.map_err(|err| Error::File(path.to_owned(), Box::new(err)))
+    }
+
+    /// Deserializes bytes in UTF-8, or in UTF-16 if they start with a UTF-16
+    /// byte order mark.
+    pub fn from_slice<T>(&self, v: &[u8]) -> Result<T>
+        where T: Deserialize,
+    {
+        if v.starts_with(b"\xFF\xFE") {
+            self.from_str(&try!(decode_utf16(&v[2..], false)))
+        } else if v.starts_with(b"\xFE\xFF") {
+            self.from_str(&try!(decode_utf16(&v[2..], true)))
+        } else {
+            self.from_str(try!(str::from_utf8(v)))
+        }
+    }
+}
+
+fn decode_utf16(bytes: &[u8], big_endian: bool) -> Result<String> {
+    let units: Vec<u16> = bytes.chunks(2)
+        .map(|pair| {
+            if pair.len() < 2 {
+                // An odd byte at the end is not a code unit. A lone low
+                // surrogate makes the decoder report it as invalid.
+                0xDC00
+            } else if big_endian {
+                ((pair[0] as u16) << 8) | pair[1] as u16
+            } else {
+                ((pair[1] as u16) << 8) | pair[0] as u16
+            }
+        })
+        .collect();
+    Ok(try!(String::from_utf16(&units)))
+}
+
+/// Decodes a YAML value from a `&str`. Empty input is read as null.
+///
+/// ```rust
+/// let none: Option<i32> = serde_yaml::from_str("").unwrap();
+/// assert_eq!(none, None);
+/// let empty: Vec<i32> = serde_yaml::from_str("# nothing here\n").unwrap();
+/// assert!(empty.is_empty());
+/// assert!(serde_yaml::from_str::<i32>("").is_err());
+/// ```
 pub fn from_str<T>(s: &str) -> Result<T>
     where T: Deserialize,
 {
-    let docs = try!(YamlLoader::load_from_str(s));
-    match docs.len() {
-        0 => Err(Error::EndOfStream),
-        1 => {
-            let doc = &docs[0];
-            Deserialize::deserialize(&mut Deserializer::new(doc))
-        }
-        n => Err(Error::TooManyDocuments(n)),
-    }
+    DeserializerBuilder::new().from_str(s)
+}
+
+/// Deserializes a single document along with the styles of its scalars.
+pub fn from_str_with_styles<T>(s: &str) -> Result<(T, ScalarStyles)>
+    where T: Deserialize,
+{
+    DeserializerBuilder::new().from_str_with_styles(s)
+}
+
+/// Deserializes each document of a multi-document stream separately. A
+/// document that fails to parse or does not match `T` gives an error in its
+/// own position without affecting the rest.
+///
+/// ```rust
+/// let docs = serde_yaml::from_str_multi::<i32>("--- 1\n--- [\n--- 3");
+/// assert_eq!(docs.len(), 3);
+/// assert_eq!(docs[0].as_ref().unwrap(), &1);
+/// assert!(docs[1].is_err());
+/// assert_eq!(docs[2].as_ref().unwrap(), &3);
+/// ```
+pub fn from_str_multi<T>(s: &str) -> Vec<Result<T>>
+    where T: Deserialize,
+{
+    DeserializerBuilder::new().from_str_multi(s)
+}
+
+/// Deserializes the document at index `n`, counting from 0, of a
+/// multi-document stream. Fails if the stream has no such document.
+///
+/// ```rust
+/// let yaml = "--- 1\n--- 2\n--- 3";
+/// assert_eq!(serde_yaml::from_str_nth::<i32>(yaml, 1).unwrap(), 2);
+/// assert!(serde_yaml::from_str_nth::<i32>(yaml, 3).is_err());
+/// ```
+pub fn from_str_nth<T>(s: &str, n: usize) -> Result<T>
+    where T: Deserialize,
+{
+    DeserializerBuilder::new().from_str_nth(s, n)
+}
+
+/// Deserializes a single document after `validate` has accepted it as a
+/// `Value`.
+///
+/// ```rust
+/// use serde_yaml::{Error, Value};
+/// let positive = |value: &Value| match value.as_i64() {
+///     Some(n) if n > 0 => Ok(()),
+///     _ => Err(Error::Custom(String::from("expected a positive number"))),
+/// };
+/// let n: u8 = serde_yaml::validated_from_str("7", &positive).unwrap();
+/// assert_eq!(n, 7);
+/// assert!(serde_yaml::validated_from_str::<i8, _>("-7", &positive).is_err());
+/// ```
+pub fn validated_from_str<T, F>(s: &str, validate: F) -> Result<T>
+    where T: Deserialize,
+          F: Fn(&Value) -> Result<()>,
+{
+    DeserializerBuilder::new().validated_from_str(s, validate)
+}
+
+/// Deserializes a single document after `check` has found no problems in it
+/// as a `Value`, or returns every problem that `check` found.
+///
+/// ```rust
+/// use serde_yaml::{Error, Value};
+/// let check = |value: &Value, errors: &mut Vec<Error>| {
+///     for n in value.as_sequence().unwrap() {
+///         if n.as_i64().map_or(true, |n| n < 0) {
+///             let msg = format!("{:?} is not a natural number", n);
+///             errors.push(Error::Custom(msg));
+///         }
+///     }
+/// };
+/// let v: Vec<u8> = serde_yaml::collect_errors_from_str("[1, 2]", &check)
+///     .unwrap();
+/// assert_eq!(v, [1, 2]);
+/// let errors = serde_yaml::collect_errors_from_str::<Vec<u8>, _>(
+///     "[-1, 2, x]", &check).unwrap_err();
+/// assert_eq!(errors.len(), 2);
+/// ```
+pub fn collect_errors_from_str<T, F>(s: &str,
+                                     check: F)
+                                     -> result::Result<T, Vec<Error>>
+    where T: Deserialize,
+          F: Fn(&Value, &mut Vec<Error>),
+{
+    DeserializerBuilder::new().collect_errors_from_str(s, check)
+}
+
+/// Loads a YAML document into a yaml-rust `Yaml` tree, for inspecting it
+/// before deserializing with `from_yaml`.
+///
+/// ```rust
+/// # extern crate serde_yaml;
+/// # extern crate yaml_rust;
+/// # fn main() {
+/// use yaml_rust::Yaml;
+/// let yaml = serde_yaml::parse("[1, two]").unwrap();
+/// assert_eq!(yaml[1], Yaml::String("two".to_owned()));
+/// let v: (i32, String) = serde_yaml::from_yaml(&yaml).unwrap();
+/// assert_eq!(v, (1, "two".to_owned()));
+/// # }
+/// ```
+pub fn parse<S>(s: S) -> Result<Yaml>
+    where S: AsRef<str>,
+{
+    DeserializerBuilder::new().parse(s)
+}
+
+/// Parses a stream into events without loading it.
+///
+/// ```rust
+/// use serde_yaml::{Event, ScalarStyle};
+/// let events: Vec<Event> = serde_yaml::events("key: 'value'")
+///     .map(|event| event.unwrap().0)
+///     .collect();
+/// assert_eq!(events[3], Event::Scalar {
+///     value: "key".to_owned(),
+///     style: ScalarStyle::Plain,
+///     anchor: None,
+///     tag: None,
+/// });
+/// assert_eq!(events.len(), 8);
+/// ```
+pub fn events(s: &str) -> Events {
+    DeserializerBuilder::new().events(s)
+}
+
+/// Deserializes a yaml-rust `Yaml` tree, such as one from `parse` or from
+/// `Serializer::take`.
+pub fn from_yaml<T>(yaml: &Yaml) -> Result<T>
+    where T: Deserialize,
+{
+    DeserializerBuilder::new().from_yaml(yaml)
 }
 
 pub fn from_iter<I, T>(iter: I) -> Result<T>
     where I: Iterator<Item = io::Result<u8>>,
           T: Deserialize,
 {
-    let bytes: Vec<u8> = try!(iter.collect());
-    from_str(try!(str::from_utf8(&bytes)))
+    DeserializerBuilder::new().from_iter(iter)
 }
 
 pub fn from_reader<R, T>(rdr: R) -> Result<T>
     where R: io::Read,
           T: Deserialize,
 {
-    from_iter(rdr.bytes())
+    DeserializerBuilder::new().from_reader(rdr)
+}
+
+/// Reads and deserializes the YAML file at `path`.
+///
+/// ```rust,no_run
+/// use std::collections::BTreeMap;
+/// let config: BTreeMap<String, String> =
+///     serde_yaml::from_path("config.yaml").unwrap();
+/// ```
+pub fn from_path<P, T>(path: P) -> Result<T>
+    where P: AsRef<Path>,
+          T: Deserialize,
+{
+    DeserializerBuilder::new().from_path(path)
 }
 
+/// Decodes a YAML value from UTF-8 bytes, or from UTF-16 bytes that start
+/// with a byte order mark.
 pub fn from_slice<T>(v: &[u8]) -> Result<T>
     where T: Deserialize,
 {
-    from_iter(v.iter().map(|byte| Ok(*byte)))
+    DeserializerBuilder::new().from_slice(v)
 }
@@ -0,0 +1,140 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Durations as `std::time::Duration` written like `1h30m`
+//!
+//! YAML has no type for durations. Use these functions on a struct field to
+//! read and write them as a scalar of whole amounts with units:
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Timeouts {
+//!     #[serde(serialize_with = "serde_yaml::duration::serialize",
+//!             deserialize_with = "serde_yaml::duration::deserialize")]
+//!     connect: Duration,
+//! }
+//! ```
+//!
+//! The units are `h`, `m`, `s`, `ms`, `us` and `ns`. Several amounts may be
+//! combined, largest unit first and each unit at most once, as in `1h30m` or
+//! `2s500ms`.
+//!
+//! Serialization writes each unit whose amount is not zero, or `0s` for a
+//! zero duration.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use serde::{de, ser};
+
+/// The units in the order they must appear, with the nanoseconds in each.
+static UNITS: &[(&str, u64)] = &[
+    ("h", 3_600_000_000_000),
+    ("m", 60_000_000_000),
+    ("s", 1_000_000_000),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("ns", 1),
+];
+
+pub fn serialize<S>(
+    duration: &Duration,
+    serializer: &mut S
+) -> Result<(), S::Error>
+    where S: ser::Serializer,
+{
+    let secs = duration.as_secs();
+    let nanos = duration.subsec_nanos() as u64;
+    let amounts = [
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        nanos / 1_000_000,
+        nanos / 1_000 % 1_000,
+        nanos % 1_000,
+    ];
+    let mut repr = String::new();
+    for (amount, &(unit, _)) in amounts.iter().zip(UNITS) {
+        if *amount > 0 {
+            write!(repr, "{}{}", amount, unit).unwrap();
+        }
+    }
+    if repr.is_empty() {
+        repr.push_str("0s");
+    }
+    serializer.serialize_str(&repr)
+}
+
+pub fn deserialize<D>(deserializer: &mut D) -> Result<Duration, D::Error>
+    where D: de::Deserializer,
+{
+    struct DurationVisitor;
+
+    impl de::Visitor for DurationVisitor {
+        type Value = Duration;
+
+        fn visit_str<E>(&mut self, v: &str) -> Result<Duration, E>
+            where E: de::Error,
+        {
+            match parse(v) {
+                Some(duration) => Ok(duration),
+                None => {
+                    Err(E::invalid_value(&format!("expected a duration like \
+                                                   `1h30m`, found `{}`",
+                                                  v)))
+                }
+            }
+        }
+    }
+
+    deserializer.deserialize_str(DurationVisitor)
+}
+
+/// Parses amounts with units, largest unit first.
+fn parse(v: &str) -> Option<Duration> {
+    if v.is_empty() {
+        return None;
+    }
+    let mut rest = v;
+    let mut units = UNITS.iter();
+    let mut total = Duration::new(0, 0);
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit())
+            .unwrap_or_else(|| rest.len());
+        let amount: u64 = match rest[..digits].parse() {
+            Ok(amount) => amount,
+            Err(_) => return None,
+        };
+        rest = &rest[digits..];
+        let letters = rest.find(|c: char| c.is_ascii_digit())
+            .unwrap_or_else(|| rest.len());
+        // Skipping past the unit leaves only the smaller ones for later.
+        let nanos = match units.find(|&&(unit, _)| unit == &rest[..letters]) {
+            Some(&(_, nanos)) => nanos,
+            None => return None,
+        };
+        rest = &rest[letters..];
+
+        let part = if nanos >= 1_000_000_000 {
+            let secs = nanos / 1_000_000_000;
+            match amount.checked_mul(secs) {
+                Some(secs) => Duration::from_secs(secs),
+                None => return None,
+            }
+        } else {
+            let per_sec = 1_000_000_000 / nanos;
+            let subsec = (amount % per_sec) * nanos;
+            Duration::new(amount / per_sec, subsec as u32)
+        };
+        total = match total.checked_add(part) {
+            Some(total) => total,
+            None => return None,
+        };
+    }
+    Some(total)
+}
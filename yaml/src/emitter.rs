@@ -0,0 +1,1134 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! YAML Emission
+//!
+//! The layout matches yaml-rust's `YamlEmitter`, but the decision of which
+//! scalars need quoting is made here so that every string reads back as a
+//! string.
+
+use std::borrow::Cow;
+use std::fmt::{self, Write};
+
+use yaml_rust::{EmitError, Yaml};
+
+use super::event::ScalarStyle;
+use super::loader;
+use super::ser::{LineEnding, NullStyle, QuoteStyle};
+use super::style::ScalarStyles;
+use super::tagged;
+
+pub type EmitResult = Result<(), EmitError>;
+
+/// A function that rewrites each string key of a mapping as it is written.
+pub type KeyTransform = Fn(&str) -> String + Send + Sync;
+
+/// Options that control the layout of the output.
+#[derive(Copy, Clone, Debug)]
+pub struct Style {
+    pub line_ending: LineEnding,
+    pub null_style: NullStyle,
+    pub quote_style: QuoteStyle,
+    /// Spaces per level of nesting.
+    pub indent: usize,
+    /// Whether a sequence that is a mapping value is indented under the key.
+    pub indent_sequences: bool,
+    /// Whether every string is written with a `!!str` tag.
+    pub tag_strings: bool,
+    /// Whether a string that would read as another type without its quotes
+    /// is written with a `!!str` tag.
+    pub tag_ambiguous_strings: bool,
+    /// Whether the document starts with `---`.
+    pub explicit_start: bool,
+    /// Whether the document ends with `...`.
+    pub explicit_end: bool,
+    /// Whether every node is written in flow style with an explicit tag.
+    pub canonical: bool,
+    /// The column past which a long string is folded onto more lines.
+    pub line_width: Option<usize>,
+    /// Whether the output is JSON, which is also YAML.
+    pub json: bool,
+    /// The length past which a scalar key is written as an explicit key.
+    pub explicit_key_length: Option<usize>,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            line_ending: LineEnding::Lf,
+            null_style: NullStyle::Tilde,
+            quote_style: QuoteStyle::Auto,
+            indent: 2,
+            indent_sequences: true,
+            tag_strings: false,
+            tag_ambiguous_strings: false,
+            explicit_start: true,
+            explicit_end: false,
+            canonical: false,
+            line_width: None,
+            json: false,
+            explicit_key_length: None,
+        }
+    }
+}
+
+pub struct Emitter<'a, W: 'a> {
+    writer: ColumnWriter<'a, W>,
+    level: isize,
+    /// Whether the node is inside a flow collection.
+    flow: bool,
+    line_break: &'static str,
+    style: Style,
+    /// The handles and prefixes of the `%TAG` directives to write, in order.
+    tag_directives: &'a [(String, String)],
+    key_transform: Option<&'a KeyTransform>,
+    /// The comments to write above keys, each with the path to its key.
+    comments: &'a [(Vec<String>, String)],
+    /// The styles to write strings in, by the path to each.
+    scalar_styles: Option<&'a ScalarStyles>,
+    /// The keys and sequence indices leading to the node being written,
+    /// tracked only when there are comments to place or styles to apply.
+    path: Vec<String>,
+}
+
+impl<'a, W> Emitter<'a, W>
+    where W: fmt::Write,
+{
+    pub fn new(writer: &'a mut W, style: Style) -> Self {
+        Emitter {
+            writer: ColumnWriter {
+                writer: writer,
+                column: 0,
+                owes_break: false,
+            },
+            level: -1,
+            flow: false,
+            line_break: match style.line_ending {
+                LineEnding::Lf => "\n",
+                LineEnding::CrLf => "\r\n",
+            },
+            style: style,
+            tag_directives: &[],
+            key_transform: None,
+            comments: &[],
+            scalar_styles: None,
+            path: Vec::new(),
+        }
+    }
+
+    /// Declares `%TAG` directives at the start of each document, and writes
+    /// tags with the handles they declare wherever a prefix matches.
+    pub fn with_tag_directives(mut self,
+                               directives: &'a [(String, String)])
+                               -> Self {
+        self.tag_directives = directives;
+        self
+    }
+
+    /// Rewrites each string key with `transform` before it is written. Keys
+    /// are quoted as the rewritten strings need.
+    pub fn with_key_transform(mut self,
+                              transform: Option<&'a KeyTransform>)
+                              -> Self {
+        self.key_transform = transform;
+        self
+    }
+
+    /// Writes each comment on the lines above the key at the end of its
+    /// path, at the indentation of the key. Keys in flow collections, and
+    /// all keys in JSON or canonical output, get no comments.
+    pub fn with_comments(mut self,
+                         comments: &'a [(Vec<String>, String)])
+                         -> Self {
+        self.comments = comments;
+        self
+    }
+
+    /// Writes each string in block context that has a style in `styles` in
+    /// that style, if the style can hold it.
+    pub fn with_scalar_styles(mut self,
+                              styles: Option<&'a ScalarStyles>)
+                              -> Self {
+        self.scalar_styles = styles;
+        self
+    }
+
+    pub fn dump(&mut self, doc: &Yaml) -> EmitResult {
+        try!(self.start_document());
+        self.level = -1;
+        if self.style.json {
+            try!(self.emit_json(doc));
+        } else if self.style.canonical {
+            try!(self.emit_canonical(doc, 0));
+        } else {
+            try!(self.emit_value(doc));
+        }
+        self.end_document()
+    }
+
+    /// Starts a document holding a sequence whose elements are passed to
+    /// `emit_seq_elt` one at a time, so that the whole sequence never has to
+    /// be built.
+    pub fn begin_seq(&mut self) -> EmitResult {
+        try!(self.start_document());
+        self.level = 0;
+        if self.style.json {
+            try!(self.writer.write_str("["));
+        } else if self.style.canonical {
+            try!(self.writer.write_str("!!seq ["));
+        }
+        Ok(())
+    }
+
+    pub fn emit_seq_elt(&mut self, index: usize, elem: &Yaml) -> EmitResult {
+        if self.style.json {
+            if index > 0 {
+                try!(self.writer.write_str(", "));
+            }
+            return self.emit_json(elem);
+        }
+        if self.style.canonical {
+            try!(self.writer.write_str(self.line_break));
+            try!(self.write_canonical_indent(1));
+            try!(self.emit_canonical(elem, 1));
+            try!(self.writer.write_str(","));
+            return Ok(());
+        }
+        if index > 0 {
+            try!(self.writer.write_str(self.line_break));
+        }
+        self.emit_item(index, elem)
+    }
+
+    pub fn end_seq(&mut self, len: usize) -> EmitResult {
+        self.level = -1;
+        if self.style.json {
+            try!(self.writer.write_str("]"));
+        } else if self.style.canonical {
+            if len > 0 {
+                try!(self.writer.write_str(self.line_break));
+            }
+            try!(self.writer.write_str("]"));
+        } else if len == 0 {
+            try!(self.writer.write_str("[]"));
+        }
+        self.end_document()
+    }
+
+    fn start_document(&mut self) -> EmitResult {
+        if self.style.json {
+            return Ok(());
+        }
+        for &(ref handle, ref prefix) in self.tag_directives {
+            try!(write!(self.writer, "%TAG {} {}", handle, prefix));
+            try!(self.writer.write_str(self.line_break));
+        }
+        // Directives must be followed by an explicit start.
+        if self.style.explicit_start || !self.tag_directives.is_empty() {
+            try!(self.writer.write_str("---"));
+            try!(self.writer.write_str(self.line_break));
+        }
+        Ok(())
+    }
+
+    fn end_document(&mut self) -> EmitResult {
+        if self.style.explicit_end && !self.style.json {
+            try!(self.writer.write_str(self.line_break));
+            try!(self.writer.write_str("..."));
+        } else if self.writer.owes_break {
+            try!(self.writer.write_str(self.line_break));
+        }
+        Ok(())
+    }
+
+    fn write_indent(&mut self) -> EmitResult {
+        if self.level <= 0 {
+            return Ok(());
+        }
+        for _ in 0..self.level {
+            for _ in 0..self.style.indent {
+                try!(self.writer.write_str(" "));
+            }
+        }
+        Ok(())
+    }
+
+    fn emit_node(&mut self, node: &Yaml) -> EmitResult {
+        if let Some((tag, inner)) = tagged::split(node) {
+            return self.emit_tagged(tag, inner);
+        }
+        match *node {
+            Yaml::Array(ref v) => self.emit_array(v),
+            Yaml::Hash(ref h) => self.emit_hash(h),
+            Yaml::String(ref v) => {
+                let quote = need_quotes(v) || SHORT_BOOLS.contains(&v.as_str());
+                self.emit_str(v, quote)
+            }
+            Yaml::Boolean(v) => {
+                try!(self.writer.write_str(if v { "true" } else { "false" }));
+                Ok(())
+            }
+            Yaml::Integer(v) => {
+                try!(write!(self.writer, "{}", v));
+                Ok(())
+            }
+            Yaml::Real(ref v) => {
+                try!(self.writer.write_str(v));
+                Ok(())
+            }
+            Yaml::Null | Yaml::BadValue => {
+                let null = match self.style.null_style {
+                    NullStyle::Null => "null",
+                    NullStyle::Tilde | NullStyle::Empty => "~",
+                };
+                try!(self.writer.write_str(null));
+                Ok(())
+            }
+            Yaml::Alias(_) => Ok(()),
+        }
+    }
+
+    /// Emits a sequence item or map value after its `-` or `:` indicator,
+    /// leaving out the space between them if the value is an empty null.
+    fn emit_entry(&mut self, indicator: &str, node: &Yaml) -> EmitResult {
+        try!(self.writer.write_str(indicator));
+        if !self.is_empty_null(node) {
+            try!(self.writer.write_str(" "));
+        }
+        self.emit_value(node)
+    }
+
+    /// Emits a node in block context, where a long string may be folded
+    /// and a null may be left empty.
+    fn emit_value(&mut self, node: &Yaml) -> EmitResult {
+        if self.is_empty_null(node) {
+            return Ok(());
+        }
+        if let Yaml::String(ref v) = *node {
+            let style = self.scalar_styles
+                .and_then(|styles| styles.get(&self.path));
+            if let Some(style) = style {
+                return self.emit_styled(node, v, style);
+            }
+            if let Some(lines) = self.fold(v) {
+                return self.emit_folded(&lines);
+            }
+        }
+        self.emit_node(node)
+    }
+
+    /// Emits the string `v` of `node` in `style`, or as usual if the style
+    /// cannot hold it.
+    fn emit_styled(&mut self,
+                   node: &Yaml,
+                   v: &str,
+                   style: ScalarStyle)
+                   -> EmitResult {
+        let indicator = match style {
+            ScalarStyle::Plain => return self.emit_node(node),
+            ScalarStyle::SingleQuoted => {
+                if v.contains(|c: char| c < ' ' || c == '\x7f') {
+                    return self.emit_node(node);
+                }
+                try!(self.write_str_tag());
+                try!(write!(self.writer, "'{}'", v.replace('\'', "''")));
+                return Ok(());
+            }
+            ScalarStyle::DoubleQuoted => {
+                try!(self.write_str_tag());
+                try!(escape_str(&mut self.writer, v));
+                return Ok(());
+            }
+            ScalarStyle::Literal => "|",
+            ScalarStyle::Folded => ">",
+        };
+        let folded = style == ScalarStyle::Folded;
+        let body = v.trim_right_matches('\n');
+        if !block_safe(body, folded) {
+            return self.emit_node(node);
+        }
+        try!(self.write_str_tag());
+        // The chomping indicator keeps as many line breaks at the end as the
+        // string has.
+        let trailing = v.len() - body.len();
+        let chomping = match trailing {
+            0 => "-",
+            1 => "",
+            _ => "+",
+        };
+        try!(write!(self.writer, "{}{}", indicator, chomping));
+        let indent = self.block_indent();
+        for (i, line) in body.split('\n').enumerate() {
+            // Folding reads a single line break between two lines as a
+            // space, so each line break in the string takes an empty line.
+            if folded && i > 0 && !line.is_empty() {
+                try!(self.writer.write_str(self.line_break));
+            }
+            try!(self.writer.write_str(self.line_break));
+            if !line.is_empty() {
+                for _ in 0..indent {
+                    try!(self.writer.write_str(" "));
+                }
+                try!(self.writer.write_str(line));
+            }
+        }
+        for _ in 1..trailing {
+            try!(self.writer.write_str(self.line_break));
+        }
+        // The last line break is written by whatever comes next.
+        self.writer.owes_break = trailing > 0;
+        Ok(())
+    }
+
+    fn write_str_tag(&mut self) -> EmitResult {
+        if self.style.tag_strings {
+            try!(self.writer.write_str("!!str "));
+        }
+        Ok(())
+    }
+
+    fn is_empty_null(&self, node: &Yaml) -> bool {
+        match *node {
+            Yaml::Null => self.style.null_style == NullStyle::Empty,
+            _ => false,
+        }
+    }
+
+    /// Splits a string that would reach past the line width into lines for a
+    /// folded scalar, or returns `None` to leave it on one line. Only a single
+    /// space between two other characters is replaced by a line break, since
+    /// that is what folding turns back into a space.
+    fn fold<'s>(&self, v: &'s str) -> Option<Vec<&'s str>> {
+        let width = match self.style.line_width {
+            Some(width) if !self.style.canonical => width,
+            _ => return None,
+        };
+        match self.style.quote_style {
+            QuoteStyle::Single | QuoteStyle::Double => return None,
+            QuoteStyle::Auto | QuoteStyle::Plain => {}
+        }
+        // Count the quotes that the string may otherwise need.
+        if self.writer.column + v.chars().count() + 2 <= width {
+            return None;
+        }
+        let foldable = !v.starts_with(' ') && !v.ends_with(' ') &&
+                       !v.contains(|c: char| {
+            c < ' ' || c == '\x7f' || c == '\u{85}' || c == '\u{2028}' ||
+            c == '\u{2029}' || c == '\u{feff}'
+        });
+        if !foldable {
+            return None;
+        }
+        let lines = wrap(v, width.saturating_sub(self.block_indent()));
+        if lines.len() > 1 {
+            Some(lines)
+        } else {
+            None
+        }
+    }
+
+    fn emit_folded(&mut self, lines: &[&str]) -> EmitResult {
+        if self.style.tag_strings {
+            try!(self.writer.write_str("!!str "));
+        }
+        try!(self.writer.write_str(">-"));
+        let indent = self.block_indent();
+        for line in lines {
+            try!(self.writer.write_str(self.line_break));
+            for _ in 0..indent {
+                try!(self.writer.write_str(" "));
+            }
+            try!(self.writer.write_str(line));
+        }
+        Ok(())
+    }
+
+    /// The indentation of the lines of a block scalar, one level deeper than
+    /// the collection holding it.
+    fn block_indent(&self) -> usize {
+        let level = if self.level < 0 { 0 } else { self.level as usize };
+        (level + 1) * self.style.indent
+    }
+
+    fn emit_str(&mut self, v: &str, quote: bool) -> EmitResult {
+        let ambiguous = quote &&
+                        (resolves_to_non_string(v) ||
+                         SHORT_BOOLS.contains(&v));
+        if self.style.tag_strings ||
+           self.style.tag_ambiguous_strings && ambiguous {
+            try!(self.writer.write_str("!!str "));
+        }
+        let plain = match self.style.quote_style {
+            QuoteStyle::Auto => !quote,
+            QuoteStyle::Plain => !quote || plain_safe(v, self.flow),
+            QuoteStyle::Single | QuoteStyle::Double => false,
+        };
+        if plain {
+            try!(self.writer.write_str(v));
+        } else if self.style.quote_style == QuoteStyle::Single &&
+                  !v.contains(|c: char| c < ' ' || c == '\x7f') {
+            try!(write!(self.writer, "'{}'", v.replace('\'', "''")));
+        } else {
+            try!(escape_str(&mut self.writer, v));
+        }
+        Ok(())
+    }
+
+    /// Writes the tag ahead of the node. A block collection goes on the
+    /// lines that follow, which the collection starts by itself except at the
+    /// top level.
+    fn emit_tagged(&mut self, tag: &str, node: &Yaml) -> EmitResult {
+        try!(self.write_tag(tag));
+        let block = match *node {
+            Yaml::Array(ref v) => !v.is_empty(),
+            Yaml::Hash(ref h) => !h.is_empty(),
+            _ => false,
+        };
+        if !block {
+            try!(self.writer.write_str(" "));
+        } else if self.level < 0 {
+            try!(self.writer.write_str(self.line_break));
+        }
+        self.emit_node(node)
+    }
+
+    /// Writes a tag as it is kept in a tagged node, in the shorthand of the
+    /// first `%TAG` directive whose prefix it starts with. The tags of the
+    /// core schema keep their `!!` handle.
+    fn write_tag(&mut self, tag: &str) -> EmitResult {
+        if !tag.starts_with('!') {
+            for &(ref handle, ref prefix) in self.tag_directives {
+                if let Some(suffix) = tag_suffix(tag, prefix) {
+                    try!(write!(self.writer, "{}{}", handle, suffix));
+                    return Ok(());
+                }
+            }
+        }
+        try!(write!(self.writer, "!{}", tag));
+        Ok(())
+    }
+
+    fn emit_array(&mut self, v: &[Yaml]) -> EmitResult {
+        if v.is_empty() {
+            try!(self.writer.write_str("[]"));
+            return Ok(());
+        }
+        if self.level >= 0 {
+            try!(self.writer.write_str(self.line_break));
+        }
+        self.level += 1;
+        try!(self.emit_items(v));
+        self.level -= 1;
+        Ok(())
+    }
+
+    /// Emits the items of a block sequence at the current level.
+    fn emit_items(&mut self, v: &[Yaml]) -> EmitResult {
+        for (cnt, x) in v.iter().enumerate() {
+            if cnt > 0 {
+                try!(self.writer.write_str(self.line_break));
+            }
+            try!(self.write_indent());
+            try!(self.emit_item(cnt, x));
+        }
+        Ok(())
+    }
+
+    fn emit_item(&mut self, index: usize, x: &Yaml) -> EmitResult {
+        if !self.tracks_path() {
+            return self.emit_entry("-", x);
+        }
+        self.path.push(index.to_string());
+        try!(self.emit_entry("-", x));
+        self.path.pop();
+        Ok(())
+    }
+
+    fn emit_hash(&mut self, h: &::yaml_rust::yaml::Hash) -> EmitResult {
+        if h.is_empty() {
+            try!(self.writer.write_str("{}"));
+            return Ok(());
+        }
+        if self.level >= 0 {
+            try!(self.writer.write_str(self.line_break));
+        }
+        self.level += 1;
+        for (cnt, (k, v)) in h.iter().enumerate() {
+            if cnt > 0 {
+                try!(self.writer.write_str(self.line_break));
+            }
+            let tracked = match self.path_segment(k) {
+                Some(segment) => {
+                    try!(self.write_comment(&segment));
+                    self.path.push(segment);
+                    true
+                }
+                None => false,
+            };
+            try!(self.write_indent());
+            let k = &*self.transform_key(k);
+            if is_collection(k) || self.is_long_key(k) {
+                // An explicit key, unlike an implicit one, may be longer than
+                // 1024 characters.
+                try!(self.writer.write_str("? "));
+                try!(self.emit_key(k));
+                try!(self.writer.write_str(self.line_break));
+                try!(self.write_indent());
+            } else {
+                try!(self.emit_key(k));
+            }
+            match *v {
+                // The items may line up with the key, which is still
+                // unambiguous since each starts with `-`.
+                Yaml::Array(ref a) if !a.is_empty() &&
+                                      !self.style.indent_sequences => {
+                    try!(self.writer.write_str(": "));
+                    try!(self.writer.write_str(self.line_break));
+                    try!(self.emit_items(a));
+                }
+                _ => try!(self.emit_entry(":", v)),
+            }
+            if tracked {
+                self.path.pop();
+            }
+        }
+        self.level -= 1;
+        Ok(())
+    }
+
+    /// Whether the path to each node is needed, to place comments or apply
+    /// styles.
+    fn tracks_path(&self) -> bool {
+        !self.comments.is_empty() || self.scalar_styles.is_some()
+    }
+
+    /// The key as a step of the path to its value, or `None` if the path is
+    /// not tracked.
+    fn path_segment(&self, k: &Yaml) -> Option<String> {
+        if !self.tracks_path() {
+            return None;
+        }
+        let k = tagged::split(k).map_or(k, |(_, inner)| inner);
+        Some(match *k {
+            Yaml::String(ref v) | Yaml::Real(ref v) => v.clone(),
+            Yaml::Integer(v) => v.to_string(),
+            Yaml::Boolean(v) => v.to_string(),
+            _ => String::new(),
+        })
+    }
+
+    /// Writes the comment for the key `segment` of the mapping at the
+    /// current path, if there is one, a line at a time.
+    fn write_comment(&mut self, segment: &str) -> EmitResult {
+        let comments = self.comments;
+        let comment = comments.iter().find(|&&(ref path, _)| {
+            path.len() == self.path.len() + 1 &&
+            path[..self.path.len()] == self.path[..] &&
+            path[self.path.len()] == segment
+        });
+        if let Some(&(_, ref comment)) = comment {
+            for line in comment.lines() {
+                try!(self.write_indent());
+                try!(self.writer.write_str("#"));
+                if !line.is_empty() {
+                    try!(write!(self.writer, " {}", line));
+                }
+                try!(self.writer.write_str(self.line_break));
+            }
+        }
+        Ok(())
+    }
+
+    /// The key as it is written, after `key_transform` if it is a string.
+    fn transform_key<'k>(&self, k: &'k Yaml) -> Cow<'k, Yaml> {
+        match (self.key_transform, k) {
+            (Some(transform), &Yaml::String(ref s)) => {
+                Cow::Owned(Yaml::String(transform(s)))
+            }
+            _ => Cow::Borrowed(k),
+        }
+    }
+
+    /// Whether a scalar key is longer than `explicit_key_length`, or too
+    /// long for an implicit key once quoted.
+    fn is_long_key(&self, k: &Yaml) -> bool {
+        let k = tagged::split(k).map_or(k, |(_, inner)| inner);
+        let len = match *k {
+            Yaml::String(ref v) | Yaml::Real(ref v) => v.chars().count(),
+            _ => return false,
+        };
+        len + 2 > IMPLICIT_KEY_LIMIT ||
+        self.style.explicit_key_length.map_or(false, |limit| len > limit)
+    }
+
+    fn emit_key(&mut self, k: &Yaml) -> EmitResult {
+        if let Some((tag, inner)) = tagged::split(k) {
+            try!(self.write_tag(tag));
+            try!(self.writer.write_str(" "));
+            return self.emit_key(inner);
+        }
+        match *k {
+            Yaml::Array(_) | Yaml::Hash(_) => self.emit_node_compact(k),
+            // Keys like `x` and `y` are too common to quote just because a
+            // YAML 1.1 parser would read a lone `y` value as a boolean.
+            Yaml::String(ref v) if !need_quotes(v) => self.emit_str(v, false),
+            _ => self.emit_node(k),
+        }
+    }
+
+    /// Emits a node in canonical form: in flow style, with the tag of every
+    /// node written out and every scalar quoted.
+    fn emit_canonical(&mut self, node: &Yaml, depth: usize) -> EmitResult {
+        let node = match tagged::split(node) {
+            Some((tag, inner)) => {
+                try!(self.write_tag(tag));
+                try!(self.writer.write_str(" "));
+                inner
+            }
+            None => {
+                try!(self.writer.write_str(core_tag(node)));
+                try!(self.writer.write_str(" "));
+                node
+            }
+        };
+        match *node {
+            Yaml::Array(ref v) => {
+                try!(self.writer.write_str("["));
+                for x in v {
+                    try!(self.writer.write_str(self.line_break));
+                    try!(self.write_canonical_indent(depth + 1));
+                    try!(self.emit_canonical(x, depth + 1));
+                    try!(self.writer.write_str(","));
+                }
+                if !v.is_empty() {
+                    try!(self.writer.write_str(self.line_break));
+                    try!(self.write_canonical_indent(depth));
+                }
+                try!(self.writer.write_str("]"));
+            }
+            Yaml::Hash(ref h) => {
+                try!(self.writer.write_str("{"));
+                for (k, v) in h {
+                    try!(self.writer.write_str(self.line_break));
+                    try!(self.write_canonical_indent(depth + 1));
+                    try!(self.writer.write_str("? "));
+                    let k = &*self.transform_key(k);
+                    try!(self.emit_canonical(k, depth + 1));
+                    try!(self.writer.write_str(self.line_break));
+                    try!(self.write_canonical_indent(depth + 1));
+                    try!(self.writer.write_str(": "));
+                    try!(self.emit_canonical(v, depth + 1));
+                    try!(self.writer.write_str(","));
+                }
+                if !h.is_empty() {
+                    try!(self.writer.write_str(self.line_break));
+                    try!(self.write_canonical_indent(depth));
+                }
+                try!(self.writer.write_str("}"));
+            }
+            Yaml::Real(ref v) => {
+                // A `!!int` is read back in decimal only.
+                match loader::resolve_real(v) {
+                    Yaml::Integer(i) => {
+                        try!(write!(self.writer, "\"{}\"", i));
+                    }
+                    _ => try!(escape_str(&mut self.writer, v)),
+                }
+            }
+            Yaml::String(ref v) => try!(escape_str(&mut self.writer, v)),
+            Yaml::Integer(v) => try!(write!(self.writer, "\"{}\"", v)),
+            Yaml::Boolean(v) => try!(write!(self.writer, "\"{}\"", v)),
+            Yaml::Null | Yaml::BadValue | Yaml::Alias(_) => {
+                try!(self.writer.write_str("\"\""));
+            }
+        }
+        Ok(())
+    }
+
+    /// Emits a node as JSON: in flow style on one line, with every string
+    /// double-quoted and without tags. Scalar keys are written as strings,
+    /// floats that JSON cannot represent, like `.inf`, as `null`, and other
+    /// scalars written verbatim, like `0o644`, as strings.
+    fn emit_json(&mut self, node: &Yaml) -> EmitResult {
+        let node = tagged::split(node).map_or(node, |(_, inner)| inner);
+        match *node {
+            Yaml::Array(ref v) => {
+                try!(self.writer.write_str("["));
+                for (cnt, x) in v.iter().enumerate() {
+                    if cnt > 0 {
+                        try!(self.writer.write_str(", "));
+                    }
+                    try!(self.emit_json(x));
+                }
+                try!(self.writer.write_str("]"));
+            }
+            Yaml::Hash(ref h) => {
+                try!(self.writer.write_str("{"));
+                for (cnt, (k, v)) in h.iter().enumerate() {
+                    if cnt > 0 {
+                        try!(self.writer.write_str(", "));
+                    }
+                    try!(self.emit_json_key(&self.transform_key(k)));
+                    try!(self.writer.write_str(": "));
+                    try!(self.emit_json(v));
+                }
+                try!(self.writer.write_str("}"));
+            }
+            Yaml::String(ref v) => try!(escape_str(&mut self.writer, v)),
+            Yaml::Real(ref v) => {
+                match loader::resolve_real(v) {
+                    Yaml::Real(_) => {
+                        if v.parse().map_or(false, f64::is_finite) {
+                            try!(self.writer.write_str(v));
+                        } else {
+                            try!(self.writer.write_str("null"));
+                        }
+                    }
+                    // A scalar written verbatim, like `0o644` or a
+                    // timestamp, which JSON has no plain form for.
+                    _ => try!(escape_str(&mut self.writer, v)),
+                }
+            }
+            Yaml::Integer(v) => try!(write!(self.writer, "{}", v)),
+            Yaml::Boolean(v) => try!(write!(self.writer, "{}", v)),
+            Yaml::Null | Yaml::BadValue | Yaml::Alias(_) => {
+                try!(self.writer.write_str("null"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a key as a JSON string. A collection cannot be a JSON key.
+    fn emit_json_key(&mut self, k: &Yaml) -> EmitResult {
+        let k = tagged::split(k).map_or(k, |(_, inner)| inner);
+        match *k {
+            Yaml::String(ref v) | Yaml::Real(ref v) => {
+                try!(escape_str(&mut self.writer, v));
+            }
+            Yaml::Integer(v) => try!(write!(self.writer, "\"{}\"", v)),
+            Yaml::Boolean(v) => try!(write!(self.writer, "\"{}\"", v)),
+            Yaml::Null | Yaml::BadValue | Yaml::Alias(_) => {
+                try!(self.writer.write_str("\"null\""));
+            }
+            Yaml::Array(_) | Yaml::Hash(_) => {
+                return Err(EmitError::BadHashmapKey);
+            }
+        }
+        Ok(())
+    }
+
+    fn write_canonical_indent(&mut self, depth: usize) -> EmitResult {
+        for _ in 0..depth * self.style.indent {
+            try!(self.writer.write_str(" "));
+        }
+        Ok(())
+    }
+
+    /// Emits a collection in flow style, as required for a complex key.
+    fn emit_node_compact(&mut self, node: &Yaml) -> EmitResult {
+        let flow = self.flow;
+        self.flow = true;
+        let result = self.emit_flow(node);
+        self.flow = flow;
+        result
+    }
+
+    fn emit_flow(&mut self, node: &Yaml) -> EmitResult {
+        if let Some((tag, inner)) = tagged::split(node) {
+            try!(self.write_tag(tag));
+            try!(self.writer.write_str(" "));
+            return self.emit_flow(inner);
+        }
+        match *node {
+            Yaml::Array(ref v) => {
+                try!(self.writer.write_str("["));
+                for (cnt, x) in v.iter().enumerate() {
+                    if cnt > 0 {
+                        try!(self.writer.write_str(", "));
+                    }
+                    try!(self.emit_flow(x));
+                }
+                try!(self.writer.write_str("]"));
+                Ok(())
+            }
+            Yaml::Hash(ref h) => {
+                try!(self.writer.write_str("{"));
+                for (cnt, (k, v)) in h.iter().enumerate() {
+                    if cnt > 0 {
+                        try!(self.writer.write_str(", "));
+                    }
+                    try!(self.emit_key(&self.transform_key(k)));
+                    try!(self.writer.write_str(": "));
+                    try!(self.emit_flow(v));
+                }
+                try!(self.writer.write_str("}"));
+                Ok(())
+            }
+            _ => self.emit_node(node),
+        }
+    }
+}
+
+/// Passes output on to the writer, keeping track of the column at which the
+/// next character will be written.
+struct ColumnWriter<'a, W: 'a> {
+    writer: &'a mut W,
+    column: usize,
+    /// Whether the last thing written is a block scalar that ends in a line
+    /// break that has not been written yet.
+    owes_break: bool,
+}
+
+impl<'a, W> fmt::Write for ColumnWriter<'a, W>
+    where W: fmt::Write,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        try!(self.writer.write_str(s));
+        self.owes_break = false;
+        match s.rfind('\n') {
+            Some(i) => self.column = s[i + 1..].chars().count(),
+            None => self.column += s.chars().count(),
+        }
+        Ok(())
+    }
+}
+
+/// Whether `body`, a string without its line breaks at the end, can be
+/// written as a block scalar, folded or literal. Its first line has to start
+/// with a character other than a space so that the indentation can be told,
+/// and every character other than a line break or tab has to be printable.
+/// Folding would join a line that starts with a space to the lines around it
+/// differently, so a folded scalar has none.
+fn block_safe(body: &str, folded: bool) -> bool {
+    body.starts_with(|c: char| c != ' ' && c != '\t' && c != '\n') &&
+    !body.contains(|c: char| {
+        c < ' ' && c != '\n' && c != '\t' || c == '\x7f' || c == '\u{85}' ||
+        c == '\u{2028}' || c == '\u{2029}' || c == '\u{feff}'
+    }) &&
+    !(folded &&
+      body.split('\n').any(|line| line.starts_with(|c| c == ' ' || c == '\t')))
+}
+
+/// Breaks a string at single spaces into lines of at most `width`
+/// characters where possible. A word longer than `width` gets a line of its
+/// own.
+fn wrap(v: &str, width: usize) -> Vec<&str> {
+    let bytes = v.as_bytes();
+    let breaks = v.char_indices().filter(|&(i, ch)| {
+        ch == ' ' && i > 0 && bytes[i - 1] != b' ' &&
+        bytes.get(i + 1) != Some(&b' ')
+    });
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut last = None;
+    for (i, _) in breaks {
+        if let Some(end) = last {
+            if v[start..i].chars().count() > width {
+                lines.push(&v[start..end]);
+                start = end + 1;
+            }
+        }
+        last = Some(i);
+    }
+    if let Some(end) = last {
+        if end > start && v[start..].chars().count() > width {
+            lines.push(&v[start..end]);
+            start = end + 1;
+        }
+    }
+    lines.push(&v[start..]);
+    lines
+}
+
+/// Whether `node` is a sequence or a mapping, tagged or not.
+fn is_collection(node: &Yaml) -> bool {
+    let node = tagged::split(node).map_or(node, |(_, inner)| inner);
+    match *node {
+        Yaml::Array(_) | Yaml::Hash(_) => true,
+        _ => false,
+    }
+}
+
+/// The rest of an application tag after `prefix`, if it has such a prefix
+/// and the rest can be written after a handle. The tag is kept as `<tag>` if
+/// it is global, and without its leading `!` if it is local.
+fn tag_suffix<'t>(tag: &'t str, prefix: &str) -> Option<&'t str> {
+    let (tag, prefix) = if tag.starts_with('<') && tag.ends_with('>') {
+        (&tag[1..tag.len() - 1], prefix)
+    } else if prefix.starts_with('!') {
+        (tag, &prefix[1..])
+    } else {
+        return None;
+    };
+    if !tag.starts_with(prefix) {
+        return None;
+    }
+    let suffix = &tag[prefix.len()..];
+    let shorthand = suffix.chars().all(|c| {
+        c.is_ascii_alphanumeric() || "-#;/?:@&=+$_.~*'()%".contains(c)
+    });
+    if !suffix.is_empty() && shorthand {
+        Some(suffix)
+    } else {
+        None
+    }
+}
+
+/// The tag of the core schema for the kind of `node`. A real that is not a
+/// float is a scalar written verbatim, which is either an integer like
+/// `0o644` or a timestamp.
+fn core_tag(node: &Yaml) -> &'static str {
+    match *node {
+        Yaml::Array(_) => "!!seq",
+        Yaml::Hash(_) => "!!map",
+        Yaml::String(_) => "!!str",
+        Yaml::Integer(_) => "!!int",
+        Yaml::Real(ref v) => {
+            match loader::resolve_real(v) {
+                Yaml::Real(_) => "!!float",
+                Yaml::Integer(_) => "!!int",
+                _ => "!!timestamp",
+            }
+        }
+        Yaml::Boolean(_) => "!!bool",
+        Yaml::Null | Yaml::BadValue | Yaml::Alias(_) => "!!null",
+    }
+}
+
+/// Whether a string must be quoted to be read back as the same string.
+///
+/// This covers strings containing indicator or control characters, strings
+/// with leading or trailing spaces, strings starting with U+FEFF, which a
+/// parser takes as a byte order mark at the start of a document, and strings
+/// that a YAML 1.1 or 1.2 parser would resolve to a null, a boolean or a
+/// number.
+fn need_quotes(string: &str) -> bool {
+    fn need_quotes_spaces(string: &str) -> bool {
+        string.starts_with(' ') || string.ends_with(' ')
+    }
+
+    string.is_empty() || need_quotes_spaces(string) ||
+    string.starts_with('"') || string.starts_with('\'') ||
+    string.contains(|character: char| {
+        match character {
+            ':' | '{' | '}' | '[' | ']' | ',' | '&' | '*' | '#' | '?' |
+            '|' | '-' | '<' | '>' | '=' | '!' | '%' | '@' | '`' | '\\' |
+            '\x7f' => true,
+            c => c < ' ',
+        }
+    }) || string.starts_with('+') || string.starts_with("...") ||
+    string.starts_with('\u{feff}') || resolves_to_non_string(string)
+}
+
+/// Whether a string can be written as a plain scalar and read back as the
+/// same string, inside a flow collection if `flow` is set. Unlike
+/// `need_quotes`, this allows indicators where they cannot start a token.
+fn plain_safe(string: &str, flow: bool) -> bool {
+    let first = match string.chars().next() {
+        Some(first) => first,
+        None => return false,
+    };
+    // Neither an indicator nor a space can start a plain scalar, and a `+`
+    // may be read as the sign of a number.
+    let indicator = match first {
+        '-' | '?' | ':' | ',' | '[' | ']' | '{' | '}' | '#' | '&' | '*' |
+        '!' | '|' | '>' | '\'' | '"' | '%' | '@' | '`' | ' ' | '+' => true,
+        _ => false,
+    };
+    let special = |c: char| {
+        c < ' ' || c == '\x7f' || c == '\u{85}' || c == '\u{feff}' ||
+        c == '\u{2028}' || c == '\u{2029}' ||
+        flow && ",[]{}:?".contains(c)
+    };
+    !indicator && !string.ends_with(' ') && !string.ends_with(':') &&
+    !string.contains(": ") && !string.contains(" #") &&
+    !string.contains(special) && !string.starts_with("---") &&
+    !string.starts_with("...") && !resolves_to_non_string(string) &&
+    !SHORT_BOOLS.contains(&string)
+}
+
+/// Whether a plain scalar would resolve to a null, a boolean or a number
+/// under YAML 1.1 or 1.2.
+fn resolves_to_non_string(string: &str) -> bool {
+    KEYWORDS.contains(&string) || string.parse::<i64>().is_ok() ||
+    string.parse::<f64>().is_ok() ||
+    string.starts_with("0x") || string.starts_with("0o") ||
+    string.starts_with("0b") || is_number_with_underscores(string) ||
+    loader::is_base60(string)
+}
+
+/// Whether a string is a number once its underscores are taken out, as YAML
+/// 1.1 allows in numbers like `1_000`.
+fn is_number_with_underscores(string: &str) -> bool {
+    if !string.contains('_') {
+        return false;
+    }
+    let digits = string.replace('_', "");
+    !digits.is_empty() && digits.parse::<f64>().is_ok()
+}
+
+/// Plain scalars that resolve to something other than a string under either
+/// YAML 1.1 or YAML 1.2.
+static KEYWORDS: &[&str] = &[
+    "~", "null", "Null", "NULL",
+    "true", "True", "TRUE", "false", "False", "FALSE",
+    "yes", "Yes", "YES", "no", "No", "NO",
+    "on", "On", "ON", "off", "Off", "OFF",
+    ".inf", ".Inf", ".INF", ".nan", ".NaN", ".NAN",
+];
+
+/// The single-letter booleans of YAML 1.1, which are only quoted as values.
+static SHORT_BOOLS: &[&str] = &["y", "Y", "n", "N"];
+
+/// The most characters an implicit key may span, per the YAML spec.
+const IMPLICIT_KEY_LIMIT: usize = 1024;
+
+/// Writes a double-quoted scalar. Adapted from yaml-rust, which in turn took
+/// it from serialize::json.
+fn escape_str<W>(wr: &mut W, v: &str) -> Result<(), fmt::Error>
+    where W: fmt::Write,
+{
+    try!(wr.write_str("\""));
+
+    let mut start = 0;
+
+    for (i, byte) in v.bytes().enumerate() {
+        let escaped = match byte {
+            b'"' => "\\\"",
+            b'\\' => "\\\\",
+            b'\x08' => "\\b",
+            b'\t' => "\\t",
+            b'\n' => "\\n",
+            b'\x0c' => "\\f",
+            b'\r' => "\\r",
+            b if b < b' ' || b == b'\x7f' => {
+                if start < i {
+                    try!(wr.write_str(&v[start..i]));
+                }
+                try!(write!(wr, "\\u{:04x}", byte));
+                start = i + 1;
+                continue;
+            }
+            _ => continue,
+        };
+
+        if start < i {
+            try!(wr.write_str(&v[start..i]));
+        }
+
+        try!(wr.write_str(escaped));
+
+        start = i + 1;
+    }
+
+    if start != v.len() {
+        try!(wr.write_str(&v[start..]));
+    }
+
+    wr.write_str("\"")
+}
@@ -9,6 +9,7 @@
 use std::error;
 use std::fmt;
 use std::io;
+use std::path::PathBuf;
 use std::result;
 use std::str;
 use std::string;
@@ -23,17 +24,49 @@ use serde::{de, ser};
 pub enum Error {
     Custom(String),
     EndOfStream,
+    InvalidType(de::Type),
 
     Emit(emitter::EmitError),
     Scan(scanner::ScanError),
     Io(io::Error),
+    Write(io::Error, usize),
     Utf8(str::Utf8Error),
     FromUtf8(string::FromUtf8Error),
+    FromUtf16(string::FromUtf16Error),
+    /// An error reading or parsing the file at the path.
+    File(PathBuf, Box<Error>),
 
     AliasUnsupported,
+    RecursiveAlias,
+    AliasLimitExceeded,
+    RecursionLimitExceeded,
+    ScalarTooLong(usize),
+    TooManyNodes(usize),
+    CollectionTooLong(usize),
+    TabIndentation(usize),
+    /// A scalar that cannot be read as the type its core tag names, like
+    /// `!!int two`, with the tag and the line of the scalar.
+    InvalidTaggedScalar(String, usize),
+    UnsupportedVersion(u32, u32),
+    DuplicateKey(String),
+    MissingTag,
     TooManyDocuments(usize),
+    TrailingContent(usize),
+    NoDocument(usize),
     VariantMapWrongSize(String, usize),
+    SeqWrongSize(usize, usize),
     VariantNotAMapOrString(String),
+    ScalarNotACollection(&'static str, String),
+    IntegerOutOfRange(String, &'static str),
+    FloatOutOfRange(String, &'static str),
+    TypeMismatch(&'static str, &'static str),
+    InvalidBase64,
+    InvalidHex,
+    MergeNotAMapping,
+    MapValueWithoutKey,
+    MapKeyWithoutValue,
+    PathNotFound(String),
+    NotAScalar,
 }
 
 impl error::Error for Error {
@@ -41,29 +74,75 @@ impl error::Error for Error {
         match *self {
             Error::Custom(_) => "syntax error",
             Error::EndOfStream => "EOF while parsing a value",
+            Error::InvalidType(_) |
+            Error::TypeMismatch(..) => "invalid type",
             Error::Emit(_) => "emit error",
             Error::Scan(_) => "scan error",
-            Error::Io(ref err) => err.description(),
+            Error::Io(ref err) |
+            Error::Write(ref err, _) => err.description(),
+            Error::File(_, ref err) => err.description(),
             Error::Utf8(ref err) => err.description(),
             Error::FromUtf8(ref err) => err.description(),
+            Error::FromUtf16(ref err) => err.description(),
             Error::AliasUnsupported => "YAML aliases are not supported",
+            Error::RecursiveAlias => "YAML alias refers to its own anchor",
+            Error::AliasLimitExceeded => {
+                "YAML aliases expand to too many nodes"
+            }
+            Error::RecursionLimitExceeded => "recursion limit exceeded",
+            Error::ScalarTooLong(_) => "YAML scalar is too long",
+            Error::TooManyNodes(_) => "YAML document has too many nodes",
+            Error::CollectionTooLong(_) => "YAML collection is too long",
+            Error::TabIndentation(_) => "tabs are not allowed for indentation",
+            Error::InvalidTaggedScalar(..) => {
+                "YAML scalar does not match its tag"
+            }
+            Error::UnsupportedVersion(..) => "unsupported YAML version",
+            Error::DuplicateKey(_) => "duplicate key in YAML mapping",
+            Error::MissingTag => "expected a YAML value with a tag",
             Error::TooManyDocuments(_) => {
                 "expected a single YAML document but found multiple"
             }
+            Error::TrailingContent(_) => {
+                "unexpected content after the end of the YAML document"
+            }
+            Error::NoDocument(_) => "YAML stream has no document at the index",
             Error::VariantMapWrongSize(..) => {
                 "expected a YAML map of size 1 while parsing variant"
             }
             Error::VariantNotAMapOrString(_) => {
                 "expected a YAML map or string while parsing variant"
             }
+            Error::SeqWrongSize(..) => "YAML sequence has the wrong size",
+            Error::ScalarNotACollection(..) => {
+                "expected a YAML collection but found a scalar"
+            }
+            Error::IntegerOutOfRange(..) => {
+                "YAML integer is out of range for the target type"
+            }
+            Error::FloatOutOfRange(..) => {
+                "YAML float is out of range for the target type"
+            }
+            Error::InvalidBase64 => "YAML !!binary scalar is not valid base64",
+            Error::InvalidHex => "YAML string is not valid hexadecimal bytes",
+            Error::MergeNotAMapping => {
+                "expected a mapping or a sequence of mappings to merge"
+            }
+            Error::MapValueWithoutKey => "serialized a map value without a key",
+            Error::MapKeyWithoutValue => "serialized a map key without a value",
+            Error::PathNotFound(_) => "no scalar found at the path",
+            Error::NotAScalar => "expected a value that serializes as a scalar",
         }
     }
 
     fn cause(&self) -> Option<&error::Error> {
         match *self {
             Error::Scan(ref err) => Some(err),
-            Error::Io(ref err) => Some(err),
+            Error::Io(ref err) |
+            Error::Write(ref err, _) => Some(err),
+            Error::File(_, ref err) => Some(&**err),
             Error::FromUtf8(ref err) => Some(err),
+            Error::FromUtf16(ref err) => Some(err),
             _ => None,
         }
     }
@@ -74,16 +153,71 @@ impl fmt::Display for Error {
         match *self {
             Error::Custom(ref msg) => write!(f, "{}", msg),
             Error::EndOfStream => write!(f, "EOF while parsing a value"),
+            Error::InvalidType(ty) => {
+                write!(f, "Invalid type. Expected `{:?}`", ty)
+            }
             Error::Emit(ref err) => write!(f, "{:?}", err),
             Error::Scan(ref err) => err.fmt(f),
             Error::Io(ref err) => err.fmt(f),
+            Error::Write(ref err, written) => {
+                write!(f, "Failed to write YAML after {} bytes: {}", written,
+                       err)
+            }
             Error::Utf8(ref err) => err.fmt(f),
             Error::FromUtf8(ref err) => err.fmt(f),
+            Error::FromUtf16(ref err) => err.fmt(f),
+            Error::File(ref path, ref err) => {
+                write!(f, "Error in {}: {}", path.display(), err)
+            }
             Error::AliasUnsupported => {
                 write!(f, "YAML aliases are not supported")
             }
+            Error::RecursiveAlias => {
+                write!(f, "YAML alias refers to its own anchor")
+            }
+            Error::AliasLimitExceeded => {
+                write!(f, "YAML aliases expand to too many nodes")
+            }
+            Error::RecursionLimitExceeded => {
+                write!(f, "Recursion limit exceeded")
+            }
+            Error::ScalarTooLong(limit) => {
+                write!(f, "YAML scalar is longer than {} bytes", limit)
+            }
+            Error::TooManyNodes(limit) => {
+                write!(f, "YAML document has more than {} nodes", limit)
+            }
+            Error::CollectionTooLong(limit) => {
+                write!(f, "YAML collection has more than {} entries", limit)
+            }
+            Error::TabIndentation(line) => {
+                write!(f, "Tabs are not allowed for indentation at line {}",
+                       line)
+            }
+            Error::InvalidTaggedScalar(ref tag, line) => {
+                write!(f, "YAML scalar at line {} is not a valid {}", line, tag)
+            }
+            Error::UnsupportedVersion(major, minor) => {
+                write!(f, "YAML version {}.{} is not supported", major, minor)
+            }
+            Error::DuplicateKey(ref key) => {
+                write!(f, "Duplicate key `{}` in YAML mapping", key)
+            }
+            Error::MissingTag => write!(f, "Expected a YAML value with a tag"),
             Error::TooManyDocuments(n) => {
-                write!(f, "Expected a single YAML document but found {}", n)
+                write!(f,
+                       "Expected a single YAML document but found {}; use \
+                        from_str_multi to read a stream of documents",
+                       n)
+            }
+            Error::TrailingContent(line) => {
+                write!(f,
+                       "Unexpected content after the end of the YAML document \
+                        at line {}",
+                       line)
+            }
+            Error::NoDocument(n) => {
+                write!(f, "YAML stream has no document at index {}", n)
             }
             Error::VariantMapWrongSize(ref variant, size) => {
                 write!(f,
@@ -97,6 +231,49 @@ impl fmt::Display for Error {
                        "Expected a YAML map or string while parsing variant {}",
                        variant)
             }
+            Error::SeqWrongSize(expected, size) => {
+                write!(f,
+                       "Expected a YAML sequence of size {} but was size {}",
+                       expected,
+                       size)
+            }
+            Error::ScalarNotACollection(expected, ref scalar) => {
+                write!(f, "Expected a YAML {}, found scalar `{}`", expected,
+                       scalar)
+            }
+            Error::IntegerOutOfRange(ref i, ty) => {
+                write!(f, "YAML integer {} is out of range for {}", i, ty)
+            }
+            Error::FloatOutOfRange(ref float, ty) => {
+                write!(f, "YAML float {} is out of range for {}", float, ty)
+            }
+            Error::TypeMismatch(expected, found) => {
+                write!(f, "Invalid type. Expected {}, found {}", expected,
+                       found)
+            }
+            Error::InvalidBase64 => {
+                write!(f, "YAML !!binary scalar is not valid base64")
+            }
+            Error::InvalidHex => {
+                write!(f, "YAML string is not valid hexadecimal bytes")
+            }
+            Error::MergeNotAMapping => {
+                write!(f,
+                       "Expected a mapping or a sequence of mappings to merge \
+                        with `<<`")
+            }
+            Error::MapValueWithoutKey => {
+                write!(f, "Serialized a map value without a key")
+            }
+            Error::MapKeyWithoutValue => {
+                write!(f, "Serialized a map key without a value")
+            }
+            Error::PathNotFound(ref path) => {
+                write!(f, "No scalar found at `{}`", path)
+            }
+            Error::NotAScalar => {
+                write!(f, "Expected a value that serializes as a scalar")
+            }
         }
     }
 }
@@ -131,6 +308,12 @@ impl From<string::FromUtf8Error> for Error {
     }
 }
 
+impl From<string::FromUtf16Error> for Error {
+    fn from(err: string::FromUtf16Error) -> Error {
+        Error::FromUtf16(err)
+    }
+}
+
 impl ser::Error for Error {
     fn custom<T: Into<String>>(msg: T) -> Self {
         Error::Custom(msg.into())
@@ -145,6 +328,12 @@ impl de::Error for Error {
     fn end_of_stream() -> Self {
         Error::EndOfStream
     }
+
+    /// Kept apart from other errors so that the deserializer can replace it
+    /// with a `TypeMismatch` naming the node that was found.
+    fn invalid_type(ty: de::Type) -> Self {
+        Error::InvalidType(ty)
+    }
 }
 
 /// Helper alias for `Result` objects that return a YAML `Error`.
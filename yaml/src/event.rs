@@ -0,0 +1,221 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! YAML Parse Events
+//!
+//! The events of yaml-rust's parser, for tools that look at the structure of
+//! a stream without loading it. The parser only pushes events to a receiver,
+//! so they are collected before being handed out.
+
+use std::vec;
+
+use yaml_rust::parser::{self, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::{Marker, TScalarStyle, TokenType};
+
+use super::error::{Error, Result};
+use super::loader::{self, CollectionTag, TagDirectives};
+
+/// An event of the parser. Anchors are numbered in the order they appear,
+/// starting from 1, and an alias refers to an anchor by its number. A tag is
+/// written the way it appears in the source, like `!!str` or `!point`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    StreamStart,
+    StreamEnd,
+    DocumentStart,
+    DocumentEnd,
+    Alias(usize),
+    Scalar {
+        value: String,
+        style: ScalarStyle,
+        anchor: Option<usize>,
+        tag: Option<String>,
+    },
+    SequenceStart {
+        anchor: Option<usize>,
+        tag: Option<String>,
+    },
+    SequenceEnd,
+    MappingStart {
+        anchor: Option<usize>,
+        tag: Option<String>,
+    },
+    MappingEnd,
+}
+
+/// How a scalar is written in the source.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScalarStyle {
+    Plain,
+    SingleQuoted,
+    DoubleQuoted,
+    /// A block scalar introduced by `|`.
+    Literal,
+    /// A block scalar introduced by `>`.
+    Folded,
+}
+
+/// The position in the source at which an event was found.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Mark {
+    line: usize,
+    column: usize,
+}
+
+impl Mark {
+    /// The line, counting from 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The column, counting from 1.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+/// The events of a YAML stream, as returned by `events`. A syntax error is
+/// the last item, after the events that precede it.
+pub struct Events {
+    events: vec::IntoIter<(Event, Mark)>,
+    error: Option<Error>,
+}
+
+impl Iterator for Events {
+    type Item = Result<(Event, Mark)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.events.next() {
+            Some(event) => Some(Ok(event)),
+            None => self.error.take().map(Err),
+        }
+    }
+}
+
+/// Parses `source`, rejecting it up front if collections are nested more
+/// than `max_depth` deep.
+pub fn parse(source: &str, max_depth: usize) -> Events {
+    let source = loader::normalize_breaks(loader::strip_bom(source));
+    let source = &*source;
+    let (collection_tags, tag_directives) = match loader::prescan(source,
+                                                                  max_depth) {
+        Ok(found) => found,
+        Err(err) => {
+            return Events {
+                events: Vec::new().into_iter(),
+                error: Some(err),
+            };
+        }
+    };
+    let mut collector = Collector {
+        events: Vec::new(),
+        collection_tags: collection_tags,
+        tag_directives: tag_directives,
+    };
+    let error = Parser::new(source.chars())
+        .load(&mut collector, true)
+        .err()
+        .map(|err| loader::scan_error(source, err));
+    Events {
+        events: collector.events.into_iter(),
+        error: error,
+    }
+}
+
+struct Collector {
+    events: Vec<(Event, Mark)>,
+    collection_tags: Vec<CollectionTag>,
+    tag_directives: TagDirectives,
+}
+
+impl MarkedEventReceiver for Collector {
+    fn on_event(&mut self, ev: &parser::Event, mark: Marker) {
+        let event = match *ev {
+            parser::Event::Nothing => return,
+            parser::Event::StreamStart => Event::StreamStart,
+            parser::Event::StreamEnd => {
+                // The parser reports the end of the stream twice.
+                if let Some(&(Event::StreamEnd, _)) = self.events.last() {
+                    return;
+                }
+                Event::StreamEnd
+            }
+            parser::Event::DocumentStart => Event::DocumentStart,
+            parser::Event::DocumentEnd => Event::DocumentEnd,
+            parser::Event::Alias(id) => Event::Alias(id),
+            parser::Event::Scalar(ref v, style, aid, ref tag) => {
+                Event::Scalar {
+                    value: v.clone(),
+                    style: scalar_style(style),
+                    anchor: anchor(aid),
+                    tag: match *tag {
+                        Some(TokenType::Tag(ref handle, ref suffix)) => {
+                            let (handle, suffix) = self.tag_directives
+                                .expand(mark, handle, suffix);
+                            Some(tag_repr(&handle, &suffix))
+                        }
+                        _ => None,
+                    },
+                }
+            }
+            parser::Event::SequenceStart(aid) => {
+                Event::SequenceStart {
+                    anchor: anchor(aid),
+                    tag: self.collection_tag(mark),
+                }
+            }
+            parser::Event::SequenceEnd => Event::SequenceEnd,
+            parser::Event::MappingStart(aid) => {
+                Event::MappingStart {
+                    anchor: anchor(aid),
+                    tag: self.collection_tag(mark),
+                }
+            }
+            parser::Event::MappingEnd => Event::MappingEnd,
+        };
+        let (line, column) = loader::line_col(mark).unwrap_or((0, 0));
+        let mark = Mark {
+            line: line,
+            column: column + 1,
+        };
+        self.events.push((event, mark));
+    }
+}
+
+impl Collector {
+    fn collection_tag(&mut self, mark: Marker) -> Option<String> {
+        loader::take_collection_tag(&mut self.collection_tags, mark)
+            .map(|(handle, suffix)| tag_repr(&handle, &suffix))
+    }
+}
+
+/// The parser numbers anchors from 1 and uses 0 for a node without one.
+fn anchor(id: usize) -> Option<usize> {
+    if id == 0 { None } else { Some(id) }
+}
+
+fn scalar_style(style: TScalarStyle) -> ScalarStyle {
+    match style {
+        TScalarStyle::Any | TScalarStyle::Plain => ScalarStyle::Plain,
+        TScalarStyle::SingleQuoted => ScalarStyle::SingleQuoted,
+        TScalarStyle::DoubleQuoted => ScalarStyle::DoubleQuoted,
+        TScalarStyle::Literal => ScalarStyle::Literal,
+        TScalarStyle::Foled => ScalarStyle::Folded,
+    }
+}
+
+/// The scanner splits a tag into a handle and a suffix, and leaves the
+/// handle empty for a verbatim tag like `!<tag:example.com,2016:x>` or for
+/// the non-specific tag `!`.
+fn tag_repr(handle: &str, suffix: &str) -> String {
+    match handle {
+        "" if suffix == "!" => String::from("!"),
+        "" => format!("!<{}>", suffix),
+        _ => format!("{}{}", handle, suffix),
+    }
+}
@@ -10,18 +10,49 @@
 #![cfg_attr(feature="clippy", plugin(clippy))]
 #![cfg_attr(feature="clippy", deny(clippy))] // turn warnings into errors
 
-extern crate dtoa;
+#[cfg(feature = "chrono")]
+extern crate chrono;
 extern crate linked_hash_map;
 #[macro_use]
 extern crate serde;
 extern crate yaml_rust;
 
-pub use self::de::{Deserializer, from_iter, from_reader, from_slice, from_str};
-pub use self::ser::{Serializer, to_string, to_vec, to_writer};
-pub use self::value::{Mapping, Sequence, Value, from_value, to_value};
+pub use self::de::{Deserializer, DeserializerBuilder, DuplicateKey,
+                   ScalarResolution,
+                   collect_errors_from_str, events, from_iter, from_path,
+                   from_reader, from_slice, from_str, from_str_multi,
+                   from_str_nth, from_str_with_styles, from_yaml, parse,
+                   validated_from_str};
+pub use self::event::{Event, Events, Mark, ScalarStyle};
+pub use self::ser::{BytesEncoding, FloatFormat, LineEnding, MultiDocWriter,
+                    NullStyle, QuoteStyle, Serializer, SerializerBuilder,
+                    UnitVariant, WholeFloat, to_fmt_writer, to_string, to_vec,
+                    to_writer};
+pub use self::number::Number;
+pub use self::raw::RawDocument;
+pub use self::style::ScalarStyles;
+pub use self::tagged::TaggedValue;
+pub use self::value::{Entry, Index, Mapping, MappingExt, Sequence,
+                      SequenceMerge, Value, ValueDeserializer, from_value,
+                      to_value};
 pub use self::error::{Error, Result};
 
+mod base64;
 mod de;
+mod emitter;
+mod event;
+mod loader;
+mod number;
+mod raw;
 mod ser;
+mod style;
+mod tagged;
 mod value;
 mod error;
+
+pub mod duration;
+pub mod radix;
+pub mod set;
+pub mod singleton_map;
+#[cfg(feature = "chrono")]
+pub mod timestamp;
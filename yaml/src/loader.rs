@@ -0,0 +1,1095 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! YAML Loading
+//!
+//! Builds `Yaml` documents from parser events the same way yaml-rust's
+//! `YamlLoader` does, with the addition of an optional first pass that lets an
+//! alias refer to an anchor defined later in the same document, and with
+//! application-specific tags kept as tagged nodes.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::mem;
+
+use yaml_rust::Yaml;
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::{Marker, ScanError, Scanner, TScalarStyle, TokenType};
+use yaml_rust::yaml;
+
+use super::de::{self, DuplicateKey, ScalarResolution};
+use super::error::{Error, Result};
+use super::tagged;
+
+/// Limits and extensions that apply while loading.
+#[derive(Copy, Clone, Debug)]
+pub struct Options {
+    /// Lets an alias refer to an anchor defined later in the same document.
+    /// Such aliases are replaced by placeholder scalars before parsing and
+    /// resolved once the document has been loaded.
+    pub forward_aliases: bool,
+    /// What to do with a key that appears twice in the same mapping.
+    pub duplicate_key: DuplicateKey,
+    /// The parser recurses once for every level of nesting, so a stream
+    /// nested more than this many levels deep is rejected before it is
+    /// parsed.
+    pub max_depth: usize,
+    /// How many nodes aliases may copy in total, which keeps a document with
+    /// aliases of aliases from expanding exponentially.
+    pub alias_limit: usize,
+    /// The most bytes a single scalar may hold.
+    pub max_scalar_bytes: Option<usize>,
+    /// The most nodes a document may hold, counting those copied by aliases.
+    pub max_total_nodes: Option<usize>,
+    /// The most elements of a sequence or entries of a mapping.
+    pub max_collection_len: Option<usize>,
+    /// The rules that give a type to a plain scalar with no tag.
+    pub scalar_resolution: ScalarResolution,
+    /// The index of the only document to keep. The others are still parsed
+    /// but are dropped as soon as they end.
+    pub only_document: Option<usize>,
+}
+
+/// Loads every document in `source`.
+pub fn load(source: &str, options: Options) -> Result<Vec<Yaml>> {
+    let source = strip_bom(source);
+    let (docs, error) = load_until_error(source, options);
+    match error {
+        Some(err) => Err(scan_error(source, err)),
+        None => docs.into_iter().collect(),
+    }
+}
+
+/// Loads the document at index `n` in `source`. A syntax error after the end
+/// of that document is not reported.
+pub fn load_nth(source: &str, n: usize, mut options: Options) -> Result<Yaml> {
+    let source = strip_bom(source);
+    options.only_document = Some(n);
+    let (mut docs, error) = load_until_error(source, options);
+    match (docs.pop(), error) {
+        (Some(doc), _) => doc,
+        (None, Some(err)) => Err(scan_error(source, err)),
+        (None, None) => Err(Error::NoDocument(n)),
+    }
+}
+
+/// Loads every document in `source`, keeping going past a document that
+/// fails to load.
+pub fn load_multi(source: &str, options: Options) -> Vec<Result<Yaml>> {
+    let source = strip_bom(source);
+    let (docs, error) = load_until_error(source, options);
+    if error.is_none() {
+        return docs;
+    }
+
+    // The parser cannot recover from a syntax error, so load the documents
+    // one at a time. The text before each one is replaced by blank lines so
+    // that errors report the right line.
+    let mut docs = Vec::new();
+    for (start, end) in document_ranges(source) {
+        let lines_before = source[..start].matches('\n').count();
+        let mut piece = String::with_capacity(lines_before + end - start);
+        piece.push_str(&"\n".repeat(lines_before));
+        piece.push_str(&source[start..end]);
+
+        let (piece_docs, error) = load_until_error(&piece, options);
+        docs.extend(piece_docs);
+        if let Some(err) = error {
+            docs.push(Err(scan_error(&piece, err)));
+        }
+    }
+    docs
+}
+
+/// Removes a byte order mark, which the scanner would otherwise take as part
+/// of the first scalar.
+pub fn strip_bom(source: &str) -> &str {
+    if source.starts_with('\u{feff}') {
+        &source['\u{feff}'.len_utf8()..]
+    } else {
+        source
+    }
+}
+
+/// Replaces each `\r\n` line break with `\n`, which the YAML spec makes no
+/// different inside of scalars. yaml-rust reads past the end of its buffer,
+/// and panics, on a `\r\n` escaped with a backslash in a double-quoted
+/// scalar.
+pub fn normalize_breaks(source: &str) -> Cow<str> {
+    if source.contains("\r\n") {
+        Cow::Owned(source.replace("\r\n", "\n"))
+    } else {
+        Cow::Borrowed(source)
+    }
+}
+
+/// Loads the documents that precede the first syntax error in `source`,
+/// along with that error.
+fn load_until_error(source: &str,
+                    options: Options)
+                    -> (Vec<Result<Yaml>>, Option<ScanError>) {
+    let source = &*normalize_breaks(source);
+    let rewritten = if options.forward_aliases {
+        rewrite_forward_aliases(source)
+    } else {
+        None
+    };
+
+    let mut loader = Loader {
+        docs: Vec::new(),
+        doc_stack: Vec::new(),
+        key_stack: Vec::new(),
+        tag_stack: Vec::new(),
+        anchor_map: BTreeMap::new(),
+        placeholder: None,
+        collection_tags: Vec::new(),
+        tag_directives: TagDirectives::default(),
+        anchor_sizes: BTreeMap::new(),
+        alias_budget: options.alias_limit,
+        options: options,
+        doc_index: 0,
+        total_nodes: 0,
+        error: None,
+    };
+    let source = match rewritten {
+        Some((source, placeholder)) => {
+            loader.placeholder = Some(placeholder);
+            Cow::Owned(source)
+        }
+        None => Cow::Borrowed(source),
+    };
+    match prescan(&source, options.max_depth) {
+        Ok((tags, directives)) => {
+            loader.collection_tags = tags;
+            loader.tag_directives = directives;
+        }
+        Err(err) => return (vec![Err(err)], None),
+    }
+    let error = Parser::new(source.chars()).load(&mut loader, true).err();
+    if let Some(err) = loader.error.take() {
+        return (vec![Err(err)], None);
+    }
+    let docs = if loader.placeholder.is_some() {
+        let mut budget = loader.alias_budget;
+        loader.docs
+            .iter()
+            .map(|doc| loader.resolve(doc, &mut Vec::new(), &mut budget))
+            .collect()
+    } else {
+        loader.docs.into_iter().map(Ok).collect()
+    };
+    (docs, error)
+}
+
+/// Splits `source` into byte ranges that each begin with a `---` line, along
+/// with any directives right before it. The first range also holds whatever
+/// precedes the first `---`.
+fn document_ranges(source: &str) -> Vec<(usize, usize)> {
+    let mut starts = vec![0];
+    let mut directives = None;
+    let mut offset = 0;
+    for line in source.split('\n') {
+        if line.starts_with('%') {
+            directives = directives.or(Some(offset));
+        } else {
+            let marker = line == "---" ||
+                         line.starts_with("---") &&
+                         line[3..].starts_with(char::is_whitespace);
+            if marker {
+                starts.push(directives.unwrap_or(offset));
+            }
+            directives = None;
+        }
+        offset += line.len() + 1;
+    }
+    starts.dedup();
+
+    let ends = starts.iter().skip(1).cloned().chain(Some(source.len()));
+    starts.iter().cloned().zip(ends).collect()
+}
+
+struct Loader {
+    docs: Vec<Yaml>,
+    // (current node, anchor_id) tuple
+    doc_stack: Vec<(Yaml, usize)>,
+    key_stack: Vec<Yaml>,
+    /// Tag of each collection in `doc_stack`.
+    tag_stack: Vec<Option<String>>,
+    anchor_map: BTreeMap<usize, Yaml>,
+    /// Prefix of the scalars standing in for forward aliases. The rest of
+    /// such a scalar is the id of the anchor it refers to.
+    placeholder: Option<String>,
+    /// Tagged collections yet to be loaded, last to first.
+    collection_tags: Vec<CollectionTag>,
+    tag_directives: TagDirectives,
+    /// Number of nodes in each anchored node, counting the node itself.
+    anchor_sizes: BTreeMap<usize, usize>,
+    /// How many more nodes aliases may copy.
+    alias_budget: usize,
+    options: Options,
+    /// Index of the document being loaded.
+    doc_index: usize,
+    /// Number of nodes loaded so far, across documents.
+    total_nodes: usize,
+    /// The first limit that the input went beyond, or the first duplicate key
+    /// if those are errors. Once set, the rest of the input is ignored.
+    error: Option<Error>,
+}
+
+impl MarkedEventReceiver for Loader {
+    fn on_event(&mut self, ev: &Event, mark: Marker) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Err(err) = self.check_limits(ev) {
+            self.error = Some(err);
+            return;
+        }
+        match *ev {
+            Event::DocumentEnd => {
+                let doc = match self.doc_stack.len() {
+                    // empty document
+                    0 => Yaml::BadValue,
+                    1 => self.doc_stack.pop().unwrap().0,
+                    _ => unreachable!(),
+                };
+                let keep = self.options
+                    .only_document
+                    .map_or(true, |n| n == self.doc_index);
+                if keep {
+                    self.docs.push(doc);
+                }
+                self.doc_index += 1;
+            }
+            Event::SequenceStart(aid) => {
+                self.doc_stack.push((Yaml::Array(Vec::new()), aid));
+                let tag = self.collection_tag(mark);
+                self.tag_stack.push(tag);
+            }
+            Event::SequenceEnd => {
+                self.end_collection();
+            }
+            Event::MappingStart(aid) => {
+                self.doc_stack.push((Yaml::Hash(yaml::Hash::new()), aid));
+                self.key_stack.push(Yaml::BadValue);
+                let tag = self.collection_tag(mark);
+                self.tag_stack.push(tag);
+            }
+            Event::MappingEnd => {
+                self.key_stack.pop().unwrap();
+                self.end_collection();
+            }
+            Event::Scalar(ref v, style, aid, ref tag) => {
+                let tag = match *tag {
+                    Some(TokenType::Tag(ref handle, ref suffix)) => {
+                        Some(self.tag_directives.expand(mark, handle, suffix))
+                    }
+                    _ => None,
+                };
+                let app_tag = match tag {
+                    Some((ref handle, ref suffix)) => app_tag(handle, suffix),
+                    None => None,
+                };
+                let core_tag = match tag {
+                    Some((ref handle, ref suffix)) if handle == "!!" => {
+                        Some(suffix)
+                    }
+                    _ => None,
+                };
+                let node = if let Some(suffix) = core_tag {
+                    // a core tag applies to quoted scalars too
+                    resolve_tagged("!!", suffix, v)
+                } else if style != TScalarStyle::Plain {
+                    Yaml::String(v.clone())
+                } else if app_tag.is_some() {
+                    plain_scalar(v, self.options.scalar_resolution)
+                } else if let Some((ref handle, ref suffix)) = tag {
+                    resolve_tagged(handle, suffix, v)
+                } else if let Some(id) = self.placeholder_id(v) {
+                    Yaml::Alias(id)
+                } else {
+                    plain_scalar(v, self.options.scalar_resolution)
+                };
+                // Only a scalar with a core tag like `!!int` can fail to
+                // resolve, and yaml-rust would leave it as a `BadValue`.
+                if let Yaml::BadValue = node {
+                    let tag = format!("!!{}", core_tag.map_or("", |s| s));
+                    let line = line_col(mark).map_or(0, |(line, _)| line);
+                    self.error = Some(Error::InvalidTaggedScalar(tag, line));
+                    return;
+                }
+                let node = match app_tag {
+                    Some(app_tag) => tagged::node(app_tag, node),
+                    None => node,
+                };
+                self.insert_new_node((node, aid));
+            }
+            Event::Alias(id) => {
+                let node = match self.anchor_map.get(&id) {
+                    Some(v) => v.clone(),
+                    // The parser rejects an alias to an anchor it has not
+                    // seen, so this one is inside the node it refers to.
+                    None => {
+                        self.error = Some(Error::RecursiveAlias);
+                        return;
+                    }
+                };
+                self.insert_new_node((node, 0));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Loader {
+    /// Charges the nodes that `ev` adds against the limits.
+    fn check_limits(&mut self, ev: &Event) -> Result<()> {
+        let nodes = match *ev {
+            Event::Scalar(ref v, _, _, _) => {
+                match self.options.max_scalar_bytes {
+                    Some(limit) if v.len() > limit => {
+                        return Err(Error::ScalarTooLong(limit));
+                    }
+                    _ => {}
+                }
+                1
+            }
+            Event::SequenceStart(_) | Event::MappingStart(_) => 1,
+            Event::Alias(id) => {
+                let size = self.anchor_sizes.get(&id).cloned().unwrap_or(0);
+                if size > self.alias_budget {
+                    return Err(Error::AliasLimitExceeded);
+                }
+                self.alias_budget -= size;
+                size
+            }
+            _ => return Ok(()),
+        };
+        self.total_nodes += nodes;
+        match self.options.max_total_nodes {
+            Some(limit) if self.total_nodes > limit => {
+                return Err(Error::TooManyNodes(limit));
+            }
+            _ => {}
+        }
+        let len = match self.doc_stack.last() {
+            Some(&(Yaml::Array(ref v), _)) => v.len(),
+            Some(&(Yaml::Hash(ref h), _)) => {
+                match self.key_stack.last() {
+                    // only a key starts a new entry
+                    Some(key) if key.is_badvalue() => h.len(),
+                    _ => return Ok(()),
+                }
+            }
+            _ => return Ok(()),
+        };
+        match self.options.max_collection_len {
+            Some(limit) if len >= limit => Err(Error::CollectionTooLong(limit)),
+            _ => Ok(()),
+        }
+    }
+
+    /// The tag of the collection starting at `mark`, if it has one that is
+    /// kept in the loaded node.
+    fn collection_tag(&mut self, mark: Marker) -> Option<String> {
+        let (handle, suffix) = match take_collection_tag(
+            &mut self.collection_tags,
+            mark
+        ) {
+            Some(tag) => tag,
+            None => return None,
+        };
+        if handle == "!!" && suffix == "set" {
+            Some(String::from(tagged::SET_TAG))
+        } else {
+            app_tag(&handle, &suffix)
+        }
+    }
+
+    fn end_collection(&mut self) {
+        let (node, aid) = self.doc_stack.pop().unwrap();
+        let node = match self.tag_stack.pop().unwrap() {
+            Some(tag) => tagged::node(tag, node),
+            None => node,
+        };
+        self.insert_new_node((node, aid));
+    }
+
+    fn insert_new_node(&mut self, node: (Yaml, usize)) {
+        // valid anchor id starts from 1
+        if node.1 > 0 {
+            self.anchor_sizes.insert(node.1, count_nodes(&node.0));
+            self.anchor_map.insert(node.1, node.0.clone());
+        }
+        match self.doc_stack.last_mut() {
+            None => self.doc_stack.push(node),
+            Some(&mut (Yaml::Array(ref mut v), _)) => v.push(node.0),
+            Some(&mut (Yaml::Hash(ref mut h), _)) => {
+                let cur_key = self.key_stack.last_mut().unwrap();
+                if cur_key.is_badvalue() {
+                    // current node is a key
+                    *cur_key = node.0;
+                } else {
+                    // current node is a value
+                    let key = mem::replace(cur_key, Yaml::BadValue);
+                    let duplicate = match h.get_mut(&key) {
+                        Some(value) => {
+                            match self.options.duplicate_key {
+                                DuplicateKey::Error => true,
+                                DuplicateKey::FirstWins => false,
+                                // replaced in place, as inserting would move
+                                // the key to the end
+                                DuplicateKey::LastWins => {
+                                    *value = node.0;
+                                    false
+                                }
+                            }
+                        }
+                        None => {
+                            h.insert(key, node.0);
+                            return;
+                        }
+                    };
+                    if duplicate {
+                        self.error = Some(Error::DuplicateKey(describe(&key)));
+                    }
+                }
+            }
+            Some(_) => unreachable!(),
+        }
+    }
+
+    fn placeholder_id(&self, v: &str) -> Option<usize> {
+        match self.placeholder {
+            Some(ref prefix) if v.starts_with(prefix.as_str()) => {
+                v[prefix.len()..].parse().ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Replaces the placeholders left by forward aliases with a copy of the
+    /// anchored node. `active` holds the anchors being resolved further up,
+    /// which catches an anchor that contains an alias to itself. Every node
+    /// copied for an alias is taken from `budget`.
+    fn resolve(&self,
+               node: &Yaml,
+               active: &mut Vec<usize>,
+               budget: &mut usize)
+               -> Result<Yaml> {
+        if !active.is_empty() {
+            if *budget == 0 {
+                return Err(Error::AliasLimitExceeded);
+            }
+            *budget -= 1;
+        }
+        if let Some((tag, inner)) = tagged::split(node) {
+            let inner = try!(self.resolve(inner, active, budget));
+            return Ok(tagged::node(tag.to_owned(), inner));
+        }
+        match *node {
+            Yaml::Alias(id) => {
+                if active.contains(&id) {
+                    return Err(Error::RecursiveAlias);
+                }
+                let target = match self.anchor_map.get(&id) {
+                    Some(target) => target,
+                    None => return Ok(Yaml::BadValue),
+                };
+                active.push(id);
+                let resolved = try!(self.resolve(target, active, budget));
+                active.pop();
+                Ok(resolved)
+            }
+            Yaml::Array(ref seq) => {
+                let mut resolved = Vec::with_capacity(seq.len());
+                for elem in seq {
+                    resolved.push(try!(self.resolve(elem, active, budget)));
+                }
+                Ok(Yaml::Array(resolved))
+            }
+            Yaml::Hash(ref map) => {
+                // A key that was a placeholder was checked for duplicates
+                // only as the placeholder, so check it again once resolved.
+                let mut resolved = yaml::Hash::new();
+                for (k, v) in map {
+                    let k = try!(self.resolve(k, active, budget));
+                    let v = try!(self.resolve(v, active, budget));
+                    let duplicate = match resolved.get_mut(&k) {
+                        Some(value) => {
+                            match self.options.duplicate_key {
+                                DuplicateKey::Error => true,
+                                DuplicateKey::FirstWins => false,
+                                DuplicateKey::LastWins => {
+                                    *value = v;
+                                    false
+                                }
+                            }
+                        }
+                        None => {
+                            resolved.insert(k, v);
+                            continue;
+                        }
+                    };
+                    if duplicate {
+                        return Err(Error::DuplicateKey(describe(&k)));
+                    }
+                }
+                Ok(Yaml::Hash(resolved))
+            }
+            ref scalar => Ok(scalar.clone()),
+        }
+    }
+}
+
+/// The number of nodes in `node`, counting itself.
+fn count_nodes(node: &Yaml) -> usize {
+    match *node {
+        Yaml::Array(ref seq) => seq.iter().map(count_nodes).sum::<usize>() + 1,
+        Yaml::Hash(ref map) => {
+            map.iter()
+                .map(|(k, v)| count_nodes(k) + count_nodes(v))
+                .sum::<usize>() + 1
+        }
+        _ => 1,
+    }
+}
+
+/// A short description of a key for error messages.
+fn describe(key: &Yaml) -> String {
+    if let Some((tag, inner)) = tagged::split(key) {
+        return format!("!{} {}", tag, describe(inner));
+    }
+    match *key {
+        Yaml::String(ref s) | Yaml::Real(ref s) => s.clone(),
+        Yaml::Integer(i) => i.to_string(),
+        Yaml::Boolean(b) => b.to_string(),
+        Yaml::Array(_) => String::from("[...]"),
+        Yaml::Hash(_) => String::from("{...}"),
+        Yaml::Null | Yaml::Alias(_) | Yaml::BadValue => String::from("~"),
+    }
+}
+
+/// The name of an application-specific tag, which is the tag as written
+/// without its leading `!`. The `!!` tags of the core schema and the
+/// non-specific `!` tag are not application-specific.
+fn app_tag(handle: &str, suffix: &str) -> Option<String> {
+    match handle {
+        "!!" => None,
+        "" if suffix == "!" => None,
+        "" => Some(format!("<{}>", suffix)),
+        _ => Some(format!("{}{}", &handle[1..], suffix)),
+    }
+}
+
+/// The handle and suffix of the tag on a collection, with the mark of the
+/// token that starts the collection.
+pub type CollectionTag = (Marker, (String, String));
+
+/// Prefix of the tags of the core schema, which `!!` stands for by default.
+const CORE_TAG_PREFIX: &str = "tag:yaml.org,2002:";
+
+/// The tag handles declared by `%TAG` directives, which the parser does not
+/// apply. Each set of handles is listed with the line and column of the
+/// document from which on it is in effect.
+#[derive(Default)]
+pub struct TagDirectives {
+    scopes: Vec<((usize, usize), HashMap<String, String>)>,
+}
+
+impl TagDirectives {
+    /// The handle and suffix of the tag at `mark`, expanded as in
+    /// `expand_tag` if the document declares its handle.
+    pub fn expand(&self, mark: Marker, handle: &str, suffix: &str)
+                  -> (String, String) {
+        let declared = if self.scopes.is_empty() || handle.is_empty() {
+            None
+        } else {
+            let position = line_col(mark);
+            self.scopes
+                .iter()
+                .rev()
+                .find(|&&(start, _)| Some(start) <= position)
+                .and_then(|&(_, ref handles)| {
+                    expand_tag(handles, handle, suffix)
+                })
+        };
+        declared.unwrap_or_else(|| (handle.to_owned(), suffix.to_owned()))
+    }
+
+    /// Puts `handles` in effect from `mark` on, unless they already are.
+    fn enter(&mut self, mark: Marker, handles: &HashMap<String, String>) {
+        let unchanged = match self.scopes.last() {
+            Some(&(_, ref last)) => last == handles,
+            None => handles.is_empty(),
+        };
+        if !unchanged {
+            let start = line_col(mark).unwrap_or((0, 0));
+            self.scopes.push((start, handles.clone()));
+        }
+    }
+}
+
+/// Writes out a tag whose handle is declared in `handles` in full, and gives
+/// the handle and suffix that the scanner would give for it: `!` for a local
+/// tag, `!!` for a tag of the core schema, and no handle for any other.
+fn expand_tag(handles: &HashMap<String, String>,
+              handle: &str,
+              suffix: &str)
+              -> Option<(String, String)> {
+    let prefix = match handles.get(handle) {
+        Some(prefix) => prefix,
+        None => return None,
+    };
+    let tag = format!("{}{}", prefix, suffix);
+    let expanded = if tag.starts_with(CORE_TAG_PREFIX) {
+        (String::from("!!"), tag[CORE_TAG_PREFIX.len()..].to_owned())
+    } else if tag.starts_with('!') {
+        (String::from("!"), tag[1..].to_owned())
+    } else {
+        (String::new(), tag)
+    };
+    Some(expanded)
+}
+
+/// Finds the tags on collections, which the parser does not pass on. Each is
+/// listed with the mark of the token that starts its collection, which the
+/// parser reports as the mark of the collection, last to first. Also gathers
+/// the `%TAG` directives of each document, and expands the handles they
+/// declare in the tags found.
+///
+/// Fails if collections are nested more than `max_depth` deep, if a line is
+/// indented with a tab, or if a `%YAML` directive names a version other than
+/// 1.x. The scanner takes such a tab as the start of a plain scalar rather
+/// than rejecting it.
+pub fn prescan(source: &str,
+               max_depth: usize)
+               -> Result<(Vec<CollectionTag>, TagDirectives)> {
+    let mut tags = Vec::new();
+    let mut directives = TagDirectives::default();
+    // handles declared for the current document
+    let mut handles = HashMap::new();
+    // whether the directives of the next document are being read
+    let mut in_directives = false;
+    let mut pending = None;
+    let mut depth = 0;
+    for token in Scanner::new(source.chars()) {
+        match token.1 {
+            TokenType::BlockSequenceStart |
+            TokenType::BlockMappingStart |
+            TokenType::FlowSequenceStart |
+            TokenType::FlowMappingStart => {
+                depth += 1;
+                if depth > max_depth {
+                    return Err(Error::RecursionLimitExceeded);
+                }
+            }
+            TokenType::BlockEnd |
+            TokenType::FlowSequenceEnd |
+            TokenType::FlowMappingEnd => depth -= 1,
+            TokenType::Scalar(TScalarStyle::Plain, ref v) if
+                v.starts_with('\t') => {
+                let line = line_col(token.0).map_or(0, |(line, _)| line);
+                return Err(Error::TabIndentation(line));
+            }
+            TokenType::VersionDirective(major, minor) => {
+                if major != 1 {
+                    return Err(Error::UnsupportedVersion(major, minor));
+                }
+                if !in_directives {
+                    handles.clear();
+                    in_directives = true;
+                }
+            }
+            TokenType::TagDirective(ref handle, ref prefix) => {
+                if !in_directives {
+                    handles.clear();
+                    in_directives = true;
+                }
+                handles.insert(handle.clone(), prefix.clone());
+            }
+            // directives only apply to the document right after them
+            TokenType::DocumentStart => {
+                if !in_directives {
+                    handles.clear();
+                }
+                in_directives = false;
+                directives.enter(token.0, &handles);
+            }
+            TokenType::DocumentEnd => {
+                handles.clear();
+                directives.enter(token.0, &handles);
+            }
+            _ => {}
+        }
+        match token.1 {
+            TokenType::Tag(ref handle, ref suffix) => {
+                let tag = expand_tag(&handles, handle, suffix);
+                pending = Some(tag.unwrap_or_else(|| {
+                    (handle.clone(), suffix.clone())
+                }));
+            }
+            // an anchor may come between the tag and the node
+            TokenType::Anchor(_) => {}
+            TokenType::BlockSequenceStart |
+            TokenType::BlockMappingStart |
+            TokenType::FlowSequenceStart |
+            TokenType::FlowMappingStart |
+            TokenType::BlockEntry => {
+                if let Some(tag) = pending.take() {
+                    tags.push((token.0, tag));
+                }
+            }
+            _ => pending = None,
+        }
+    }
+    tags.reverse();
+    Ok((tags, directives))
+}
+
+/// Removes and returns the tag of the collection starting at `mark` from the
+/// tags found by `prescan`, along with any tags passed over on the way.
+pub fn take_collection_tag(tags: &mut Vec<CollectionTag>,
+                           mark: Marker)
+                           -> Option<(String, String)> {
+    let position = line_col(mark);
+    while let Some(&(tag_mark, _)) = tags.last() {
+        if tag_mark == mark {
+            return tags.pop().map(|(_, tag)| tag);
+        }
+        // A tag followed by `- ` may belong to an empty scalar rather than to
+        // a sequence, in which case no collection starts there.
+        if line_col(tag_mark) >= position {
+            break;
+        }
+        tags.pop();
+    }
+    None
+}
+
+/// Converts a scan error, replacing the scanner's complaint about a tab with
+/// the line that is indented with one. The scanner reports the mark of the
+/// scalar it was reading, which may be on an earlier line. Content left over
+/// after a complete document is reported as such, rather than as a missing
+/// `---`.
+pub fn scan_error(source: &str, err: ScanError) -> Error {
+    let msg = err.to_string();
+    let line = match error_line_col(&err) {
+        Some((line, _)) if msg.contains("found a tab") => line,
+        Some((line, _)) if msg.contains("expected <document start>") => {
+            return Error::TrailingContent(line);
+        }
+        _ => return Error::Scan(err),
+    };
+    let tab_line = source.split('\n')
+        .enumerate()
+        .skip(line - 1)
+        .find(|&(_, text)| {
+            let indent = text.len() - text.trim_left().len();
+            text[..indent].contains('\t')
+        });
+    match tab_line {
+        Some((index, _)) => Error::TabIndentation(index + 1),
+        None => Error::Scan(err),
+    }
+}
+
+/// Interprets an untagged plain scalar as `Yaml::from_str` does, except that
+/// the infinities and NaN of the core schema are reals rather than strings.
+fn plain_scalar(v: &str, resolution: ScalarResolution) -> Yaml {
+    if resolution == ScalarResolution::Yaml11 {
+        if let Some(node) = yaml11_scalar(v) {
+            return node;
+        }
+    }
+    match special_float(v) {
+        Some(_) => Yaml::Real(v.to_owned()),
+        None => Yaml::from_str(v),
+    }
+}
+
+/// The type that YAML 1.1 gives a plain scalar where YAML 1.2 would read it
+/// differently: the booleans like `yes` and `off`, integers in binary, in
+/// octal with a leading `0` or in base 60 like `1:30`, and numbers with `_`
+/// between their digits.
+fn yaml11_scalar(v: &str) -> Option<Yaml> {
+    if let Some(b) = de::lenient_bool(v) {
+        return Some(Yaml::Boolean(b));
+    }
+    let (sign, unsigned) = match v.as_bytes().first() {
+        Some(&b'-') => ("-", &v[1..]),
+        Some(&b'+') => ("", &v[1..]),
+        _ => ("", v),
+    };
+    let bytes = unsigned.as_bytes();
+    let integer = if unsigned.starts_with("0b") {
+        radix_integer(&unsigned[2..], 2)
+    } else if unsigned.starts_with("0x") {
+        radix_integer(&unsigned[2..], 16)
+    } else if unsigned.contains(':') {
+        return base60(sign, unsigned);
+    } else if bytes.len() > 1 && bytes[0] == b'0' &&
+              bytes.iter().all(|&b| b'0' <= b && b <= b'7' || b == b'_') {
+        radix_integer(&unsigned[1..], 8)
+    } else if unsigned.contains('_') &&
+              bytes.first().map_or(false, |b| b.is_ascii_digit()) {
+        // Without the underscores it may be an integer or a float.
+        return match Yaml::from_str(&format!("{}{}",
+                                             sign,
+                                             unsigned.replace('_', ""))) {
+            node @ Yaml::Integer(_) |
+            node @ Yaml::Real(_) => Some(node),
+            _ => None,
+        };
+    } else {
+        None
+    };
+    integer.map(|n| Yaml::Integer(if sign == "-" { -n } else { n }))
+}
+
+/// Digits in `radix`, with any number of `_` between them.
+fn radix_integer(digits: &str, radix: u32) -> Option<i64> {
+    let digits = digits.replace('_', "");
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    i64::from_str_radix(&digits, radix).ok()
+}
+
+/// Whether YAML 1.1 reads a plain scalar as a number in base 60, like `1:30`.
+pub fn is_base60(v: &str) -> bool {
+    let (sign, unsigned) = match v.as_bytes().first() {
+        Some(&b'-') => ("-", &v[1..]),
+        Some(&b'+') => ("", &v[1..]),
+        _ => ("", v),
+    };
+    base60(sign, unsigned).is_some()
+}
+
+/// A YAML 1.1 number in base 60, like `1:30` for 90 or `1:30.5` for 90.5,
+/// without its sign. Each part after the first is below 60. An integer may
+/// not start with `0`, which keeps a time like `09:30` a string.
+fn base60(sign: &str, unsigned: &str) -> Option<Yaml> {
+    let (whole, fraction) = match unsigned.find('.') {
+        Some(dot) => (&unsigned[..dot], Some(&unsigned[dot..])),
+        None => (unsigned, None),
+    };
+    let mut parts = whole.split(':');
+    let first = parts.next().unwrap_or("");
+    if fraction.is_none() && first.starts_with('0') {
+        return None;
+    }
+    let mut n = match radix_integer(first, 10) {
+        Some(n) if first.starts_with(|c: char| c.is_ascii_digit()) => n,
+        _ => return None,
+    };
+    for part in parts {
+        let part = match part.len() {
+            1 | 2 if part.bytes().all(|b| b.is_ascii_digit()) => {
+                part.parse::<i64>().unwrap()
+            }
+            _ => return None,
+        };
+        if part >= 60 {
+            return None;
+        }
+        n = match n.checked_mul(60).and_then(|n| n.checked_add(part)) {
+            Some(n) => n,
+            None => return None,
+        };
+    }
+    match fraction {
+        None => Some(Yaml::Integer(if sign == "-" { -n } else { n })),
+        Some(fraction) => {
+            let digits = fraction[1..].replace('_', "");
+            if !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let f = format!("0.{}", digits).parse::<f64>().unwrap();
+            Some(Yaml::Real(format!("{}{}", sign, n as f64 + f)))
+        }
+    }
+}
+
+/// What the text of a `Yaml::Real` reads back as. The serializer keeps a
+/// scalar that it writes verbatim, like `0o644` or a timestamp, as a real,
+/// so the text is not always that of a float.
+pub fn resolve_real(text: &str) -> Yaml {
+    if text.parse::<f64>().is_ok() || special_float(text).is_some() {
+        return Yaml::Real(text.to_owned());
+    }
+    plain_scalar(text, ScalarResolution::Yaml12)
+}
+
+/// The value of `.inf`, `-.inf` or `.nan` in any of the capitalizations of
+/// the core schema. Rust's own spellings, like `inf`, are not included since
+/// they parse as they are.
+pub fn special_float(s: &str) -> Option<f64> {
+    let (sign, rest) = match s.as_bytes().first() {
+        Some(&b'-') => (-1.0, &s[1..]),
+        Some(&b'+') => (1.0, &s[1..]),
+        _ => (1.0, s),
+    };
+    match rest {
+        ".inf" | ".Inf" | ".INF" => Some(sign * f64::INFINITY),
+        ".nan" | ".NaN" | ".NAN" if rest.len() == s.len() => Some(f64::NAN),
+        _ => None,
+    }
+}
+
+/// Interprets a plain scalar carrying a tag that is not application-specific,
+/// as `YamlLoader` does.
+fn resolve_tagged(handle: &str, suffix: &str, v: &str) -> Yaml {
+    if handle != "!!" {
+        return Yaml::String(v.to_owned());
+    }
+    match suffix {
+        "bool" => {
+            match v.parse::<bool>() {
+                Err(_) => Yaml::BadValue,
+                Ok(v) => Yaml::Boolean(v),
+            }
+        }
+        "int" => {
+            match v.parse::<i64>() {
+                Err(_) => Yaml::BadValue,
+                Ok(v) => Yaml::Integer(v),
+            }
+        }
+        "float" => {
+            if v.parse::<f64>().is_ok() || special_float(v).is_some() {
+                Yaml::Real(v.to_owned())
+            } else {
+                Yaml::BadValue
+            }
+        }
+        "null" => {
+            match v {
+                "" | "~" | "null" => Yaml::Null,
+                _ => Yaml::BadValue,
+            }
+        }
+        "binary" => {
+            tagged::node(String::from(tagged::BINARY_TAG),
+                         Yaml::String(v.to_owned()))
+        }
+        _ => Yaml::String(v.to_owned()),
+    }
+}
+
+/// Scans `source` for aliases that precede their anchor and replaces each one
+/// with a plain scalar naming the id the parser will give that anchor.
+/// Returns the rewritten source and the placeholder prefix, or `None` if
+/// there is nothing to rewrite. Scan errors are left for the real parse to
+/// report.
+fn rewrite_forward_aliases(source: &str) -> Option<(String, String)> {
+    // An alias that is never defined stays as it is, so that the parser
+    // reports it as usual.
+    let mut forward: Vec<(Marker, String, Option<usize>)> = Vec::new();
+    let mut defined = HashMap::new();
+    let mut pending = 0;
+    let mut anchor_id = 0;
+
+    let mut scanner = Scanner::new(source.chars());
+    for token in &mut scanner {
+        match token.1 {
+            TokenType::DocumentStart | TokenType::DocumentEnd => {
+                defined.clear();
+                pending = forward.len();
+            }
+            TokenType::Anchor(name) => {
+                // the parser numbers anchors from 1 in order of appearance
+                anchor_id += 1;
+                for alias in &mut forward[pending..] {
+                    if alias.2.is_none() && alias.1 == name {
+                        alias.2 = Some(anchor_id);
+                    }
+                }
+                defined.insert(name, anchor_id);
+            }
+            TokenType::Alias(ref name) if !defined.contains_key(name) => {
+                forward.push((token.0, name.clone(), None));
+            }
+            _ => {}
+        }
+    }
+    if scanner.get_error().is_some() ||
+       forward.iter().all(|alias| alias.2.is_none()) {
+        return None;
+    }
+
+    let mut placeholder = String::from("serde_yaml_forward_alias_");
+    while source.contains(placeholder.as_str()) {
+        placeholder.push('_');
+    }
+
+    let line_starts: Vec<usize> = Some(0)
+        .into_iter()
+        .chain(source.char_indices()
+            .filter(|&(_, ch)| ch == '\n')
+            .map(|(i, _)| i + 1))
+        .collect();
+    let mut rewritten = String::with_capacity(source.len());
+    let mut copied = 0;
+    for (mark, name, id) in forward {
+        let (id, start) = match (id, alias_start(source, &line_starts, mark)) {
+            (Some(id), Some(start)) => (id, start),
+            _ => continue,
+        };
+        // the alias is `*` followed by its name
+        let end = start + 1 + name.len();
+        if source.get(start..end) != Some(&format!("*{}", name)[..]) {
+            continue;
+        }
+        rewritten.push_str(&source[copied..start]);
+        rewritten.push_str(&placeholder);
+        rewritten.push_str(&id.to_string());
+        copied = end;
+    }
+    rewritten.push_str(&source[copied..]);
+
+    Some((rewritten, placeholder))
+}
+
+/// The byte offset in `source` of the token at `mark`.
+fn alias_start(source: &str, line_starts: &[usize], mark: Marker)
+               -> Option<usize> {
+    line_col(mark)
+        .and_then(|(line, col)| line_starts.get(line - 1).map(|&s| (s, col)))
+        .and_then(|(line_start, col)| {
+            source[line_start..]
+                .char_indices()
+                .nth(col)
+                .map(|(offset, _)| line_start + offset)
+        })
+}
+
+/// The line (from 1) and column (from 0) of a mark. yaml-rust keeps the
+/// fields of `Marker` private, and a `ScanError` message is the one place
+/// that shows them.
+pub fn line_col(mark: Marker) -> Option<(usize, usize)> {
+    error_line_col(&ScanError::new(mark, ""))
+}
+
+/// The line (from 1) and column (from 0) at which a scan error occurred.
+fn error_line_col(err: &ScanError) -> Option<(usize, usize)> {
+    let msg = err.to_string();
+    let mut words = msg.split_whitespace().rev();
+    match (words.next(), words.next(), words.next(), words.next(),
+           words.next()) {
+        (Some(col), Some("column"), Some(line), Some("line"), Some("at")) => {
+            match (line.parse::<usize>(), col.parse::<usize>()) {
+                (Ok(line), Ok(col)) if line > 0 && col > 0 => {
+                    Some((line, col - 1))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
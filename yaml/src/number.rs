@@ -0,0 +1,297 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem;
+
+use serde::{self, Serialize, Deserialize};
+
+use super::{FloatFormat, WholeFloat};
+use super::ser::format_real;
+
+/// A YAML number, which remembers whether it was written as an integer or
+/// as a float.
+///
+/// Only the value is kept, not the text it was written as, so a number is
+/// written back in its plain decimal form: `1e10` as `10000000000.0` and
+/// `0x1F` as `31`.
+///
+/// ```rust
+/// use serde_yaml::Number;
+/// let int: Number = serde_yaml::from_str("1").unwrap();
+/// assert!(int.is_i64());
+/// let float: Number = serde_yaml::from_str("1.0").unwrap();
+/// assert!(float.is_f64());
+///
+/// let float: Number = serde_yaml::from_str("1e10").unwrap();
+/// assert_eq!(float.to_string(), "10000000000.0");
+/// let int: Number = serde_yaml::from_str("0x1F").unwrap();
+/// assert_eq!(int.to_string(), "31");
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Number {
+    n: N,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum N {
+    Int(i64),
+    /// An integer too big for `i64`. Smaller ones are always `Int`.
+    PosInt(u64),
+    Float(f64),
+}
+
+impl Number {
+    /// Whether the number was written as an integer.
+    pub fn is_i64(&self) -> bool {
+        self.as_i64().is_some()
+    }
+
+    /// Returns the number if it was written as an integer.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.n {
+            N::Int(i) => Some(i),
+            N::PosInt(_) | N::Float(_) => None,
+        }
+    }
+
+    /// Whether the number was written as an integer that is not negative.
+    pub fn is_u64(&self) -> bool {
+        self.as_u64().is_some()
+    }
+
+    /// Returns the number if it was written as an integer that is not
+    /// negative.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.n {
+            N::Int(i) if i >= 0 => Some(i as u64),
+            N::PosInt(u) => Some(u),
+            N::Int(_) | N::Float(_) => None,
+        }
+    }
+
+    /// Whether the number was written as a float.
+    pub fn is_f64(&self) -> bool {
+        match self.n {
+            N::Int(_) | N::PosInt(_) => false,
+            N::Float(_) => true,
+        }
+    }
+
+    /// Returns the number as a float, converting an integer if necessary.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.n {
+            N::Int(i) => Some(i as f64),
+            N::PosInt(u) => Some(u as f64),
+            N::Float(f) => Some(f),
+        }
+    }
+}
+
+macro_rules! from_int {
+    ($($ty:ty)*) => {
+        $(
+            impl From<$ty> for Number {
+                fn from(i: $ty) -> Self {
+                    Number { n: N::Int(i as i64) }
+                }
+            }
+        )*
+    };
+}
+
+from_int!(i8 i16 i32 i64 isize u8 u16 u32);
+
+impl From<u64> for Number {
+    fn from(u: u64) -> Self {
+        if u > i64::MAX as u64 {
+            Number { n: N::PosInt(u) }
+        } else {
+            Number { n: N::Int(u as i64) }
+        }
+    }
+}
+
+impl From<f32> for Number {
+    fn from(f: f32) -> Self {
+        Number { n: N::Float(f as f64) }
+    }
+}
+
+impl From<f64> for Number {
+    fn from(f: f64) -> Self {
+        Number { n: N::Float(f) }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.n {
+            N::Int(i) => write!(f, "{}", i),
+            N::PosInt(u) => write!(f, "{}", u),
+            N::Float(v) => {
+                let real = format_real(v,
+                                       WholeFloat::DotZero,
+                                       FloatFormat::Shortest);
+                f.write_str(&real)
+            }
+        }
+    }
+}
+
+impl Serialize for Number {
+    fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+        where S: serde::Serializer
+    {
+        match self.n {
+            N::Int(i) => serializer.serialize_i64(i),
+            N::PosInt(u) => serializer.serialize_u64(u),
+            N::Float(f) => serializer.serialize_f64(f),
+        }
+    }
+}
+
+impl Deserialize for Number {
+    fn deserialize<D>(deserializer: &mut D) -> Result<Self, D::Error>
+        where D: serde::Deserializer
+    {
+        struct NumberVisitor;
+
+        impl serde::de::Visitor for NumberVisitor {
+            type Value = Number;
+
+            fn visit_i64<E>(&mut self, i: i64) -> Result<Number, E>
+                where E: serde::de::Error
+            {
+                Ok(i.into())
+            }
+
+            fn visit_u64<E>(&mut self, u: u64) -> Result<Number, E>
+                where E: serde::de::Error
+            {
+                Ok(u.into())
+            }
+
+            fn visit_f64<E>(&mut self, f: f64) -> Result<Number, E>
+                where E: serde::de::Error
+            {
+                Ok(f.into())
+            }
+        }
+
+        deserializer.deserialize(NumberVisitor)
+    }
+}
+
+impl PartialEq for N {
+    fn eq(&self, other: &N) -> bool {
+        match (*self, *other) {
+            (N::Int(a), N::Int(b)) => a == b,
+            (N::PosInt(a), N::PosInt(b)) => a == b,
+            (N::Float(a), N::Float(b)) => {
+                if a.is_nan() && b.is_nan() {
+                    // compare NaN for bitwise equality
+                    let (a, b): (i64, i64) = unsafe {
+                        (mem::transmute(a), mem::transmute(b))
+                    };
+                    a == b
+                } else {
+                    a == b
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for N {}
+
+/// Numbers are ordered by value, exactly even between integers and floats.
+/// An integer comes before a float of the same value, so that only equal
+/// numbers compare as equal. NaN comes after every other number.
+impl Ord for N {
+    fn cmp(&self, other: &N) -> Ordering {
+        match (*self, *other) {
+            (N::Int(a), N::Int(b)) => a.cmp(&b),
+            (N::PosInt(a), N::PosInt(b)) => a.cmp(&b),
+            (N::Int(_), N::PosInt(_)) => Ordering::Less,
+            (N::PosInt(_), N::Int(_)) => Ordering::Greater,
+            (N::Float(a), N::Float(b)) => {
+                match (a.is_nan(), b.is_nan()) {
+                    (false, false) => a.partial_cmp(&b).unwrap(),
+                    (true, true) => {
+                        // consistent with the bitwise equality of NaN
+                        let (a, b): (i64, i64) = unsafe {
+                            (mem::transmute(a), mem::transmute(b))
+                        };
+                        a.cmp(&b)
+                    }
+                    (a, b) => a.cmp(&b),
+                }
+            }
+            (N::Int(i), N::Float(f)) => cmp_int_float(i, f),
+            (N::PosInt(u), N::Float(f)) => cmp_pos_int_float(u, f),
+            (N::Float(f), N::Int(i)) => cmp_int_float(i, f).reverse(),
+            (N::Float(f), N::PosInt(u)) => cmp_pos_int_float(u, f).reverse(),
+        }
+    }
+}
+
+impl PartialOrd for N {
+    fn partial_cmp(&self, other: &N) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Rounding to the nearest float never reorders two numbers, so an integer
+// that rounds to something other than `f` is on the same side of `f` as its
+// rounding. One that rounds to `f` is within the range of integers, where
+// the comparison can be made exactly.
+
+fn cmp_int_float(i: i64, f: f64) -> Ordering {
+    if f.is_nan() {
+        return Ordering::Less;
+    }
+    match (i as f64).partial_cmp(&f).unwrap() {
+        // `i64::MAX` rounds up to 2^63, which is not an `i64`
+        Ordering::Equal if f < 9223372036854775808.0 => {
+            i.cmp(&(f as i64)).then(Ordering::Less)
+        }
+        Ordering::Equal => Ordering::Less,
+        ordering => ordering,
+    }
+}
+
+fn cmp_pos_int_float(u: u64, f: f64) -> Ordering {
+    if f.is_nan() {
+        return Ordering::Less;
+    }
+    match (u as f64).partial_cmp(&f).unwrap() {
+        // `u64::MAX` rounds up to 2^64, which is not a `u64`
+        Ordering::Equal if f < 18446744073709551616.0 => {
+            u.cmp(&(f as u64)).then(Ordering::Less)
+        }
+        Ordering::Equal => Ordering::Less,
+        ordering => ordering,
+    }
+}
+
+impl Hash for Number {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self.n {
+            N::Int(i) => (0, i).hash(state),
+            N::PosInt(u) => (0, u).hash(state),
+            N::Float(_) => {
+                // you should feel bad for using f64 as a map key
+                1.hash(state);
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Unsigned integers written in hexadecimal or octal
+//!
+//! YAML reads `0xFF` and `0o644` as integers. Use the functions in `hex` or
+//! `octal` on a struct field to write it in one of those forms, as suits
+//! bitmasks and file permissions:
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct File {
+//!     #[serde(serialize_with = "serde_yaml::radix::octal::serialize",
+//!             deserialize_with = "serde_yaml::radix::octal::deserialize")]
+//!     mode: u32,
+//! }
+//! ```
+//!
+//! Deserialization accepts the integer written in any form, and also a string
+//! holding it in hexadecimal or octal, as JSON output has it. Integers are
+//! read as `i64`, so a value above `i64::MAX` does not read back.
+
+use serde::{de, ser};
+use serde::de::value::ValueDeserializer;
+
+use super::ser::PLAIN_TOKEN;
+
+/// Integers written like `0xFF`.
+pub mod hex {
+    use serde::{de, ser};
+
+    pub fn serialize<T, S>(
+        value: &T,
+        serializer: &mut S
+    ) -> Result<(), S::Error>
+        where T: Copy + Into<u64>,
+              S: ser::Serializer,
+    {
+        super::serialize(format!("0x{:X}", (*value).into()), serializer)
+    }
+
+    pub fn deserialize<T, D>(deserializer: &mut D) -> Result<T, D::Error>
+        where T: de::Deserialize,
+              D: de::Deserializer,
+    {
+        super::deserialize(deserializer)
+    }
+}
+
+/// Integers written like `0o644`.
+pub mod octal {
+    use serde::{de, ser};
+
+    pub fn serialize<T, S>(
+        value: &T,
+        serializer: &mut S
+    ) -> Result<(), S::Error>
+        where T: Copy + Into<u64>,
+              S: ser::Serializer,
+    {
+        super::serialize(format!("0o{:o}", (*value).into()), serializer)
+    }
+
+    pub fn deserialize<T, D>(deserializer: &mut D) -> Result<T, D::Error>
+        where T: de::Deserialize,
+              D: de::Deserializer,
+    {
+        super::deserialize(deserializer)
+    }
+}
+
+fn serialize<S>(repr: String, serializer: &mut S) -> Result<(), S::Error>
+    where S: ser::Serializer,
+{
+    serializer.serialize_newtype_struct(PLAIN_TOKEN, repr)
+}
+
+fn deserialize<T, D>(deserializer: &mut D) -> Result<T, D::Error>
+    where T: de::Deserialize,
+          D: de::Deserializer,
+{
+    let n = try!(deserializer.deserialize(RadixVisitor));
+    T::deserialize(&mut ValueDeserializer::<D::Error>::into_deserializer(n))
+}
+
+/// Reads an unsigned integer, or a string holding one like `0x1A4` or
+/// `0o644`.
+struct RadixVisitor;
+
+impl de::Visitor for RadixVisitor {
+    type Value = u64;
+
+    fn visit_u64<E>(&mut self, v: u64) -> Result<u64, E>
+        where E: de::Error,
+    {
+        Ok(v)
+    }
+
+    fn visit_i64<E>(&mut self, v: i64) -> Result<u64, E>
+        where E: de::Error,
+    {
+        if v < 0 {
+            return Err(E::invalid_value(&format!("expected an unsigned \
+                                                  integer, found {}",
+                                                 v)));
+        }
+        Ok(v as u64)
+    }
+
+    fn visit_str<E>(&mut self, v: &str) -> Result<u64, E>
+        where E: de::Error,
+    {
+        let parsed = if v.starts_with("0x") {
+            u64::from_str_radix(&v[2..], 16).ok()
+        } else if v.starts_with("0o") {
+            u64::from_str_radix(&v[2..], 8).ok()
+        } else {
+            None
+        };
+        parsed.ok_or_else(|| {
+            E::invalid_value(&format!("expected an integer like `0x1A4` or \
+                                       `0o644`, found `{}`",
+                                      v))
+        })
+    }
+}
@@ -0,0 +1,279 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Editing scalars in place, keeping comments and layout.
+//!
+//! yaml-rust drops comments and does not expose the position of a node, so
+//! the document is scanned here line by line. Only block mappings and block
+//! sequences are followed, and only a scalar written on the same line as its
+//! key or `- ` can be read or replaced. Everything else in the document,
+//! comments included, is kept byte for byte.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use super::{de, ser};
+use super::error::{Error, Result};
+use super::value::Value;
+
+/// A YAML document that keeps its source text, so that scalars can be
+/// changed without losing comments or formatting.
+///
+/// A path names each mapping key or sequence index on the way to a scalar.
+///
+/// ```rust
+/// use serde_yaml::RawDocument;
+/// let mut doc = RawDocument::parse("port: 80 # default\n").unwrap();
+/// doc.set(&["port"], &8080).unwrap();
+/// assert_eq!(doc.as_str(), "port: 8080 # default\n");
+/// ```
+#[derive(Clone, Debug)]
+pub struct RawDocument {
+    text: String,
+    scalars: Vec<Scalar>,
+}
+
+/// Where the scalar at `path` is written in the source.
+#[derive(Clone, Debug)]
+struct Scalar {
+    path: Vec<String>,
+    start: usize,
+    end: usize,
+}
+
+/// A block collection that encloses the current line.
+struct Frame {
+    indent: usize,
+    seq: bool,
+    /// The key, or the index of the entry in a sequence.
+    name: String,
+    count: usize,
+}
+
+impl RawDocument {
+    /// Parses a single YAML document, failing if it is not valid YAML.
+    pub fn parse(text: &str) -> Result<Self> {
+        try!(de::from_str::<Value>(text));
+        Ok(RawDocument {
+            text: text.to_owned(),
+            scalars: scan(text),
+        })
+    }
+
+    /// The scalar at `path`, or `None` if there is no scalar the scanner can
+    /// find there.
+    pub fn get(&self, path: &[&str]) -> Option<Value> {
+        self.find(path).and_then(|scalar| {
+            de::from_str(&self.text[scalar.start..scalar.end]).ok()
+        })
+    }
+
+    /// Replaces the scalar at `path` with `value`, which must serialize as a
+    /// scalar. The rest of the line, such as a trailing comment, is kept.
+    pub fn set<T>(&mut self, path: &[&str], value: &T) -> Result<()>
+        where T: Serialize,
+    {
+        let (start, end) = match self.find(path) {
+            Some(scalar) => (scalar.start, scalar.end),
+            None => return Err(Error::PathNotFound(path.join("/"))),
+        };
+        let yaml = try!(ser::to_string(value));
+        let repr = yaml.trim_left_matches("---\n");
+        if repr.contains('\n') {
+            return Err(Error::NotAScalar);
+        }
+        let mut text = String::with_capacity(self.text.len() + repr.len());
+        text.push_str(&self.text[..start]);
+        text.push_str(repr);
+        text.push_str(&self.text[end..]);
+        self.scalars = scan(&text);
+        self.text = text;
+        Ok(())
+    }
+
+    /// The source text of the document, with any edits applied.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    fn find(&self, path: &[&str]) -> Option<&Scalar> {
+        self.scalars.iter().find(|scalar| scalar.path == path)
+    }
+}
+
+impl fmt::Display for RawDocument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Finds every scalar written on the same line as its key or `- `.
+fn scan(text: &str) -> Vec<Scalar> {
+    let mut scalars = Vec::new();
+    let mut frames: Vec<Frame> = Vec::new();
+    // The indentation of a block scalar whose lines are being skipped.
+    let mut block_scalar: Option<usize> = None;
+
+    let mut offset = 0;
+    for full_line in text.split('\n') {
+        let line_start = offset;
+        offset += full_line.len() + 1;
+        let line = full_line.trim_right_matches('\r');
+        let trimmed = line.trim_left_matches(' ');
+        let mut col = line.len() - trimmed.len();
+
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(indent) = block_scalar {
+            if col > indent {
+                continue;
+            }
+            block_scalar = None;
+        }
+        if line.starts_with("---") || line.starts_with("...") ||
+           line.starts_with('%') {
+            continue;
+        }
+
+        let mut rest = trimmed;
+        while rest == "-" || rest.starts_with("- ") {
+            enter(&mut frames, col, None);
+            let after = rest[1..].trim_left_matches(' ');
+            col += rest.len() - after.len();
+            rest = after;
+        }
+        if rest.is_empty() || rest.starts_with('#') {
+            continue;
+        }
+
+        let line_indent = col;
+        if let Some((key, value_at)) = split_key(rest) {
+            enter(&mut frames, col, Some(key));
+            let after = rest[value_at..].trim_left_matches(' ');
+            col += rest.len() - after.len();
+            rest = after;
+            if rest.is_empty() || rest.starts_with('#') {
+                continue;
+            }
+        }
+        if rest.starts_with('|') || rest.starts_with('>') {
+            block_scalar = Some(line_indent);
+            continue;
+        }
+
+        let start = line_start + col;
+        scalars.push(Scalar {
+            path: frames.iter().map(|frame| frame.name.clone()).collect(),
+            start: start,
+            end: start + scalar_len(rest),
+        });
+    }
+    scalars
+}
+
+/// Moves to a key, if `key` is given, or else to a sequence entry, starting at
+/// column `col`.
+fn enter(frames: &mut Vec<Frame>, col: usize, key: Option<String>) {
+    let seq = key.is_none();
+    loop {
+        let sibling = match frames.last() {
+            Some(last) if last.indent > col => None,
+            Some(last) if last.indent == col && last.seq == seq => Some(true),
+            // A sequence may sit at the same indentation as the key that
+            // holds it, and ends at the next key.
+            Some(last) if last.indent == col && last.seq => None,
+            _ => Some(false),
+        };
+        match sibling {
+            None => {
+                frames.pop();
+            }
+            Some(true) => {
+                let last = frames.last_mut().unwrap();
+                last.name = match key {
+                    Some(key) => key,
+                    None => last.count.to_string(),
+                };
+                last.count += 1;
+                return;
+            }
+            Some(false) => break,
+        }
+    }
+    frames.push(Frame {
+        indent: col,
+        seq: seq,
+        name: key.unwrap_or_else(|| String::from("0")),
+        count: 1,
+    });
+}
+
+/// Splits `key: value` into the key and the offset of the value.
+fn split_key(s: &str) -> Option<(String, usize)> {
+    let key_end = if s.starts_with('"') || s.starts_with('\'') {
+        quoted_len(s)
+    } else {
+        match s.find(": ") {
+            Some(end) => end,
+            None if s.ends_with(':') => s.len() - 1,
+            None => return None,
+        }
+    };
+    let after = &s[key_end..];
+    if !(after.starts_with(": ") || after == ":") {
+        return None;
+    }
+    let raw_key = s[..key_end].trim_right();
+    if raw_key.contains(" #") || raw_key.starts_with('[') ||
+       raw_key.starts_with('{') {
+        return None;
+    }
+    let key = if raw_key.starts_with('"') || raw_key.starts_with('\'') {
+        match de::from_str::<String>(raw_key) {
+            Ok(key) => key,
+            Err(_) => return None,
+        }
+    } else {
+        raw_key.to_owned()
+    };
+    Some((key, key_end + 1))
+}
+
+/// The length of the scalar at the start of `s`, leaving out a trailing
+/// comment and spaces.
+fn scalar_len(s: &str) -> usize {
+    if s.starts_with('"') || s.starts_with('\'') {
+        return quoted_len(s);
+    }
+    let end = s.find(" #").unwrap_or(s.len());
+    s[..end].trim_right().len()
+}
+
+/// The length of the quoted scalar at the start of `s`, including quotes.
+fn quoted_len(s: &str) -> usize {
+    let quote = s.as_bytes()[0];
+    let bytes = s.as_bytes();
+    let mut i = 1;
+    while i < bytes.len() {
+        if quote == b'"' && bytes[i] == b'\\' {
+            i += 2;
+            continue;
+        }
+        if bytes[i] == quote {
+            if quote == b'\'' && bytes.get(i + 1) == Some(&b'\'') {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    s.len()
+}
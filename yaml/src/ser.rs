@@ -9,32 +9,137 @@
 //! YAML Serialization
 //!
 //! This module provides YAML serialization with the type `Serializer`.
+//!
+//! Map keys need not be strings. A key that is a sequence or a mapping, such
+//! as a struct, is written in flow style like `{x: 1, y: 2}: value`. Keys are
+//! compared once converted to YAML, so if two keys of a map convert to the
+//! same node, the later entry replaces the earlier one, just as a duplicate
+//! key does when a document is read with `DuplicateKey::LastWins`.
+//!
+//! Unit values, unit structs and `None` are all written as `~`. A `~` reads
+//! back as any of them, except that an `Option<()>` cannot tell `Some(())`
+//! from `None` and reads both as `None`.
 
-use std::{fmt, io, mem};
+use std::{cmp, fmt, io, mem};
+use std::sync::Arc;
 
-use yaml_rust::{Yaml, YamlEmitter};
+use yaml_rust::Yaml;
 use yaml_rust::yaml;
 
 use serde::ser;
 
+use super::base64;
+use super::emitter::{Emitter, KeyTransform, Style};
 use super::error::{Error, Result};
+use super::style::ScalarStyles;
+use super::tagged::{self, TAGGED_TOKEN};
+use super::value::Value;
+
+/// Newtype struct name by which `serde_yaml::timestamp` and
+/// `serde_yaml::radix` ask for their string to be written as a plain scalar
+/// rather than a quoted string. The string is kept as a `Yaml::Real`, which
+/// the emitter writes verbatim, so anything else reading a real has to go
+/// through `loader::resolve_real` to tell such text from a float.
+pub const PLAIN_TOKEN: &str = "$serde_yaml::plain";
+
+/// Newtype struct name by which `serde_yaml::set` asks for its sequence to be
+/// written as a `!!set` mapping.
+pub const SET_TOKEN: &str = "$serde_yaml::set";
+
+/// Newtype struct name by which `serde_yaml::singleton_map` asks for a variant
+/// to be written as a mapping from its name even if variants are tagged.
+pub const SINGLETON_MAP_TOKEN: &str = "$serde_yaml::singleton_map";
 
 /// A structure for serializing a Rust value into a YAML value.
 pub struct Serializer {
     /// The YAML value to hold the result.
     doc: Yaml,
+    /// Options inherited by the serializers of nested values.
+    config: Config,
 }
 
 impl Serializer {
     pub fn new() -> Self {
-        Serializer {
-            doc: Yaml::Null,
-        }
+        SerializerBuilder::new().build()
     }
 
     pub fn take(self) -> Yaml {
         self.doc
     }
+
+    /// Serializes a nested value using the same options as this serializer.
+    fn to_yaml<T>(&self, elem: T) -> Result<Yaml>
+        where T: ser::Serialize,
+    {
+        let mut ser = Serializer {
+            doc: Yaml::Null,
+            config: self.config,
+        };
+        try!(elem.serialize(&mut ser));
+        Ok(ser.take())
+    }
+
+    /// The node for a newtype, tuple or struct variant holding `content`.
+    fn variant(&self, variant: &str, content: Yaml) -> Result<Yaml> {
+        if self.config.tag_variants {
+            Ok(tagged::node(String::from(variant), content))
+        } else {
+            Ok(singleton_hash(try!(self.to_yaml(variant)), content))
+        }
+    }
+
+    fn serialize_real<F>(&mut self, v: F) -> Result<()>
+        where F: fmt::Display + fmt::LowerExp + Into<f64> + Copy,
+    {
+        self.doc = Yaml::Real(format_real(v,
+                                          self.config.whole_float,
+                                          self.config.float_format));
+        Ok(())
+    }
+}
+
+/// Formats a float in the given format. The shortest format has the fewest
+/// digits that read back as the same value, and uses exponent notation for
+/// magnitudes outside of `1e-5..1e16` so that `1e300` is not written out with
+/// three hundred zeros. Infinity and NaN are written as `.inf`, `-.inf` and
+/// `.nan` in every format, which is how YAML spells them.
+pub fn format_real<F>(v: F,
+                      whole_float: WholeFloat,
+                      float_format: FloatFormat)
+                      -> String
+    where F: fmt::Display + fmt::LowerExp + Into<f64> + Copy,
+{
+    let f = v.into();
+    let magnitude = f.abs();
+    let mut real = match float_format {
+        _ if f.is_nan() => return String::from(".nan"),
+        _ if f.is_infinite() => {
+            return String::from(if f < 0.0 { "-.inf" } else { ".inf" });
+        }
+        FloatFormat::Shortest if magnitude != 0.0 &&
+                                 !(1e-5..1e16).contains(&magnitude) => {
+            format!("{:e}", v)
+        }
+        FloatFormat::Shortest => v.to_string(),
+        FloatFormat::Fixed(precision) => format!("{:.*}", precision, v),
+        FloatFormat::Scientific => format!("{:e}", v),
+    };
+
+    // The mantissa is fixed up in place, which saves copying the string.
+    let mantissa_len = real.find('e').unwrap_or(real.len());
+    // Rust formats whole floats without a decimal point, which would read
+    // back as an integer.
+    if whole_float == WholeFloat::DotZero &&
+       real[..mantissa_len].chars().all(|c| c == '-' || c.is_numeric()) {
+        real.insert_str(mantissa_len, ".0");
+    }
+    if let Some(e) = real.find('e') {
+        // YAML 1.1 requires a sign on the exponent.
+        if !real[e + 1..].starts_with('-') {
+            real.insert(e + 1, '+');
+        }
+    }
+    real
 }
 
 impl Default for Serializer {
@@ -43,6 +148,554 @@ impl Default for Serializer {
     }
 }
 
+/// How a float with no fractional part, like `2.0_f64`, is rendered.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WholeFloat {
+    /// Emit `2.0` so that the value reads back as a float. This is the
+    /// default.
+    DotZero,
+    /// Emit `2`, which is more compact but reads back as an integer.
+    Plain,
+}
+
+/// How floats are rendered. Every format is the same on every platform.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FloatFormat {
+    /// Emit the fewest digits that read back as the same value, like
+    /// `3.14159`. This is the default.
+    Shortest,
+    /// Emit the given number of digits after the decimal point, like `3.142`
+    /// for `Fixed(3)`. Digits past those are rounded away.
+    Fixed(usize),
+    /// Emit exponent notation with the fewest digits that read back as the
+    /// same value, like `3.14159e+0`.
+    Scientific,
+}
+
+/// How a unit variant, like `Status::Running` in a C-style enum, is rendered.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum UnitVariant {
+    /// Emit the name of the variant, `Running`. This is the default.
+    Name,
+    /// Emit the index of the variant among all variants of the enum, as an
+    /// integer.
+    Index,
+}
+
+/// The line break written between lines of output. Line breaks inside of
+/// strings are always escaped, so the output is uniform whatever the strings
+/// contain.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Unix style `\n`. This is the default.
+    Lf,
+    /// Windows style `\r\n`.
+    CrLf,
+}
+
+/// How a null, such as `None` or `()`, is written.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NullStyle {
+    /// `~`. This is the default.
+    Tilde,
+    /// `null`.
+    Null,
+    /// Nothing at all, as in `key:`. A null map key, or a null inside of a
+    /// flow collection, is written as `~` instead.
+    Empty,
+}
+
+/// How strings are quoted. Whatever the style, a string that cannot be
+/// written that way is double-quoted instead, so it always reads back as the
+/// same string under YAML 1.2. Under YAML 1.1 it does too, except for the
+/// one-letter keys `y`, `Y`, `n` and `N`, which are left plain although
+/// YAML 1.1 reads them as booleans.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QuoteStyle {
+    /// Plain, unless the string looks like something that would need
+    /// quotes. This is the default.
+    Auto,
+    /// Single quotes, except for strings with control characters or line
+    /// breaks.
+    Single,
+    /// Double quotes.
+    Double,
+    /// Plain wherever it reads back as the same string, by the rules above,
+    /// which is more often than `Auto` leaves a string plain.
+    Plain,
+}
+
+/// How bytes, such as a `serde::bytes::ByteBuf`, are rendered. Each form can
+/// be deserialized back into bytes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BytesEncoding {
+    /// Emit a sequence with one integer per byte.
+    IntArray,
+    /// Emit a `!!binary` scalar holding the bytes in base64. This is the
+    /// default.
+    Base64,
+    /// Emit a string with two lowercase hexadecimal digits per byte. Reading
+    /// it back requires `DeserializerBuilder::hex_bytes`.
+    Hex,
+}
+
+/// Options that control how values are serialized.
+#[derive(Copy, Clone, Debug)]
+struct Config {
+    whole_float: WholeFloat,
+    float_format: FloatFormat,
+    unit_variant: UnitVariant,
+    bytes_encoding: BytesEncoding,
+    style: Style,
+    omit_empty: bool,
+    sort_keys: bool,
+    tag_variants: bool,
+    /// Bytes reserved for the output of `to_vec` and `to_string`.
+    capacity: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            whole_float: WholeFloat::DotZero,
+            float_format: FloatFormat::Shortest,
+            unit_variant: UnitVariant::Name,
+            bytes_encoding: BytesEncoding::Base64,
+            style: Style::default(),
+            omit_empty: false,
+            sort_keys: false,
+            tag_variants: false,
+            capacity: 128,
+        }
+    }
+}
+
+/// Builds a `Serializer` with non-default options.
+///
+/// ```rust
+/// use serde_yaml::{SerializerBuilder, WholeFloat};
+/// let builder = SerializerBuilder::new()
+///     .float_whole_number_style(WholeFloat::Plain);
+/// assert_eq!(builder.to_string(&2.0).unwrap(), "---\n2");
+/// ```
+#[derive(Clone, Default)]
+pub struct SerializerBuilder {
+    config: Config,
+    tag_directives: Vec<(String, String)>,
+    key_transform: Option<Arc<KeyTransform>>,
+    comments: Vec<(Vec<String>, String)>,
+    scalar_styles: Option<ScalarStyles>,
+}
+
+impl fmt::Debug for SerializerBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SerializerBuilder")
+            .field("config", &self.config)
+            .field("tag_directives", &self.tag_directives)
+            .field("key_transform", &self.key_transform.is_some())
+            .field("comments", &self.comments)
+            .field("scalar_styles", &self.scalar_styles)
+            .finish()
+    }
+}
+
+impl SerializerBuilder {
+    pub fn new() -> Self {
+        SerializerBuilder::default()
+    }
+
+    /// Controls how floats with no fractional part are rendered.
+    pub fn float_whole_number_style(mut self, style: WholeFloat) -> Self {
+        self.config.whole_float = style;
+        self
+    }
+
+    /// Controls how floats are rendered.
+    ///
+    /// ```rust
+    /// use serde_yaml::{FloatFormat, SerializerBuilder};
+    /// let builder = SerializerBuilder::new()
+    ///     .float_format(FloatFormat::Fixed(3));
+    /// assert_eq!(builder.to_string(&3.14).unwrap(), "---\n3.140");
+    /// ```
+    pub fn float_format(mut self, format: FloatFormat) -> Self {
+        self.config.float_format = format;
+        self
+    }
+
+    /// Controls how unit variants of enums are rendered.
+    pub fn unit_variant_style(mut self, style: UnitVariant) -> Self {
+        self.config.unit_variant = style;
+        self
+    }
+
+    /// Controls how bytes are rendered.
+    pub fn bytes_encoding(mut self, encoding: BytesEncoding) -> Self {
+        self.config.bytes_encoding = encoding;
+        self
+    }
+
+    /// Writes newtype, tuple and struct variants of enums as the variant's
+    /// content tagged with its name, like `!Rgb [255, 0, 0]`, rather than as
+    /// a mapping from the name to the content, like `Rgb: [255, 0, 0]`. Unit
+    /// variants are unaffected. Either form can be deserialized.
+    pub fn tag_variants(mut self, enabled: bool) -> Self {
+        self.config.tag_variants = enabled;
+        self
+    }
+
+    /// Controls the line break written between lines of output.
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.config.style.line_ending = line_ending;
+        self
+    }
+
+    /// Controls how nulls are written. Every style reads back as a null.
+    pub fn null_style(mut self, style: NullStyle) -> Self {
+        self.config.style.null_style = style;
+        self
+    }
+
+    /// Controls how strings are quoted, keys included. With single or double
+    /// quotes a long string is not folded under `line_width`.
+    ///
+    /// ```rust
+    /// use serde_yaml::{QuoteStyle, SerializerBuilder};
+    /// let builder = SerializerBuilder::new().quote_style(QuoteStyle::Double);
+    /// assert_eq!(builder.to_string(&"secret").unwrap(), "---\n\"secret\"");
+    /// ```
+    pub fn quote_style(mut self, style: QuoteStyle) -> Self {
+        self.config.style.quote_style = style;
+        self
+    }
+
+    /// Writes an explicit `!!str` tag before every string, so that no parser
+    /// can resolve one to another type. Quoting is unaffected.
+    ///
+    /// ```rust
+    /// use serde_yaml::SerializerBuilder;
+    /// let builder = SerializerBuilder::new().tag_strings(true);
+    /// assert_eq!(builder.to_string(&"on").unwrap(), "---\n!!str \"on\"");
+    /// ```
+    pub fn tag_strings(mut self, enabled: bool) -> Self {
+        self.config.style.tag_strings = enabled;
+        self
+    }
+
+    /// Writes a `!!str` tag before a string that would read as a null, a
+    /// boolean or a number if it were not quoted, like a version number
+    /// `"1.10"`. Other strings are left untagged.
+    ///
+    /// ```rust
+    /// use serde_yaml::SerializerBuilder;
+    /// let builder = SerializerBuilder::new().tag_ambiguous_strings(true);
+    /// let yaml = builder.to_string(&["1.10", "v1.10"]).unwrap();
+    /// assert_eq!(yaml, "---\n- !!str \"1.10\"\n- v1.10");
+    /// ```
+    pub fn tag_ambiguous_strings(mut self, enabled: bool) -> Self {
+        self.config.style.tag_ambiguous_strings = enabled;
+        self
+    }
+
+    /// Sets the number of spaces by which each level of nesting is indented.
+    /// The default is 2. A nested block needs at least 1 to be told from its
+    /// parent, so 0 is taken as 1.
+    pub fn indent(mut self, spaces: usize) -> Self {
+        self.config.style.indent = cmp::max(spaces, 1);
+        self
+    }
+
+    /// Controls whether the items of a sequence that is a mapping value are
+    /// indented under the key, which they are by default. When disabled, the
+    /// `-` of each item lines up with the key instead.
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    /// use serde_yaml::SerializerBuilder;
+    /// let mut map = BTreeMap::new();
+    /// map.insert("k", vec![1]);
+    /// let builder = SerializerBuilder::new().indent_sequences(false);
+    /// assert_eq!(builder.to_string(&map).unwrap(), "---\nk: \n- 1");
+    /// ```
+    pub fn indent_sequences(mut self, enabled: bool) -> Self {
+        self.config.style.indent_sequences = enabled;
+        self
+    }
+
+    /// Leaves out the entries of a map or struct whose value is an empty
+    /// sequence or mapping, instead of writing them as `[]` or `{}`.
+    pub fn omit_empty_collections(mut self, enabled: bool) -> Self {
+        self.config.omit_empty = enabled;
+        self
+    }
+
+    /// Writes the entries of every map and struct in order of their keys,
+    /// rather than in the order they were serialized, so that the output of
+    /// a `HashMap` is the same from one run to the next.
+    ///
+    /// Without it, entries are written in the order the map gives them: a
+    /// `BTreeMap` in order of its keys, a `Mapping` in the order its entries
+    /// were inserted, a struct in the order of its fields, and a `HashMap` in
+    /// an order that may change between runs.
+    ///
+    /// Keys are ordered as the `Value`s they read back as: null, then
+    /// booleans, numbers, strings, sequences and finally mappings. Booleans
+    /// put `false` first, numbers are ordered by value whether integer or
+    /// float with NaN last, strings by their bytes, and collections element
+    /// by element.
+    pub fn sort_keys(mut self, enabled: bool) -> Self {
+        self.config.sort_keys = enabled;
+        self
+    }
+
+    /// Writes canonical YAML, in which every node is in flow style with its
+    /// tag written out and every scalar is quoted. The output is verbose but
+    /// does not depend on how a parser resolves plain scalars.
+    pub fn canonical(mut self, enabled: bool) -> Self {
+        self.config.style.canonical = enabled;
+        self
+    }
+
+    /// Folds a string that would reach past the given column onto several
+    /// lines, as a folded block scalar that reads back as the same string.
+    /// Strings with leading or trailing spaces, or with control characters,
+    /// are left on one line, as is a string with no single space to break at.
+    /// There is no limit by default.
+    pub fn line_width(mut self, width: usize) -> Self {
+        self.config.style.line_width = Some(width);
+        self
+    }
+
+    /// Writes a string key longer than the given number of characters as an
+    /// explicit key, on its own line after `?`, with its value on the next
+    /// line after `:`. Keys too long for the YAML spec to allow as implicit
+    /// keys are written this way regardless.
+    pub fn explicit_key_length(mut self, chars: usize) -> Self {
+        self.config.style.explicit_key_length = Some(chars);
+        self
+    }
+
+    /// Controls whether the output starts with the `---` document marker,
+    /// which it does by default.
+    pub fn explicit_start(mut self, enabled: bool) -> Self {
+        self.config.style.explicit_start = enabled;
+        self
+    }
+
+    /// Controls whether the output ends with the `...` document marker,
+    /// which it does not by default.
+    pub fn explicit_end(mut self, enabled: bool) -> Self {
+        self.config.style.explicit_end = enabled;
+        self
+    }
+
+    /// Writes JSON, which YAML parsers read too: every collection in flow
+    /// style on one line, every string double-quoted, and no tags or
+    /// document markers. Keys that are not strings are written as strings,
+    /// and serializing a collection as a key fails. Floats that JSON cannot
+    /// represent, like infinity, are written as `null`, and the values of
+    /// `radix` and `timestamp` fields as strings.
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    /// use serde_yaml::SerializerBuilder;
+    /// let mut map = BTreeMap::new();
+    /// map.insert(1, vec!["a", "b"]);
+    /// let builder = SerializerBuilder::new().json_compatible(true);
+    /// assert_eq!(builder.to_string(&map).unwrap(), r#"{"1": ["a", "b"]}"#);
+    /// ```
+    pub fn json_compatible(mut self, enabled: bool) -> Self {
+        self.config.style.json = enabled;
+        self
+    }
+
+    /// Writes a `%TAG` directive declaring `handle`, like `!e!`, as short
+    /// for `prefix`, like `tag:example.com,2000:`, and writes each tag that
+    /// starts with `prefix` in that shorthand. The document then starts with
+    /// `---` whether or not `explicit_start` is set.
+    ///
+    /// ```rust
+    /// use serde_yaml::{SerializerBuilder, TaggedValue, Value};
+    /// let builder = SerializerBuilder::new()
+    ///     .tag_directive("!e!", "tag:example.com,2000:");
+    /// let tagged = TaggedValue {
+    ///     tag: String::from("<tag:example.com,2000:color>"),
+    ///     value: Value::String(String::from("red")),
+    /// };
+    /// assert_eq!(builder.to_string(&tagged).unwrap(),
+    ///            "%TAG !e! tag:example.com,2000:\n---\n!e!color red");
+    /// ```
+    pub fn tag_directive(mut self, handle: &str, prefix: &str) -> Self {
+        self.tag_directives.push((handle.to_owned(), prefix.to_owned()));
+        self
+    }
+
+    /// Reserves room for this many bytes of output up front in `to_vec` and
+    /// `to_string`, so that a large document is not copied as its buffer
+    /// grows. The default is 128. Nodes are already sized by the lengths
+    /// that serde gives for sequences and maps.
+    pub fn capacity(mut self, bytes: usize) -> Self {
+        self.config.capacity = bytes;
+        self
+    }
+
+    /// Rewrites every string key of every mapping as it is written, struct
+    /// fields included, after any renaming that serde does. Keys are quoted
+    /// as the rewritten strings need. Two keys that are rewritten to the same
+    /// string are both written.
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    /// use serde_yaml::SerializerBuilder;
+    /// let builder = SerializerBuilder::new()
+    ///     .key_transform(|key| format!("app.{}", key));
+    /// let mut map = BTreeMap::new();
+    /// map.insert("port", 80);
+    /// assert_eq!(builder.to_string(&map).unwrap(), "---\napp.port: 80");
+    /// ```
+    pub fn key_transform<F>(mut self, transform: F) -> Self
+        where F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.key_transform = Some(Arc::new(transform));
+        self
+    }
+
+    /// Writes `comment` as `#` lines above the mapping key at the end of
+    /// `path`, which names a key of each nested mapping in turn, or the index
+    /// of an element for a sequence. Struct fields are named as they are
+    /// written, before any `key_transform`. A comment with several lines is
+    /// written as several `#` lines, and comments are left out of JSON and
+    /// canonical output.
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    /// use serde_yaml::SerializerBuilder;
+    /// let builder = SerializerBuilder::new()
+    ///     .comment(&["server", "port"], "The port to listen on");
+    /// let mut server = BTreeMap::new();
+    /// server.insert("port", 80);
+    /// let mut map = BTreeMap::new();
+    /// map.insert("server", server);
+    /// let expected = "---\nserver: \n  # The port to listen on\n  port: 80";
+    /// assert_eq!(builder.to_string(&map).unwrap(), expected);
+    /// ```
+    pub fn comment(mut self, path: &[&str], comment: &str) -> Self {
+        let path = path.iter().map(|step| step.to_string()).collect();
+        self.comments.push((path, comment.to_owned()));
+        self
+    }
+
+    /// Writes each string at a path in `styles` in the style given for it,
+    /// where that style can hold the string, as a `Value` read with
+    /// `DeserializerBuilder::from_str_with_styles` was written. Paths are
+    /// matched as for `comment`. Strings in flow collections, and all strings
+    /// in JSON or canonical output, are written as usual.
+    pub fn scalar_styles(mut self, styles: ScalarStyles) -> Self {
+        self.scalar_styles = Some(styles);
+        self
+    }
+
+    pub fn build(&self) -> Serializer {
+        Serializer {
+            doc: Yaml::Null,
+            config: self.config,
+        }
+    }
+
+    /// Writes the YAML for `value`. A top-level sequence is written one
+    /// element at a time as it is serialized, so if an element fails to
+    /// serialize the elements before it have already been written. The
+    /// writer is flushed once the document is complete.
+    pub fn to_writer<W, T>(&self, writer: &mut W, value: &T) -> Result<()>
+        where W: io::Write,
+              T: ser::Serialize,
+    {
+        let mut writer_adapter = FmtToIoWriter {
+            writer: writer,
+            written: 0,
+            error: None,
+        };
+        let result = self.to_fmt_writer(&mut writer_adapter, value);
+        // The emitter only sees a `fmt::Error`, so report the `io::Error`
+        // behind it instead, along with how much was written before it.
+        let written = writer_adapter.written;
+        if let Some(err) = writer_adapter.error {
+            return Err(Error::Write(err, written));
+        }
+        try!(result);
+        writer_adapter.writer.flush().map_err(|err| Error::Write(err, written))
+    }
+
+    /// Writes the YAML for `value` to a `fmt::Write`, such as a `String`
+    /// being built up, as `to_writer` does to an `io::Write`. The emitter
+    /// writes `str`s, so nothing is encoded to bytes or checked again.
+    ///
+    /// ```rust
+    /// use serde_yaml::SerializerBuilder;
+    /// let mut out = String::from("# generated\n");
+    /// SerializerBuilder::new().to_fmt_writer(&mut out, &vec![1, 2]).unwrap();
+    /// assert_eq!(out, "# generated\n---\n- 1\n- 2");
+    /// ```
+    pub fn to_fmt_writer<W, T>(&self, writer: &mut W, value: &T) -> Result<()>
+        where W: fmt::Write,
+              T: ser::Serialize,
+    {
+        let mut streamer = SeqStreamer {
+            inner: self.build(),
+            emitter: Emitter::new(writer, self.config.style)
+                .with_tag_directives(&self.tag_directives)
+                .with_comments(&self.comments)
+                .with_scalar_styles(self.scalar_styles.as_ref())
+                .with_key_transform(self.key_transform
+                    .as_ref()
+                    .map(|transform| &**transform)),
+            streamed: false,
+        };
+        try!(value.serialize(&mut streamer));
+        if streamer.streamed {
+            Ok(())
+        } else {
+            streamer.emitter.dump(&streamer.inner.doc).map_err(Error::from)
+        }
+    }
+
+    pub fn to_vec<T>(&self, value: &T) -> Result<Vec<u8>>
+        where T: ser::Serialize,
+    {
+        let mut vec = Vec::with_capacity(self.config.capacity);
+        try!(self.to_writer(&mut vec, value));
+        Ok(vec)
+    }
+
+    /// Returns a writer that appends each value it is given to `writer` as
+    /// another document of a stream, with these options.
+    pub fn multi_doc_writer<W>(&self, writer: W) -> MultiDocWriter<W>
+        where W: io::Write,
+    {
+        let mut builder = self.clone();
+        // A document has to end with `...` for the directives of the next
+        // one to be read as directives.
+        if !builder.tag_directives.is_empty() {
+            builder = builder.explicit_end(true);
+        }
+        MultiDocWriter {
+            writer: writer,
+            builder: builder,
+            count: 0,
+        }
+    }
+
+    /// Writes the YAML for `value` to a string. The builder is borrowed, so
+    /// one set of options can be applied to any number of values.
+    pub fn to_string<T>(&self, value: &T) -> Result<String>
+        where T: ser::Serialize,
+    {
+        let mut string = String::with_capacity(self.config.capacity);
+        try!(self.to_fmt_writer(&mut string, value));
+        Ok(string)
+    }
+}
+
 impl ser::Serializer for Serializer {
     type Error = Error;
     type SeqState = yaml::Array;
@@ -80,7 +733,7 @@ impl ser::Serializer for Serializer {
     }
 
     fn serialize_usize(&mut self, v: usize) -> Result<()> {
-        self.serialize_i64(v as i64)
+        self.serialize_u64(v as u64)
     }
 
     fn serialize_u8(&mut self, v: u8) -> Result<()> {
@@ -96,17 +749,20 @@ impl ser::Serializer for Serializer {
     }
 
     fn serialize_u64(&mut self, v: u64) -> Result<()> {
+        if v > i64::MAX as u64 {
+            // Too big for `Yaml::Integer`, but written just the same.
+            self.doc = Yaml::Real(v.to_string());
+            return Ok(());
+        }
         self.serialize_i64(v as i64)
     }
 
     fn serialize_f32(&mut self, v: f32) -> Result<()> {
-        self.doc = Yaml::Real(v.to_string());
-        Ok(())
+        self.serialize_real(v)
     }
 
     fn serialize_f64(&mut self, v: f64) -> Result<()> {
-        self.doc = Yaml::Real(v.to_string());
-        Ok(())
+        self.serialize_real(v)
     }
 
     fn serialize_char(&mut self, value: char) -> Result<()> {
@@ -120,11 +776,23 @@ impl ser::Serializer for Serializer {
     }
 
     fn serialize_bytes(&mut self, value: &[u8]) -> Result<()> {
-        let mut state = try!(self.serialize_seq(Some(value.len())));
-        for c in value {
-            try!(self.serialize_seq_elt(&mut state, c));
-        }
-        self.serialize_seq_end(state)
+        self.doc = match self.config.bytes_encoding {
+            BytesEncoding::IntArray => {
+                Yaml::Array(value.iter()
+                                .map(|&b| Yaml::Integer(b as i64))
+                                .collect())
+            }
+            BytesEncoding::Base64 => {
+                tagged::node(String::from(tagged::BINARY_TAG),
+                             Yaml::String(base64::encode(value)))
+            }
+            BytesEncoding::Hex => {
+                Yaml::String(value.iter()
+                                 .map(|b| format!("{:02x}", b))
+                                 .collect())
+            }
+        };
+        Ok(())
     }
 
     fn serialize_unit(&mut self) -> Result<()> {
@@ -140,21 +808,54 @@ impl ser::Serializer for Serializer {
     fn serialize_unit_variant(
         &mut self,
         _name: &str,
-        _variant_index: usize,
+        variant_index: usize,
         variant: &str
     ) -> Result<()> {
-        self.doc = Yaml::String(String::from(variant));
+        self.doc = match self.config.unit_variant {
+            UnitVariant::Name => Yaml::String(String::from(variant)),
+            UnitVariant::Index => Yaml::Integer(variant_index as i64),
+        };
         Ok(())
     }
 
     fn serialize_newtype_struct<T>(
         &mut self,
-        _name: &'static str,
+        name: &'static str,
         value: T
     ) -> Result<()>
         where T: ser::Serialize,
     {
-        value.serialize(self)
+        try!(value.serialize(self));
+        if name == PLAIN_TOKEN {
+            // The emitter writes reals verbatim, which is also what a plain
+            // scalar needs. See `PLAIN_TOKEN` for what that costs.
+            if let Yaml::String(repr) = mem::replace(&mut self.doc, Yaml::Null) {
+                self.doc = Yaml::Real(repr);
+            }
+        } else if name == TAGGED_TOKEN {
+            let pair = mem::replace(&mut self.doc, Yaml::Null);
+            self.doc = tagged::from_pair(pair);
+        } else if name == SET_TOKEN {
+            self.doc = match mem::replace(&mut self.doc, Yaml::Null) {
+                Yaml::Array(members) => {
+                    let set = members.into_iter()
+                        .map(|member| (member, Yaml::Null))
+                        .collect();
+                    tagged::node(String::from(tagged::SET_TAG), Yaml::Hash(set))
+                }
+                other => other,
+            };
+        } else if name == SINGLETON_MAP_TOKEN {
+            let doc = mem::replace(&mut self.doc, Yaml::Null);
+            let variant = tagged::split(&doc).map(|(tag, _)| tag.to_owned());
+            self.doc = match variant {
+                Some(variant) => {
+                    singleton_hash(Yaml::String(variant), tagged::untag(doc))
+                }
+                None => doc,
+            };
+        }
+        Ok(())
     }
 
     fn serialize_newtype_variant<T>(
@@ -166,7 +867,8 @@ impl ser::Serializer for Serializer {
     ) -> Result<()>
         where T: ser::Serialize,
     {
-        self.doc = singleton_hash(try!(to_yaml(variant)), try!(to_yaml(value)));
+        let content = try!(self.to_yaml(value));
+        self.doc = try!(self.variant(variant, content));
         Ok(())
     }
 
@@ -182,6 +884,8 @@ impl ser::Serializer for Serializer {
 
     fn serialize_seq(&mut self, len: Option<usize>) -> Result<yaml::Array> {
         Ok(match len {
+            // Doubles its capacity whenever it fills, so an unknown length
+            // costs a reallocation per doubling rather than per element.
             None => yaml::Array::new(),
             Some(len) => yaml::Array::with_capacity(len),
         })
@@ -194,7 +898,7 @@ impl ser::Serializer for Serializer {
     ) -> Result<()>
         where T: ser::Serialize,
     {
-        state.push(try!(to_yaml(elem)));
+        state.push(try!(self.to_yaml(elem)));
         Ok(())
     }
 
@@ -273,13 +977,16 @@ impl ser::Serializer for Serializer {
         state: (&'static str, yaml::Array)
     ) -> Result<()> {
         try!(self.serialize_seq_end(state.1));
-        self.doc = singleton_hash(try!(to_yaml(state.0)),
-                                  mem::replace(&mut self.doc, Yaml::Null));
+        let content = mem::replace(&mut self.doc, Yaml::Null);
+        self.doc = try!(self.variant(state.0, content));
         Ok(())
     }
 
-    fn serialize_map(&mut self, _len: Option<usize>) -> Result<(Option<yaml::Yaml>, yaml::Hash)> {
-        Ok((None, yaml::Hash::new()))
+    fn serialize_map(&mut self, len: Option<usize>) -> Result<(Option<yaml::Yaml>, yaml::Hash)> {
+        Ok((None, match len {
+            None => yaml::Hash::new(),
+            Some(len) => yaml::Hash::with_capacity(len),
+        }))
     }
 
     fn serialize_map_key<T>(
@@ -289,7 +996,10 @@ impl ser::Serializer for Serializer {
     ) -> Result<()>
         where T: ser::Serialize
     {
-        state.0 = Some(try!(to_yaml(key)));
+        if state.0.is_some() {
+            return Err(Error::MapKeyWithoutValue);
+        }
+        state.0 = Some(try!(self.to_yaml(key)));
         Ok(())
     }
 
@@ -300,18 +1010,37 @@ impl ser::Serializer for Serializer {
     ) -> Result<()>
         where T: ser::Serialize
     {
-        match state.0.take() {
-            Some(key) => state.1.insert(key, try!(to_yaml(value))),
-            None => {
-                return Err(Error::Custom("serialize_map_value called without matching \
-                                          serialize_map_key call".to_owned()));
-            }
+        let key = match state.0.take() {
+            Some(key) => key,
+            None => return Err(Error::MapValueWithoutKey),
+        };
+        let value = try!(self.to_yaml(value));
+        let empty = match value {
+            Yaml::Array(ref v) => v.is_empty(),
+            Yaml::Hash(ref h) => h.is_empty(),
+            _ => false,
         };
+        if !(empty && self.config.omit_empty) {
+            state.1.insert(key, value);
+        }
         Ok(())
     }
 
     fn serialize_map_end(&mut self, state: (Option<yaml::Yaml>, yaml::Hash)) -> Result<()> {
-        self.doc = Yaml::Hash(state.1);
+        if state.0.is_some() {
+            return Err(Error::MapKeyWithoutValue);
+        }
+        let mut hash = state.1;
+        if self.config.sort_keys {
+            // Keys are ordered as the `Value`s they read back as, so that a
+            // `0o644` from `radix` sorts as the integer it is.
+            let mut entries: Vec<_> = hash.into_iter()
+                .map(|(k, v)| (Value::from(k.clone()), k, v))
+                .collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            hash = entries.into_iter().map(|(_, k, v)| (k, v)).collect();
+        }
+        self.doc = Yaml::Hash(hash);
         Ok(())
     }
 
@@ -367,65 +1096,435 @@ impl ser::Serializer for Serializer {
         state: (&'static str, (Option<yaml::Yaml>, yaml::Hash))
     ) -> Result<()> {
         try!(self.serialize_map_end(state.1));
-        self.doc = singleton_hash(try!(to_yaml(state.0)),
-                                  mem::replace(&mut self.doc, Yaml::Null));
+        let content = mem::replace(&mut self.doc, Yaml::Null);
+        self.doc = try!(self.variant(state.0, content));
         Ok(())
     }
 }
 
+/// Serializes the elements of a top-level sequence straight to the emitter.
+/// Every other value is handed to `inner` and emitted once it is complete.
+struct SeqStreamer<'a, W: 'a> {
+    inner: Serializer,
+    emitter: Emitter<'a, W>,
+    /// Whether the value was a sequence and has been written already.
+    streamed: bool,
+}
+
+impl<'a, W> ser::Serializer for SeqStreamer<'a, W>
+    where W: fmt::Write,
+{
+    type Error = Error;
+    type SeqState = usize;
+    type TupleState = yaml::Array;
+    type TupleStructState = yaml::Array;
+    type TupleVariantState = (&'static str, yaml::Array);
+    type MapState = (Option<yaml::Yaml>, yaml::Hash);
+    type StructState = (Option<yaml::Yaml>, yaml::Hash);
+    type StructVariantState = (&'static str, (Option<yaml::Yaml>, yaml::Hash));
+
+    fn serialize_bool(&mut self, v: bool) -> Result<()> {
+        self.inner.serialize_bool(v)
+    }
+
+    fn serialize_isize(&mut self, v: isize) -> Result<()> {
+        self.inner.serialize_isize(v)
+    }
+
+    fn serialize_i8(&mut self, v: i8) -> Result<()> {
+        self.inner.serialize_i8(v)
+    }
+
+    fn serialize_i16(&mut self, v: i16) -> Result<()> {
+        self.inner.serialize_i16(v)
+    }
+
+    fn serialize_i32(&mut self, v: i32) -> Result<()> {
+        self.inner.serialize_i32(v)
+    }
+
+    fn serialize_i64(&mut self, v: i64) -> Result<()> {
+        self.inner.serialize_i64(v)
+    }
+
+    fn serialize_usize(&mut self, v: usize) -> Result<()> {
+        self.inner.serialize_usize(v)
+    }
+
+    fn serialize_u8(&mut self, v: u8) -> Result<()> {
+        self.inner.serialize_u8(v)
+    }
+
+    fn serialize_u16(&mut self, v: u16) -> Result<()> {
+        self.inner.serialize_u16(v)
+    }
+
+    fn serialize_u32(&mut self, v: u32) -> Result<()> {
+        self.inner.serialize_u32(v)
+    }
+
+    fn serialize_u64(&mut self, v: u64) -> Result<()> {
+        self.inner.serialize_u64(v)
+    }
+
+    fn serialize_f32(&mut self, v: f32) -> Result<()> {
+        self.inner.serialize_f32(v)
+    }
+
+    fn serialize_f64(&mut self, v: f64) -> Result<()> {
+        self.inner.serialize_f64(v)
+    }
+
+    fn serialize_char(&mut self, value: char) -> Result<()> {
+        self.inner.serialize_char(value)
+    }
+
+    fn serialize_str(&mut self, value: &str) -> Result<()> {
+        self.inner.serialize_str(value)
+    }
+
+    fn serialize_bytes(&mut self, value: &[u8]) -> Result<()> {
+        self.inner.serialize_bytes(value)
+    }
+
+    fn serialize_unit(&mut self) -> Result<()> {
+        self.inner.serialize_unit()
+    }
+
+    fn serialize_unit_struct(&mut self, name: &'static str) -> Result<()> {
+        self.inner.serialize_unit_struct(name)
+    }
+
+    fn serialize_unit_variant(
+        &mut self,
+        name: &'static str,
+        variant_index: usize,
+        variant: &'static str
+    ) -> Result<()> {
+        self.inner.serialize_unit_variant(name, variant_index, variant)
+    }
+
+    fn serialize_newtype_struct<T>(
+        &mut self,
+        name: &'static str,
+        value: T
+    ) -> Result<()>
+        where T: ser::Serialize,
+    {
+        self.inner.serialize_newtype_struct(name, value)
+    }
+
+    fn serialize_newtype_variant<T>(
+        &mut self,
+        name: &'static str,
+        variant_index: usize,
+        variant: &'static str,
+        value: T
+    ) -> Result<()>
+        where T: ser::Serialize,
+    {
+        self.inner.serialize_newtype_variant(name, variant_index, variant,
+                                             value)
+    }
+
+    fn serialize_none(&mut self) -> Result<()> {
+        self.inner.serialize_none()
+    }
+
+    fn serialize_some<V>(&mut self, value: V) -> Result<()>
+        where V: ser::Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(&mut self, _len: Option<usize>) -> Result<usize> {
+        try!(self.emitter.begin_seq());
+        self.streamed = true;
+        Ok(0)
+    }
+
+    fn serialize_seq_elt<T>(&mut self, state: &mut usize, elem: T) -> Result<()>
+        where T: ser::Serialize,
+    {
+        let elem = try!(self.inner.to_yaml(elem));
+        try!(self.emitter.emit_seq_elt(*state, &elem));
+        *state += 1;
+        Ok(())
+    }
+
+    fn serialize_seq_end(&mut self, state: usize) -> Result<()> {
+        try!(self.emitter.end_seq(state));
+        Ok(())
+    }
+
+    fn serialize_seq_fixed_size(&mut self, len: usize) -> Result<usize> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple(&mut self, len: usize) -> Result<yaml::Array> {
+        self.inner.serialize_tuple(len)
+    }
+
+    fn serialize_tuple_elt<T>(
+        &mut self,
+        state: &mut yaml::Array,
+        elem: T
+    ) -> Result<()>
+        where T: ser::Serialize,
+    {
+        self.inner.serialize_tuple_elt(state, elem)
+    }
+
+    fn serialize_tuple_end(&mut self, state: yaml::Array) -> Result<()> {
+        self.inner.serialize_tuple_end(state)
+    }
+
+    fn serialize_tuple_struct(
+        &mut self,
+        name: &'static str,
+        len: usize
+    ) -> Result<yaml::Array> {
+        self.inner.serialize_tuple_struct(name, len)
+    }
+
+    fn serialize_tuple_struct_elt<V>(
+        &mut self,
+        state: &mut yaml::Array,
+        value: V
+    ) -> Result<()>
+        where V: ser::Serialize,
+    {
+        self.inner.serialize_tuple_struct_elt(state, value)
+    }
+
+    fn serialize_tuple_struct_end(&mut self, state: yaml::Array) -> Result<()> {
+        self.inner.serialize_tuple_struct_end(state)
+    }
+
+    fn serialize_tuple_variant(
+        &mut self,
+        name: &'static str,
+        idx: usize,
+        variant: &'static str,
+        len: usize
+    ) -> Result<(&'static str, yaml::Array)> {
+        self.inner.serialize_tuple_variant(name, idx, variant, len)
+    }
+
+    fn serialize_tuple_variant_elt<V>(
+        &mut self,
+        state: &mut (&'static str, yaml::Array),
+        v: V
+    ) -> Result<()>
+        where V: ser::Serialize,
+    {
+        self.inner.serialize_tuple_variant_elt(state, v)
+    }
+
+    fn serialize_tuple_variant_end(
+        &mut self,
+        state: (&'static str, yaml::Array)
+    ) -> Result<()> {
+        self.inner.serialize_tuple_variant_end(state)
+    }
+
+    fn serialize_map(
+        &mut self,
+        len: Option<usize>
+    ) -> Result<(Option<yaml::Yaml>, yaml::Hash)> {
+        self.inner.serialize_map(len)
+    }
+
+    fn serialize_map_key<T>(
+        &mut self,
+        state: &mut (Option<yaml::Yaml>, yaml::Hash),
+        key: T
+    ) -> Result<()>
+        where T: ser::Serialize
+    {
+        self.inner.serialize_map_key(state, key)
+    }
+
+    fn serialize_map_value<T>(
+        &mut self,
+        state: &mut (Option<yaml::Yaml>, yaml::Hash),
+        value: T
+    ) -> Result<()>
+        where T: ser::Serialize
+    {
+        self.inner.serialize_map_value(state, value)
+    }
+
+    fn serialize_map_end(
+        &mut self,
+        state: (Option<yaml::Yaml>, yaml::Hash)
+    ) -> Result<()> {
+        self.inner.serialize_map_end(state)
+    }
+
+    fn serialize_struct(
+        &mut self,
+        name: &'static str,
+        len: usize
+    ) -> Result<(Option<yaml::Yaml>, yaml::Hash)> {
+        self.inner.serialize_struct(name, len)
+    }
+
+    fn serialize_struct_elt<V>(
+        &mut self,
+        state: &mut (Option<yaml::Yaml>, yaml::Hash),
+        key: &'static str,
+        value: V
+    ) -> Result<()>
+        where V: ser::Serialize,
+    {
+        self.inner.serialize_struct_elt(state, key, value)
+    }
+
+    fn serialize_struct_end(
+        &mut self,
+        state: (Option<yaml::Yaml>, yaml::Hash)
+    ) -> Result<()> {
+        self.inner.serialize_struct_end(state)
+    }
+
+    fn serialize_struct_variant(
+        &mut self,
+        name: &'static str,
+        idx: usize,
+        variant: &'static str,
+        len: usize
+    ) -> Result<(&'static str, (Option<yaml::Yaml>, yaml::Hash))> {
+        self.inner.serialize_struct_variant(name, idx, variant, len)
+    }
+
+    fn serialize_struct_variant_elt<V>(
+        &mut self,
+        state: &mut (&'static str, (Option<yaml::Yaml>, yaml::Hash)),
+        field: &'static str,
+        v: V
+    ) -> Result<()>
+        where V: ser::Serialize,
+    {
+        self.inner.serialize_struct_variant_elt(state, field, v)
+    }
+
+    fn serialize_struct_variant_end(
+        &mut self,
+        state: (&'static str, (Option<yaml::Yaml>, yaml::Hash))
+    ) -> Result<()> {
+        self.inner.serialize_struct_variant_end(state)
+    }
+}
+
 pub fn to_writer<W, T>(writer: &mut W, value: &T) -> Result<()>
     where W: io::Write,
           T: ser::Serialize,
 {
-    let doc = try!(to_yaml(value));
-    let mut writer_adapter = FmtToIoWriter {
-        writer: writer,
-    };
-    try!(YamlEmitter::new(&mut writer_adapter).dump(&doc));
-    Ok(())
+    SerializerBuilder::new().to_writer(writer, value)
+}
+
+/// Serializes `value` into a `fmt::Write` as a YAML document.
+pub fn to_fmt_writer<W, T>(writer: &mut W, value: &T) -> Result<()>
+    where W: fmt::Write,
+          T: ser::Serialize,
+{
+    SerializerBuilder::new().to_fmt_writer(writer, value)
 }
 
 pub fn to_vec<T>(value: &T) -> Result<Vec<u8>>
     where T: ser::Serialize,
 {
-    let mut vec = Vec::with_capacity(128);
-    try!(to_writer(&mut vec, value));
-    Ok(vec)
+    SerializerBuilder::new().to_vec(value)
 }
 
+/// Serializes `value` as a YAML document. The document can be any value, so a
+/// scalar or a sequence is written on its own like a mapping is.
+///
+/// ```rust
+/// assert_eq!(serde_yaml::to_string(&42).unwrap(), "---\n42");
+/// assert_eq!(serde_yaml::to_string(&vec![1, 2]).unwrap(), "---\n- 1\n- 2");
+/// assert_eq!(serde_yaml::to_string(&()).unwrap(), "---\n~");
+/// ```
 pub fn to_string<T>(value: &T) -> Result<String>
     where T: ser::Serialize,
 {
-    Ok(try!(String::from_utf8(try!(to_vec(value)))))
+    SerializerBuilder::new().to_string(value)
+}
+
+/// Writes values one at a time as the documents of a YAML stream, so that a
+/// stream can be built up without holding all of it in memory.
+///
+/// ```rust
+/// let mut writer = serde_yaml::MultiDocWriter::new(Vec::new());
+/// writer.write(&1).unwrap();
+/// writer.write(&"two").unwrap();
+/// let yaml = String::from_utf8(writer.into_inner()).unwrap();
+/// assert_eq!(yaml, "---\n1\n---\ntwo");
+/// ```
+pub struct MultiDocWriter<W> {
+    writer: W,
+    builder: SerializerBuilder,
+    count: usize,
+}
+
+impl<W> MultiDocWriter<W>
+    where W: io::Write,
+{
+    pub fn new(writer: W) -> Self {
+        SerializerBuilder::new().multi_doc_writer(writer)
+    }
+
+    /// Writes `value` as the next document and flushes the writer. Every
+    /// document after the first starts with `---` on a line of its own, even
+    /// if the builder leaves out the marker.
+    pub fn write<T>(&mut self, value: &T) -> Result<()>
+        where T: ser::Serialize,
+    {
+        let mut builder = self.builder.clone();
+        if self.count > 0 {
+            let line_break = match builder.config.style.line_ending {
+                LineEnding::Lf => "\n",
+                LineEnding::CrLf => "\r\n",
+            };
+            try!(self.writer.write_all(line_break.as_bytes()));
+            builder = builder.explicit_start(true);
+        }
+        try!(builder.to_writer(&mut self.writer, value));
+        self.count += 1;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
 }
 
 /// The yaml-rust library uses `fmt.Write` intead of `io.Write` so this is a
-/// simple adapter.
+/// simple adapter. It keeps the first `io::Error` so that it can be
+/// returned in place of the `fmt::Error` the emitter sees.
 struct FmtToIoWriter<'a, W>
     where W: io::Write + 'a,
 {
     writer: &'a mut W,
+    /// Bytes written so far, not counting a write that failed partway.
+    written: usize,
+    error: Option<io::Error>,
 }
 
 impl<'a, W> fmt::Write for FmtToIoWriter<'a, W>
     where W: io::Write + 'a,
 {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        if self.writer.write(s.as_bytes()).is_err() {
+        if let Err(err) = self.writer.write_all(s.as_bytes()) {
+            self.error = Some(err);
             return Err(fmt::Error);
         }
+        self.written += s.len();
         Ok(())
     }
 }
 
-fn to_yaml<T>(elem: T) -> Result<Yaml>
-    where T: ser::Serialize,
-{
-    let mut ser = Serializer::new();
-    try!(elem.serialize(&mut ser));
-    Ok(ser.take())
-}
-
 fn singleton_hash(k: Yaml, v: Yaml) -> Yaml {
     let mut hash = yaml::Hash::new();
     hash.insert(k, v);
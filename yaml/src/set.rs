@@ -0,0 +1,35 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Sets as YAML `!!set` mappings
+//!
+//! A set is serialized as a sequence unless this function is used on the
+//! field:
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Package {
+//!     #[serde(serialize_with = "serde_yaml::set::serialize")]
+//!     features: BTreeSet<String>,
+//! }
+//! ```
+//!
+//! The set is then written as a mapping tagged `!!set` whose keys are the
+//! members and whose values are null. Reading a `!!set` into a `HashSet`,
+//! `BTreeSet` or any other sequence type works without this function.
+
+use serde::ser;
+
+use super::ser::SET_TOKEN;
+
+pub fn serialize<T, S>(set: &T, serializer: &mut S) -> Result<(), S::Error>
+    where T: ser::Serialize,
+          S: ser::Serializer,
+{
+    serializer.serialize_newtype_struct(SET_TOKEN, set)
+}
@@ -0,0 +1,48 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Enums as mappings from the variant name to the content
+//!
+//! With `SerializerBuilder::tag_variants`, a variant with content is written
+//! as its content tagged with the variant name, like `!Rgb [255, 0, 0]`. Use
+//! these functions on a field to write its enum as a mapping with a single
+//! key, like `Rgb: [255, 0, 0]`, whatever the serializer's options:
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Theme {
+//!     #[serde(serialize_with = "serde_yaml::singleton_map::serialize",
+//!             deserialize_with = "serde_yaml::singleton_map::deserialize")]
+//!     background: Color,
+//! }
+//! ```
+//!
+//! Only the variant of the field itself is affected; enums within its
+//! content are written as the options say. Unit variants are written as
+//! their name either way. Serializers other than this crate's already write
+//! enums this way.
+
+use serde::{de, ser};
+
+use super::ser::SINGLETON_MAP_TOKEN;
+
+pub fn serialize<T, S>(value: &T, serializer: &mut S) -> Result<(), S::Error>
+    where T: ser::Serialize,
+          S: ser::Serializer,
+{
+    serializer.serialize_newtype_struct(SINGLETON_MAP_TOKEN, value)
+}
+
+/// Reads the enum from a mapping with a single key, or from any other form
+/// it could be read from without this function.
+pub fn deserialize<T, D>(deserializer: &mut D) -> Result<T, D::Error>
+    where T: de::Deserialize,
+          D: de::Deserializer,
+{
+    T::deserialize(deserializer)
+}
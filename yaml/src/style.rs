@@ -0,0 +1,142 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Keeping the styles of scalars across a round trip.
+//!
+//! A `Value` holds only what a scalar means, not how it was written, so the
+//! styles are kept beside it by the path to each scalar, in the way that
+//! `SerializerBuilder::comment` names keys.
+
+use std::collections::BTreeMap;
+
+use super::error::Result;
+use super::event::{Event, Events, ScalarStyle};
+
+/// The styles that the scalars of a document are written in, by the path to
+/// each. A path names each mapping key or sequence index on the way to a
+/// scalar, as written in the source.
+///
+/// `DeserializerBuilder::from_str_with_styles` finds the styles of a
+/// document, and `SerializerBuilder::scalar_styles` writes the strings at the
+/// same paths in the same styles. Only quoted and block scalars are kept,
+/// since plain is how a string is written unless it needs quotes.
+///
+/// ```rust
+/// use serde_yaml::{ScalarStyle, ScalarStyles, SerializerBuilder};
+/// let mut styles = ScalarStyles::new();
+/// styles.insert(&["0"], ScalarStyle::Literal);
+/// let builder = SerializerBuilder::new().scalar_styles(styles);
+/// let yaml = builder.to_string(&vec!["one\ntwo"]).unwrap();
+/// assert_eq!(yaml, "---\n- |-\n  one\n  two");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScalarStyles {
+    styles: BTreeMap<Vec<String>, ScalarStyle>,
+}
+
+impl ScalarStyles {
+    pub fn new() -> Self {
+        ScalarStyles::default()
+    }
+
+    /// The style of the scalar at `path`, or `None` if it is written as
+    /// usual.
+    pub fn get<S>(&self, path: &[S]) -> Option<ScalarStyle>
+        where S: AsRef<str>,
+    {
+        self.styles.get(&owned_path(path)).cloned()
+    }
+
+    /// Sets the style of the scalar at `path`. A string that the style cannot
+    /// hold, like a multi-line string in single quotes, is written as usual.
+    pub fn insert<S>(&mut self, path: &[S], style: ScalarStyle)
+        where S: AsRef<str>,
+    {
+        self.styles.insert(owned_path(path), style);
+    }
+
+    pub fn len(&self) -> usize {
+        self.styles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.styles.is_empty()
+    }
+}
+
+fn owned_path<S>(path: &[S]) -> Vec<String>
+    where S: AsRef<str>,
+{
+    path.iter().map(|step| step.as_ref().to_owned()).collect()
+}
+
+/// A collection enclosing the node being read.
+enum Frame {
+    /// A sequence, with the index of its next item.
+    Seq(usize),
+    /// A mapping whose next node is a key.
+    Key,
+    /// A mapping whose next node is the value of this key, which is `None`
+    /// if the key is not a scalar.
+    Value(Option<String>),
+}
+
+/// Finds the styles of the scalars in the first document of `events`. The
+/// scalars inside a key that is a collection have no path, and are left out.
+pub fn collect(events: Events) -> Result<ScalarStyles> {
+    let mut styles = ScalarStyles::new();
+    let mut stack = Vec::new();
+    for event in events {
+        match try!(event).0 {
+            Event::Scalar { value, style, .. } => {
+                if style != ScalarStyle::Plain {
+                    if let Some(path) = path(&stack) {
+                        styles.styles.insert(path, style);
+                    }
+                }
+                advance(&mut stack, Some(value));
+            }
+            Event::Alias(_) => advance(&mut stack, None),
+            Event::SequenceStart { .. } => stack.push(Frame::Seq(0)),
+            Event::MappingStart { .. } => stack.push(Frame::Key),
+            Event::SequenceEnd | Event::MappingEnd => {
+                stack.pop();
+                advance(&mut stack, None);
+            }
+            Event::DocumentEnd => break,
+            Event::StreamStart | Event::StreamEnd | Event::DocumentStart => {}
+        }
+    }
+    Ok(styles)
+}
+
+/// The path to the node that comes next, if it is not inside a key.
+fn path(stack: &[Frame]) -> Option<Vec<String>> {
+    stack.iter()
+        .map(|frame| {
+            match *frame {
+                Frame::Seq(index) => Some(index.to_string()),
+                Frame::Key => None,
+                Frame::Value(ref key) => key.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Moves past a node in the collection enclosing it. `scalar` is the value
+/// of the node if it is a scalar, which names the value after it if it is a
+/// key.
+fn advance(stack: &mut [Frame], scalar: Option<String>) {
+    if let Some(frame) = stack.last_mut() {
+        *frame = match *frame {
+            Frame::Seq(index) => Frame::Seq(index + 1),
+            Frame::Key => Frame::Value(scalar),
+            Frame::Value(_) => Frame::Key,
+        };
+    }
+}
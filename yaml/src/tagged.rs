@@ -0,0 +1,201 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use yaml_rust::Yaml;
+use yaml_rust::yaml;
+
+use serde::{self, Deserialize, Serialize};
+
+use super::error;
+use super::value::Value;
+
+/// Newtype struct name by which `TaggedValue` asks to be written as a tagged
+/// node, and to be given the tag when read.
+pub const TAGGED_TOKEN: &str = "$serde_yaml::tagged";
+
+/// Tag by which a `!!set` mapping is kept as a tagged node. Application tags
+/// are stored without their leading `!`, so this is written out as `!!set`.
+pub const SET_TAG: &str = "!set";
+
+/// Tag by which a `!!binary` scalar is kept as a tagged node, so that it can
+/// be decoded when bytes are expected.
+pub const BINARY_TAG: &str = "!binary";
+
+/// Anchor id of the alias that marks a `Yaml::Array` as a tagged node. Loaded
+/// documents never contain aliases, so it cannot be mistaken for data.
+const TAG_MARK: usize = !0;
+
+/// A value with an application-specific tag, like `!Ref MyResource`.
+///
+/// The tag is stored without its leading `!`. Tags of the core schema, such as
+/// `!!str`, are not application-specific and are resolved as usual instead.
+///
+/// ```rust
+/// use serde_yaml::{TaggedValue, Value};
+/// let tagged: TaggedValue = serde_yaml::from_str("!Ref MyResource").unwrap();
+/// assert_eq!(tagged.tag, "Ref");
+/// assert_eq!(tagged.value, Value::String("MyResource".to_owned()));
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct TaggedValue {
+    pub tag: String,
+    pub value: Value,
+}
+
+impl Serialize for TaggedValue {
+    fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+        where S: serde::Serializer
+    {
+        serializer.serialize_newtype_struct(TAGGED_TOKEN,
+                                            (&self.tag, &self.value))
+    }
+}
+
+impl Deserialize for TaggedValue {
+    fn deserialize<D>(deserializer: &mut D) -> Result<Self, D::Error>
+        where D: serde::Deserializer
+    {
+        struct TaggedValueVisitor;
+
+        impl serde::de::Visitor for TaggedValueVisitor {
+            type Value = TaggedValue;
+
+            fn visit_newtype_struct<D>(
+                &mut self,
+                deserializer: &mut D
+            ) -> Result<TaggedValue, D::Error>
+                where D: serde::Deserializer,
+            {
+                deserializer.deserialize_tuple(2, TaggedValueVisitor)
+            }
+
+            fn visit_seq<V>(
+                &mut self,
+                mut visitor: V
+            ) -> Result<TaggedValue, V::Error>
+                where V: serde::de::SeqVisitor,
+            {
+                let tag = match try!(visitor.visit()) {
+                    Some(tag) => tag,
+                    None => return Err(serde::de::Error::invalid_length(0)),
+                };
+                let value = match try!(visitor.visit()) {
+                    Some(value) => value,
+                    None => return Err(serde::de::Error::invalid_length(1)),
+                };
+                try!(visitor.end());
+                Ok(TaggedValue {
+                    tag: tag,
+                    value: value,
+                })
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(TAGGED_TOKEN,
+                                                TaggedValueVisitor)
+    }
+}
+
+/// Builds the node for `value` carrying `tag`.
+pub fn node(tag: String, value: Yaml) -> Yaml {
+    Yaml::Array(vec![Yaml::Alias(TAG_MARK), Yaml::String(tag), value])
+}
+
+/// Turns the `[tag, value]` sequence that a `TaggedValue` serializes as into
+/// a tagged node.
+pub fn from_pair(pair: Yaml) -> Yaml {
+    let mut pair = match pair {
+        Yaml::Array(pair) => pair,
+        other => return other,
+    };
+    match (pair.pop(), pair.pop(), pair.is_empty()) {
+        (Some(value), Some(Yaml::String(tag)), true) => node(tag, value),
+        (value, tag, _) => {
+            pair.extend(tag);
+            pair.extend(value);
+            Yaml::Array(pair)
+        }
+    }
+}
+
+/// The tag and inner node of a tagged node.
+pub fn split(node: &Yaml) -> Option<(&str, &Yaml)> {
+    match *node {
+        Yaml::Array(ref v) if v.len() == 3 && v[0] == Yaml::Alias(TAG_MARK) => {
+            match v[1] {
+                Yaml::String(ref tag) => Some((tag, &v[2])),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Like `split`, but gives the tag as the string node that holds it.
+pub fn split_node(node: &Yaml) -> Option<(&Yaml, &Yaml)> {
+    match *node {
+        Yaml::Array(ref v) if split(node).is_some() => Some((&v[1], &v[2])),
+        _ => None,
+    }
+}
+
+/// Drops the tag of a tagged node, leaving any other node as it is.
+pub fn untag(node: Yaml) -> Yaml {
+    if split(&node).is_none() {
+        return node;
+    }
+    match node {
+        Yaml::Array(mut v) => v.pop().unwrap(),
+        _ => unreachable!(),
+    }
+}
+
+/// Drops the tags of a node and of every node inside of it.
+pub fn strip(node: Yaml) -> Yaml {
+    match untag(node) {
+        Yaml::Array(v) => Yaml::Array(v.into_iter().map(strip).collect()),
+        Yaml::Hash(h) => {
+            Yaml::Hash(h.into_iter()
+                        .map(|(k, v)| (strip(k), strip(v)))
+                        .collect())
+        }
+        other => other,
+    }
+}
+
+/// Replaces each node with an application-specific tag by what `resolver`
+/// gives for its tag and its inner node, innermost nodes first. The core tags
+/// kept in tagged nodes, which start with `!`, are left alone.
+pub fn resolve<F>(node: Yaml, resolver: &F) -> error::Result<Yaml>
+    where F: Fn(&str, Value) -> error::Result<Value> + ?Sized,
+{
+    let node = match node {
+        Yaml::Array(v) => {
+            let mut resolved = Vec::with_capacity(v.len());
+            for elem in v {
+                resolved.push(try!(resolve(elem, resolver)));
+            }
+            Yaml::Array(resolved)
+        }
+        Yaml::Hash(h) => {
+            let mut resolved = yaml::Hash::new();
+            for (k, v) in h {
+                resolved.insert(try!(resolve(k, resolver)),
+                                try!(resolve(v, resolver)));
+            }
+            Yaml::Hash(resolved)
+        }
+        other => return Ok(other),
+    };
+    let tag = match split(&node) {
+        Some((tag, _)) if !tag.starts_with('!') => tag.to_owned(),
+        _ => return Ok(node),
+    };
+    let value = try!(resolver(&tag, untag(node).into()));
+    Ok(value.into())
+}
@@ -0,0 +1,214 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! YAML timestamps as `chrono::DateTime<UTC>`
+//!
+//! Enabled by the `chrono` feature. Use these functions on a struct field:
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Release {
+//!     #[serde(serialize_with = "serde_yaml::timestamp::serialize",
+//!             deserialize_with = "serde_yaml::timestamp::deserialize")]
+//!     date: DateTime<UTC>,
+//! }
+//! ```
+//!
+//! Deserialization accepts every form of the YAML 1.1 `!!timestamp` type: a
+//! date alone (`2002-12-14`, taken as midnight UTC), a canonical or ISO 8601
+//! date and time (`2001-12-14t21:59:43.10-05:00`), and the space separated
+//! form (`2001-12-14 21:59:43.10 -5`). A time without a zone is taken as UTC.
+//!
+//! Serialization emits an unquoted RFC 3339 timestamp in UTC.
+
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone, UTC};
+use serde::{de, ser};
+
+use super::ser::PLAIN_TOKEN;
+
+macro_rules! try_opt {
+    ($e:expr) => {
+        match $e {
+            Some(v) => v,
+            None => return None,
+        }
+    };
+}
+
+pub fn serialize<S>(
+    datetime: &DateTime<UTC>,
+    serializer: &mut S
+) -> Result<(), S::Error>
+    where S: ser::Serializer,
+{
+    let repr = datetime.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string();
+    serializer.serialize_newtype_struct(PLAIN_TOKEN, repr)
+}
+
+pub fn deserialize<D>(deserializer: &mut D) -> Result<DateTime<UTC>, D::Error>
+    where D: de::Deserializer,
+{
+    de::Deserialize::deserialize(deserializer).map(|Timestamp(dt)| dt)
+}
+
+struct Timestamp(DateTime<UTC>);
+
+impl de::Deserialize for Timestamp {
+    fn deserialize<D>(deserializer: &mut D) -> Result<Timestamp, D::Error>
+        where D: de::Deserializer,
+    {
+        struct TimestampVisitor;
+
+        impl de::Visitor for TimestampVisitor {
+            type Value = Timestamp;
+
+            fn visit_str<E>(&mut self, v: &str) -> Result<Timestamp, E>
+                where E: de::Error,
+            {
+                match parse(v) {
+                    Some(datetime) => Ok(Timestamp(datetime)),
+                    None => {
+                        Err(E::invalid_value(&format!("expected a YAML \
+                                                       timestamp, found \
+                                                       `{}`",
+                                                      v)))
+                    }
+                }
+            }
+        }
+
+        deserializer.deserialize_str(TimestampVisitor)
+    }
+}
+
+/// Parses a scalar according to the YAML 1.1 timestamp regular expression.
+fn parse(v: &str) -> Option<DateTime<UTC>> {
+    let mut cur = Cursor {
+        s: v.as_bytes(),
+        pos: 0,
+    };
+
+    let year = try_opt!(cur.digits(4, 4));
+    if !cur.eat(b'-') {
+        return None;
+    }
+    let month = try_opt!(cur.digits(1, 2));
+    if !cur.eat(b'-') {
+        return None;
+    }
+    let day = try_opt!(cur.digits(1, 2));
+    let date = try_opt!(NaiveDate::from_ymd_opt(year as i32, month, day));
+
+    if cur.at_end() {
+        // Only the strict `YYYY-MM-DD` form may omit the time.
+        if v.len() != 10 {
+            return None;
+        }
+        return Some(UTC.from_utc_datetime(&date.and_hms(0, 0, 0)));
+    }
+
+    if !(cur.eat(b'T') || cur.eat(b't') || cur.spaces() > 0) {
+        return None;
+    }
+    let hour = try_opt!(cur.digits(1, 2));
+    if !cur.eat(b':') {
+        return None;
+    }
+    let minute = try_opt!(cur.digits(2, 2));
+    if !cur.eat(b':') {
+        return None;
+    }
+    let second = try_opt!(cur.digits(2, 2));
+    let mut nano = 0;
+    if cur.eat(b'.') {
+        let mut scale = 100_000_000;
+        while let Some(digit) = cur.digit() {
+            nano += digit * scale;
+            scale /= 10;
+        }
+    }
+    let naive = try_opt!(date.and_hms_nano_opt(hour, minute, second, nano));
+
+    cur.spaces();
+    let offset = if cur.at_end() || cur.eat(b'Z') {
+        0
+    } else {
+        let sign = if cur.eat(b'+') {
+            1
+        } else if cur.eat(b'-') {
+            -1
+        } else {
+            return None;
+        };
+        let hours = try_opt!(cur.digits(1, 2));
+        let minutes = if cur.eat(b':') {
+            try_opt!(cur.digits(2, 2))
+        } else {
+            0
+        };
+        sign * (hours * 3600 + minutes * 60) as i32
+    };
+    if !cur.at_end() {
+        return None;
+    }
+
+    let tz = try_opt!(FixedOffset::east_opt(offset));
+    let local = try_opt!(tz.from_local_datetime(&naive).single());
+    Some(local.with_timezone(&UTC))
+}
+
+struct Cursor<'a> {
+    s: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn at_end(&self) -> bool {
+        self.pos == self.s.len()
+    }
+
+    fn eat(&mut self, b: u8) -> bool {
+        if self.s.get(self.pos) == Some(&b) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn spaces(&mut self) -> usize {
+        let start = self.pos;
+        while self.eat(b' ') || self.eat(b'\t') {}
+        self.pos - start
+    }
+
+    fn digit(&mut self) -> Option<u32> {
+        let digit = self.s.get(self.pos).and_then(|&b| (b as char).to_digit(10));
+        if digit.is_some() {
+            self.pos += 1;
+        }
+        digit
+    }
+
+    /// Reads between `min` and `max` decimal digits.
+    fn digits(&mut self, min: usize, max: usize) -> Option<u32> {
+        let start = self.pos;
+        let mut n = 0;
+        while self.pos - start < max {
+            match self.digit() {
+                Some(digit) => n = n * 10 + digit,
+                None => break,
+            }
+        }
+        if self.pos - start < min {
+            None
+        } else {
+            Some(n)
+        }
+    }
+}
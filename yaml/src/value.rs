@@ -6,30 +6,111 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use std::hash::{Hash, Hasher};
 use std::mem;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::str::FromStr;
 
-use dtoa;
 use linked_hash_map::LinkedHashMap;
 use serde::{self, Serialize, Deserialize};
 use yaml_rust::Yaml;
 
-use super::{Error, Deserializer, Serializer};
+use super::{Error, Deserializer, FloatFormat, Number, Serializer, WholeFloat,
+            from_str};
+use super::loader::{self, special_float};
+use super::ser::format_real;
+use super::tagged;
 
-#[derive(Clone, PartialOrd, Debug)]
+/// Any YAML value.
+///
+/// Values are ordered first by type, as null, then booleans, numbers,
+/// strings, sequences and mappings, and then by content. Numbers are ordered
+/// by value, with NaN after every other number. Sequences and mappings are
+/// compared element by element, and then by length. This is a total order,
+/// so values can be sorted or used as the keys of a `BTreeMap`.
+#[derive(Clone, PartialOrd, Ord, Debug)]
 pub enum Value {
     Null,
     Bool(bool),
-    I64(i64),
-    F64(f64),
+    Number(Number),
     String(String),
     Sequence(Sequence),
     Mapping(Mapping),
 }
 
 pub type Sequence = Vec<Value>;
+/// A YAML mapping, which keeps its entries in the order they were inserted.
+/// A mapping read from a document has its keys in the order they are
+/// written, and is written back in that order unless
+/// `SerializerBuilder::sort_keys` is enabled.
 pub type Mapping = LinkedHashMap<Value, Value>;
 
+/// Adds an entry API to `Mapping`, like that of the maps in
+/// `std::collections`.
+///
+/// ```rust
+/// use serde_yaml::{Mapping, MappingExt, Value};
+/// let mut config = Mapping::new();
+/// config.entry("timeout".into()).or_insert(30.into());
+/// config.entry("timeout".into()).or_insert(60.into());
+/// assert_eq!(config.get(&"timeout".into()), Some(&Value::from(30)));
+/// ```
+pub trait MappingExt {
+    /// The entry for `key`, which may or may not be in the mapping yet.
+    fn entry(&mut self, key: Value) -> Entry;
+}
+
+impl MappingExt for Mapping {
+    fn entry(&mut self, key: Value) -> Entry {
+        Entry {
+            map: self,
+            key: key,
+        }
+    }
+}
+
+/// A key of a `Mapping` and the place for its value, from
+/// `MappingExt::entry`.
+pub struct Entry<'a> {
+    map: &'a mut Mapping,
+    key: Value,
+}
+
+impl<'a> Entry<'a> {
+    pub fn key(&self) -> &Value {
+        &self.key
+    }
+
+    /// The value for the key, after inserting `default` at the end of the
+    /// mapping if the key is not there yet.
+    pub fn or_insert(self, default: Value) -> &'a mut Value {
+        self.or_insert_with(|| default)
+    }
+
+    /// Like `or_insert`, but only computes the default when it is needed.
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut Value
+        where F: FnOnce() -> Value,
+    {
+        if self.map.contains_key(&self.key) {
+            return self.map.get_mut(&self.key).unwrap();
+        }
+        self.map.insert(self.key, default());
+        // a new key goes at the end
+        self.map.iter_mut().next_back().unwrap().1
+    }
+}
+
+/// How `Value::merge_with` combines two sequences at the same place.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SequenceMerge {
+    /// The sequence being merged in replaces the other. This is what
+    /// `Value::merge` does.
+    Replace,
+    /// The elements of the sequence being merged in are added to the end of
+    /// the other.
+    Append,
+}
+
 /// Shortcut function to encode a `T` into a YAML `Value`.
 ///
 /// ```rust
@@ -55,9 +136,7 @@ pub fn to_value<T: ?Sized>(value: &T) -> Value
 pub fn from_value<T>(value: Value) -> Result<T, Error>
     where T: Deserialize,
 {
-    let yaml = value.into();
-    let mut de = Deserializer::new(&yaml);
-    Deserialize::deserialize(&mut de)
+    Deserialize::deserialize(&mut ValueDeserializer::new(value))
 }
 
 impl Value {
@@ -80,24 +159,42 @@ impl Value {
         }
     }
 
-    pub fn is_i64(&self) -> bool {
-        self.as_i64().is_some()
+    pub fn is_number(&self) -> bool {
+        self.as_number().is_some()
     }
 
-    pub fn as_i64(&self) -> Option<i64> {
+    pub fn as_number(&self) -> Option<&Number> {
         match *self {
-            Value::I64(i) => Some(i),
+            Value::Number(ref n) => Some(n),
             _ => None,
         }
     }
 
+    pub fn is_i64(&self) -> bool {
+        self.as_i64().is_some()
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number().and_then(Number::as_i64)
+    }
+
+    pub fn is_u64(&self) -> bool {
+        self.as_u64().is_some()
+    }
+
+    /// Returns the number if it was written as an integer that is not
+    /// negative.
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number().and_then(Number::as_u64)
+    }
+
     pub fn is_f64(&self) -> bool {
         self.as_f64().is_some()
     }
 
     pub fn as_f64(&self) -> Option<f64> {
         match *self {
-            Value::F64(i) => Some(i),
+            Value::Number(ref n) if n.is_f64() => n.as_f64(),
             _ => None,
         }
     }
@@ -113,6 +210,13 @@ impl Value {
         }
     }
 
+    pub fn into_string(self) -> Option<String> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
     pub fn is_sequence(&self) -> bool {
         self.as_sequence().is_some()
     }
@@ -131,6 +235,13 @@ impl Value {
         }
     }
 
+    pub fn into_sequence(self) -> Option<Sequence> {
+        match self {
+            Value::Sequence(seq) => Some(seq),
+            _ => None,
+        }
+    }
+
     pub fn is_mapping(&self) -> bool {
         self.as_mapping().is_some()
     }
@@ -148,18 +259,403 @@ impl Value {
             _ => None,
         }
     }
+
+    pub fn into_mapping(self) -> Option<Mapping> {
+        match self {
+            Value::Mapping(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// Moves the value out, leaving a null in its place, so that a part of a
+    /// document can be taken without cloning it.
+    ///
+    /// ```rust
+    /// use serde_yaml::Value;
+    /// let mut value: Value = serde_yaml::from_str("[[1, 2], x]").unwrap();
+    /// let seq = value.as_sequence_mut().unwrap()[0].take();
+    /// assert_eq!(seq.into_sequence().unwrap().len(), 2);
+    /// assert!(value.get(0).unwrap().is_null());
+    /// ```
+    pub fn take(&mut self) -> Value {
+        mem::replace(self, Value::Null)
+    }
+
+    /// The kind of YAML node this value is, as named in error messages.
+    ///
+    /// ```rust
+    /// use serde_yaml::Value;
+    /// let value: Value = serde_yaml::from_str("[1, 2]").unwrap();
+    /// assert_eq!(value.type_name(), "sequence");
+    /// assert_eq!(value.get(0).unwrap().type_name(), "integer");
+    /// ```
+    pub fn type_name(&self) -> &'static str {
+        match *self {
+            Value::Null => "null",
+            Value::Bool(_) => "bool",
+            Value::Number(ref n) if n.is_f64() => "real",
+            Value::Number(_) => "integer",
+            Value::String(_) => "string",
+            Value::Sequence(_) => "sequence",
+            Value::Mapping(_) => "mapping",
+        }
+    }
+
+    /// Looks up an element of a sequence by its index, or a value of a
+    /// mapping by its string key. Returns `None` if there is no such element,
+    /// or if this value is of a kind that cannot be indexed that way.
+    ///
+    /// ```rust
+    /// use serde_yaml::Value;
+    /// let value: Value = serde_yaml::from_str("a: [x, y]").unwrap();
+    /// assert_eq!(value.get("a").and_then(|a| a.get(1)),
+    ///            Some(&Value::String("y".to_owned())));
+    /// assert_eq!(value.get(0), None);
+    /// ```
+    pub fn get<I>(&self, index: I) -> Option<&Value>
+        where I: Index,
+    {
+        index.index_into(self)
+    }
+
+    /// Looks up a value by a JSON Pointer style path such as `/a/b/0`, in
+    /// which each part is a mapping key or a sequence index. As in JSON
+    /// Pointer, `~1` stands for `/` and `~0` for `~` inside a part. A part
+    /// made of digits also matches an integer key.
+    ///
+    /// ```rust
+    /// use serde_yaml::Value;
+    /// let yaml = "database:\n  hosts: [a, b]\n  port: 5432";
+    /// let value: Value = serde_yaml::from_str(yaml).unwrap();
+    /// assert_eq!(value.pointer("/database/port").and_then(Value::as_i64),
+    ///            Some(5432));
+    /// assert_eq!(value.pointer("/database/hosts/1").and_then(Value::as_str),
+    ///            Some("b"));
+    /// ```
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        if !pointer.starts_with('/') {
+            return None;
+        }
+        let mut target = self;
+        for part in pointer[1..].split('/') {
+            let part = part.replace("~1", "/").replace("~0", "~");
+            let next = match *target {
+                Value::Sequence(ref seq) => {
+                    part.parse::<usize>().ok().and_then(|i| seq.get(i))
+                }
+                Value::Mapping(ref map) => {
+                    let int_key = part.parse::<i64>().ok();
+                    map.get(&Value::String(part)).or_else(|| {
+                        int_key.and_then(|i| map.get(&Value::Number(i.into())))
+                    })
+                }
+                _ => None,
+            };
+            if let Some(next) = next {
+                target = next;
+            } else {
+                return None;
+            }
+        }
+        Some(target)
+    }
+
+    /// Resolves the YAML merge keys in this value and every value inside of
+    /// it. A `<<` key names a mapping, or a sequence of mappings, whose
+    /// entries are added to the mapping holding the key. Keys already in that
+    /// mapping win, as do the mappings listed earlier in a sequence.
+    ///
+    /// ```rust
+    /// use serde_yaml::Value;
+    /// let yaml = "base: &base {a: 1, b: 2}\nderived: {<<: *base, b: 3}";
+    /// let mut value: Value = serde_yaml::from_str(yaml).unwrap();
+    /// value.apply_merge().unwrap();
+    /// let derived = value.get("derived").unwrap();
+    /// assert_eq!(derived.get("a").and_then(Value::as_i64), Some(1));
+    /// assert_eq!(derived.get("b").and_then(Value::as_i64), Some(3));
+    /// ```
+    pub fn apply_merge(&mut self) -> Result<(), Error> {
+        match *self {
+            Value::Sequence(ref mut seq) => {
+                for elem in seq {
+                    try!(elem.apply_merge());
+                }
+            }
+            Value::Mapping(ref mut map) => {
+                for (_, value) in map.iter_mut() {
+                    try!(value.apply_merge());
+                }
+                match map.remove(&Value::String("<<".to_owned())) {
+                    Some(Value::Mapping(source)) => merge_into(map, source),
+                    Some(Value::Sequence(sources)) => {
+                        for source in sources {
+                            match source {
+                                Value::Mapping(m) => merge_into(map, m),
+                                _ => return Err(Error::MergeNotAMapping),
+                            }
+                        }
+                    }
+                    Some(_) => return Err(Error::MergeNotAMapping),
+                    None => {}
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Merges `other` into this value, as when overlaying the settings for
+    /// one environment onto a base configuration. Two mappings are merged
+    /// key by key, recursively, keeping the order of the keys already here
+    /// and adding new keys at the end. Anything else in `other`, sequences
+    /// included, replaces what is here. This is unrelated to the `<<` keys of
+    /// `apply_merge`.
+    ///
+    /// ```rust
+    /// use serde_yaml::Value;
+    /// let mut config: Value =
+    ///     serde_yaml::from_str("db: {host: localhost, port: 5432}").unwrap();
+    /// let overrides: Value =
+    ///     serde_yaml::from_str("db: {host: prod.example.com}").unwrap();
+    /// config.merge(overrides);
+    /// assert_eq!(config.pointer("/db/host").and_then(Value::as_str),
+    ///            Some("prod.example.com"));
+    /// assert_eq!(config.pointer("/db/port").and_then(Value::as_i64),
+    ///            Some(5432));
+    /// ```
+    pub fn merge(&mut self, other: Value) {
+        self.merge_with(other, SequenceMerge::Replace);
+    }
+
+    /// Like `merge`, but with a choice of what to do where both values hold
+    /// a sequence.
+    pub fn merge_with(&mut self, other: Value, sequences: SequenceMerge) {
+        match other {
+            Value::Mapping(other) => {
+                if let Value::Mapping(ref mut map) = *self {
+                    for (key, value) in other {
+                        match map.get_mut(&key) {
+                            Some(existing) => {
+                                existing.merge_with(value, sequences)
+                            }
+                            None => {
+                                map.insert(key, value);
+                            }
+                        }
+                    }
+                    return;
+                }
+                *self = Value::Mapping(other);
+            }
+            Value::Sequence(other) => {
+                if sequences == SequenceMerge::Append {
+                    if let Value::Sequence(ref mut seq) = *self {
+                        seq.extend(other);
+                        return;
+                    }
+                }
+                *self = Value::Sequence(other);
+            }
+            other => *self = other,
+        }
+    }
+
+    /// Sorts the keys of every mapping in this value, however deeply nested,
+    /// so that the document serializes the same however it was built. Keys
+    /// are sorted in the order of `Value`'s `Ord`. Keys themselves are left
+    /// as they are, only the values are sorted recursively.
+    ///
+    /// ```rust
+    /// use serde_yaml::Value;
+    /// let mut value: Value = serde_yaml::from_str("{b: 2, a: 1}").unwrap();
+    /// value.sort_keys();
+    /// assert_eq!(serde_yaml::to_string(&value).unwrap(), "---\na: 1\nb: 2");
+    /// ```
+    pub fn sort_keys(&mut self) {
+        match *self {
+            Value::Sequence(ref mut seq) => {
+                for elem in seq {
+                    elem.sort_keys();
+                }
+            }
+            Value::Mapping(ref mut map) => {
+                let mut entries: Vec<_> = mem::replace(map, Mapping::new())
+                    .into_iter()
+                    .collect();
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+                for (key, mut value) in entries {
+                    value.sort_keys();
+                    map.insert(key, value);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Adds the entries of `source` whose keys are not already in `map`.
+fn merge_into(map: &mut Mapping, source: Mapping) {
+    for (key, value) in source {
+        if !map.contains_key(&key) {
+            map.insert(key, value);
+        }
+    }
+}
+
+/// A type that can index into a `Value`: `usize` for sequences, and `str` or
+/// `String` for mappings.
+pub trait Index {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value>;
+}
+
+impl Index for usize {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        match *v {
+            Value::Sequence(ref seq) => seq.get(*self),
+            _ => None,
+        }
+    }
+}
+
+impl Index for str {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        match *v {
+            Value::Mapping(ref map) => map.get(&Value::String(self.to_owned())),
+            _ => None,
+        }
+    }
+}
+
+impl Index for String {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        self[..].index_into(v)
+    }
+}
+
+impl<T: ?Sized> Index for &T
+    where T: Index,
+{
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        (**self).index_into(v)
+    }
+}
+
+/// Parses a single YAML document, the same as `serde_yaml::from_str`.
+///
+/// ```rust
+/// use serde_yaml::Value;
+/// let value: Value = "a: 1\nb: 2".parse().unwrap();
+/// assert_eq!(value.get("b").and_then(Value::as_i64), Some(2));
+/// ```
+impl FromStr for Value {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        from_str(s)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+macro_rules! from_number {
+    ($($ty:ty)*) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(n: $ty) -> Self {
+                    Value::Number(n.into())
+                }
+            }
+        )*
+    };
+}
+
+from_number!(i8 i16 i32 i64 isize u8 u16 u32 u64 f32 f64);
+
+impl<'a> From<&'a str> for Value {
+    fn from(s: &'a str) -> Self {
+        Value::String(s.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(v: Vec<T>) -> Self {
+        v.into_iter().collect()
+    }
+}
+
+impl From<Mapping> for Value {
+    fn from(mapping: Mapping) -> Self {
+        Value::Mapping(mapping)
+    }
+}
+
+/// Collects a sequence.
+///
+/// ```rust
+/// use serde_yaml::Value;
+/// let value: Value = vec!["a", "b"].into_iter().collect();
+/// assert_eq!(serde_yaml::to_string(&value).unwrap(), "---\n- a\n- b");
+/// ```
+impl<T: Into<Value>> FromIterator<T> for Value {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Value::Sequence(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Collects a mapping, keeping the order of the entries.
+///
+/// ```rust
+/// use serde_yaml::Value;
+/// let value: Value = vec![("a", 1), ("b", 2)].into_iter().collect();
+/// assert_eq!(serde_yaml::to_string(&value).unwrap(), "---\na: 1\nb: 2");
+/// ```
+impl<K, V> FromIterator<(K, V)> for Value
+    where K: Into<Value>,
+          V: Into<Value>,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Value::Mapping(iter.into_iter()
+                           .map(|(k, v)| (k.into(), v.into()))
+                           .collect())
+    }
 }
 
 impl From<Yaml> for Value {
     fn from(yaml: Yaml) -> Self {
-        match yaml {
+        // A `Value` has no room for tags, so they are dropped.
+        match tagged::untag(yaml) {
             Yaml::Real(f) => {
-                match f.parse() {
-                    Ok(f) => Value::F64(f),
-                    Err(_) => Value::String(f),
+                // An integer too big for `i64` is loaded as a real.
+                match f.parse::<u64>() {
+                    Ok(u) if u > i64::MAX as u64 => {
+                        return Value::Number(u.into());
+                    }
+                    _ => {}
+                }
+                match f.parse::<f64>().ok().or_else(|| special_float(&f)) {
+                    Some(f) => Value::Number(f.into()),
+                    None => {
+                        match loader::resolve_real(&f) {
+                            Yaml::Integer(i) => Value::Number(i.into()),
+                            _ => Value::String(f),
+                        }
+                    }
                 }
             }
-            Yaml::Integer(i) => Value::I64(i),
+            Yaml::Integer(i) => Value::Number(i.into()),
             Yaml::String(s) => Value::String(s),
             Yaml::Boolean(b) => Value::Bool(b),
             Yaml::Array(array) =>  {
@@ -184,11 +680,17 @@ impl From<Value> for Yaml {
         match value {
             Value::Null => Yaml::Null,
             Value::Bool(b) => Yaml::Boolean(b),
-            Value::I64(i) => Yaml::Integer(i),
-            Value::F64(f) => {
-                let mut buf = Vec::new();
-                dtoa::write(&mut buf, f).unwrap();
-                Yaml::Real(String::from_utf8(buf).unwrap())
+            Value::Number(n) => {
+                match (n.as_i64(), n.as_u64()) {
+                    (Some(i), _) => Yaml::Integer(i),
+                    (None, Some(u)) => Yaml::Real(u.to_string()),
+                    (None, None) => {
+                        let f = n.as_f64().unwrap();
+                        Yaml::Real(format_real(f,
+                                               WholeFloat::DotZero,
+                                               FloatFormat::Shortest))
+                    }
+                }
             }
             Value::String(s) => Yaml::String(s),
             Value::Sequence(seq) => {
@@ -212,8 +714,7 @@ impl Serialize for Value {
         match *self {
             Value::Null => serializer.serialize_unit(),
             Value::Bool(b) => serializer.serialize_bool(b),
-            Value::I64(i) => serializer.serialize_i64(i),
-            Value::F64(f) => serializer.serialize_f64(f),
+            Value::Number(ref n) => n.serialize(serializer),
             Value::String(ref s) => serializer.serialize_str(s),
             Value::Sequence(ref seq) => seq.serialize(serializer),
             Value::Mapping(ref map) => map.serialize(serializer),
@@ -239,19 +740,19 @@ impl Deserialize for Value {
             fn visit_i64<E>(&mut self, i: i64) -> Result<Value, E>
                 where E: serde::de::Error
             {
-                Ok(Value::I64(i))
+                Ok(Value::Number(i.into()))
             }
 
             fn visit_u64<E>(&mut self, u: u64) -> Result<Value, E>
                 where E: serde::de::Error
             {
-                Ok(Value::I64(u as i64))
+                Ok(Value::Number(u.into()))
             }
 
             fn visit_f64<E>(&mut self, f: f64) -> Result<Value, E>
                 where E: serde::de::Error
             {
-                Ok(Value::F64(f))
+                Ok(Value::Number(f.into()))
             }
 
             fn visit_str<E>(&mut self, s: &str) -> Result<Value, E>
@@ -308,23 +809,125 @@ impl Deserialize for Value {
     }
 }
 
+/// A deserializer that owns the `Value` it reads, for buffering a node and
+/// replaying it into a type chosen after looking at it, as untagged and
+/// internally tagged enums need. It reads the value exactly as `from_str`
+/// would read the same document, and can be used once.
+///
+/// ```rust
+/// use serde::Deserialize;
+/// use serde_yaml::{Value, ValueDeserializer};
+/// # extern crate serde;
+/// # extern crate serde_yaml;
+/// # fn main() {
+/// let value: Value = serde_yaml::from_str("[1, 2]").unwrap();
+/// let mut de = ValueDeserializer::new(value);
+/// let pair: (u8, u8) = Deserialize::deserialize(&mut de).unwrap();
+/// assert_eq!(pair, (1, 2));
+/// # }
+/// ```
+pub struct ValueDeserializer {
+    value: Option<Value>,
+}
+
+impl ValueDeserializer {
+    pub fn new(value: Value) -> Self {
+        ValueDeserializer { value: Some(value) }
+    }
+
+    fn take(&mut self) -> Result<Yaml, Error> {
+        self.value.take().map(Yaml::from).ok_or(Error::EndOfStream)
+    }
+}
+
+/// Implements each method by handing the value to the `Deserializer` for a
+/// `Yaml` tree, so that both read a document the same way.
+macro_rules! forward_to_yaml_deserializer {
+    ($($method:ident($($arg:ident: $ty:ty),*))*) => {
+        $(
+            fn $method<V>(
+                &mut self,
+                $($arg: $ty,)*
+                visitor: V
+            ) -> Result<V::Value, Error>
+                where V: serde::de::Visitor,
+            {
+                let yaml = try!(self.take());
+                serde::Deserializer::$method(&mut Deserializer::new(&yaml),
+                                             $($arg,)*
+                                             visitor)
+            }
+        )*
+    }
+}
+
+impl serde::Deserializer for ValueDeserializer {
+    type Error = Error;
+
+    forward_to_yaml_deserializer! {
+        deserialize()
+        deserialize_bool()
+        deserialize_usize()
+        deserialize_u8()
+        deserialize_u16()
+        deserialize_u32()
+        deserialize_u64()
+        deserialize_isize()
+        deserialize_i8()
+        deserialize_i16()
+        deserialize_i32()
+        deserialize_i64()
+        deserialize_f32()
+        deserialize_f64()
+        deserialize_char()
+        deserialize_str()
+        deserialize_string()
+        deserialize_unit()
+        deserialize_option()
+        deserialize_seq()
+        deserialize_seq_fixed_size(len: usize)
+        deserialize_bytes()
+        deserialize_map()
+        deserialize_unit_struct(name: &'static str)
+        deserialize_newtype_struct(name: &'static str)
+        deserialize_tuple_struct(name: &'static str, len: usize)
+        deserialize_struct(name: &'static str,
+                           fields: &'static [&'static str])
+        deserialize_struct_field()
+        deserialize_tuple(len: usize)
+        deserialize_ignored_any()
+    }
+
+    fn deserialize_enum<V>(
+        &mut self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V
+    ) -> Result<V::Value, Error>
+        where V: serde::de::EnumVisitor,
+    {
+        let yaml = try!(self.take());
+        serde::Deserializer::deserialize_enum(&mut Deserializer::new(&yaml),
+                                              name,
+                                              variants,
+                                              visitor)
+    }
+}
+
+impl serde::de::value::ValueDeserializer<Error> for Value {
+    type Deserializer = ValueDeserializer;
+
+    fn into_deserializer(self) -> ValueDeserializer {
+        ValueDeserializer::new(self)
+    }
+}
+
 impl PartialEq for Value {
     fn eq(&self, other: &Value) -> bool {
         match (self, other) {
             (&Value::Null, &Value::Null) => true,
             (&Value::Bool(a), &Value::Bool(b)) => a == b,
-            (&Value::I64(a), &Value::I64(b)) => a == b,
-            (&Value::F64(a), &Value::F64(b)) => {
-                if a.is_nan() && b.is_nan() {
-                    // compare NaN for bitwise equality
-                    let (a, b): (i64, i64) = unsafe {
-                        (mem::transmute(a), mem::transmute(b))
-                    };
-                    a == b
-                } else {
-                    a == b
-                }
-            }
+            (&Value::Number(ref a), &Value::Number(ref b)) => a == b,
             (&Value::String(ref a), &Value::String(ref b)) => a == b,
             (&Value::Sequence(ref a), &Value::Sequence(ref b)) => a == b,
             (&Value::Mapping(ref a), &Value::Mapping(ref b)) => a == b,
@@ -340,11 +943,7 @@ impl Hash for Value {
         match *self {
             Value::Null => 0.hash(state),
             Value::Bool(b) => (1, b).hash(state),
-            Value::I64(i) => (2, i).hash(state),
-            Value::F64(_) => {
-                // you should feel bad for using f64 as a map key
-                3.hash(state);
-            }
+            Value::Number(ref n) => (2, n).hash(state),
             Value::String(ref s) => (4, s).hash(state),
             Value::Sequence(ref seq) => (5, seq).hash(state),
             Value::Mapping(ref map) => (6, map).hash(state),
@@ -0,0 +1,202 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![feature(test)]
+
+extern crate serde;
+extern crate serde_yaml;
+extern crate test;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::{Serialize, Serializer};
+use test::Bencher;
+
+/// Counts allocations so that each benchmark can report how many a single
+/// run makes, which is steadier than its timing.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, size: usize)
+                      -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.realloc(ptr, layout, size)
+    }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+/// Runs `f` once and prints how many times it allocated.
+fn report_allocations<F: FnOnce()>(name: &str, f: F) {
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+    f();
+    let after = ALLOCATIONS.load(Ordering::SeqCst);
+    println!("{}: {} allocations", name, after - before);
+}
+
+struct Server {
+    name: String,
+    port: u16,
+    enabled: bool,
+    weight: f64,
+    tags: Vec<String>,
+}
+
+impl Serialize for Server {
+    fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+        where S: Serializer
+    {
+        let mut state = try!(serializer.serialize_struct("Server", 5));
+        try!(serializer.serialize_struct_elt(&mut state, "name", &self.name));
+        try!(serializer.serialize_struct_elt(&mut state, "port", self.port));
+        try!(serializer.serialize_struct_elt(&mut state, "enabled",
+                                             self.enabled));
+        try!(serializer.serialize_struct_elt(&mut state, "weight",
+                                             self.weight));
+        try!(serializer.serialize_struct_elt(&mut state, "tags", &self.tags));
+        serializer.serialize_struct_end(state)
+    }
+}
+
+fn large_seq() -> Vec<Vec<u32>> {
+    (0..20_000).map(|i| vec![i; 8]).collect()
+}
+
+fn large_struct_seq() -> Vec<Server> {
+    (0..10_000)
+        .map(|i| {
+            Server {
+                name: format!("server-{}", i),
+                port: (i % 65_536) as u16,
+                enabled: i % 2 == 0,
+                weight: i as f64 / 4.0,
+                tags: vec!["web".to_owned(), format!("zone-{}", i % 8)],
+            }
+        })
+        .collect()
+}
+
+fn large_nested() -> BTreeMap<String, Vec<Server>> {
+    let mut servers = large_struct_seq().into_iter();
+    (0..100)
+        .map(|i| (format!("group-{}", i), servers.by_ref().take(100).collect()))
+        .collect()
+}
+
+/// The numbers below it, serialized without the length given up front as a
+/// sequence built from an iterator adapter would be.
+struct Counter(u32);
+
+impl Serialize for Counter {
+    fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+        where S: Serializer
+    {
+        let mut state = try!(serializer.serialize_seq(None));
+        for i in (0..self.0).filter(|_| true) {
+            try!(serializer.serialize_seq_elt(&mut state, i));
+        }
+        serializer.serialize_seq_end(state)
+    }
+}
+
+fn large_map() -> BTreeMap<String, u32> {
+    (0..20_000).map(|i| (format!("key-{}", i), i)).collect()
+}
+
+/// `to_writer` emits each element of a top-level sequence as soon as it is
+/// serialized, so only one element is held as a `Yaml` tree at a time.
+#[bench]
+fn bench_to_writer_large_seq(b: &mut Bencher) {
+    let thing = large_seq();
+    b.iter(|| serde_yaml::to_writer(&mut io::sink(), &thing).unwrap());
+}
+
+/// For comparison, builds the `Value` of the whole sequence at once.
+#[bench]
+fn bench_to_value_large_seq(b: &mut Bencher) {
+    let thing = large_seq();
+    b.iter(|| serde_yaml::to_value(&thing));
+}
+
+#[bench]
+fn bench_to_string_large_struct_seq(b: &mut Bencher) {
+    let thing = large_struct_seq();
+    report_allocations("to_string_large_struct_seq", || {
+        serde_yaml::to_string(&thing).unwrap();
+    });
+    b.iter(|| serde_yaml::to_string(&thing).unwrap());
+}
+
+#[bench]
+fn bench_to_string_large_map(b: &mut Bencher) {
+    let thing = large_map();
+    report_allocations("to_string_large_map", || {
+        serde_yaml::to_string(&thing).unwrap();
+    });
+    b.iter(|| serde_yaml::to_string(&thing).unwrap());
+}
+
+/// The default path, whose output buffer grows as the document is written.
+#[bench]
+fn bench_to_string_large_nested(b: &mut Bencher) {
+    let thing = large_nested();
+    report_allocations("to_string_large_nested", || {
+        serde_yaml::to_string(&thing).unwrap();
+    });
+    b.iter(|| serde_yaml::to_string(&thing).unwrap());
+}
+
+/// For comparison, reserves the whole output up front.
+#[bench]
+fn bench_to_string_large_nested_presized(b: &mut Bencher) {
+    let thing = large_nested();
+    let len = serde_yaml::to_string(&thing).unwrap().len();
+    let builder = serde_yaml::SerializerBuilder::new().capacity(len);
+    report_allocations("to_string_large_nested_presized", || {
+        builder.to_string(&thing).unwrap();
+    });
+    b.iter(|| builder.to_string(&thing).unwrap());
+}
+
+/// A nested sequence of unknown length is collected before it is written,
+/// growing as its elements arrive.
+#[bench]
+fn bench_to_string_large_unsized_seq(b: &mut Bencher) {
+    let mut thing = BTreeMap::new();
+    thing.insert("counter", Counter(100_000));
+    report_allocations("to_string_large_unsized_seq", || {
+        serde_yaml::to_string(&thing).unwrap();
+    });
+    b.iter(|| serde_yaml::to_string(&thing).unwrap());
+}
+
+/// For comparison, the same sequence with its length known.
+#[bench]
+fn bench_to_string_large_sized_seq(b: &mut Bencher) {
+    let mut thing = BTreeMap::new();
+    thing.insert("counter", (0..100_000).collect::<Vec<u32>>());
+    report_allocations("to_string_large_sized_seq", || {
+        serde_yaml::to_string(&thing).unwrap();
+    });
+    b.iter(|| serde_yaml::to_string(&thing).unwrap());
+}
@@ -0,0 +1,111 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+extern crate chrono;
+extern crate serde_yaml;
+
+use self::chrono::{DateTime, TimeZone, UTC};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Release {
+    #[serde(serialize_with = "serde_yaml::timestamp::serialize",
+            deserialize_with = "serde_yaml::timestamp::deserialize")]
+    date: DateTime<UTC>,
+}
+
+fn test_de(yaml: &str, expected: DateTime<UTC>) {
+    let deserialized: Release = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(Release { date: expected }, deserialized);
+}
+
+#[test]
+fn test_date() {
+    let yaml = indoc!("
+        ---
+        date: 2002-12-14");
+    test_de(yaml, UTC.ymd(2002, 12, 14).and_hms(0, 0, 0));
+}
+
+#[test]
+fn test_canonical() {
+    let yaml = indoc!("
+        ---
+        date: 2001-12-15T02:59:43.1Z");
+    test_de(yaml, UTC.ymd(2001, 12, 15).and_hms_milli(2, 59, 43, 100));
+}
+
+#[test]
+fn test_iso8601() {
+    let yaml = indoc!("
+        ---
+        date: 2001-12-14t21:59:43.10-05:00");
+    test_de(yaml, UTC.ymd(2001, 12, 15).and_hms_milli(2, 59, 43, 100));
+}
+
+#[test]
+fn test_space_separated() {
+    let yaml = indoc!("
+        ---
+        date: 2001-12-14 21:59:43.10 -5");
+    test_de(yaml, UTC.ymd(2001, 12, 15).and_hms_milli(2, 59, 43, 100));
+}
+
+#[test]
+fn test_no_time_zone() {
+    let yaml = indoc!("
+        ---
+        date: 2001-12-15 2:59:43.10");
+    test_de(yaml, UTC.ymd(2001, 12, 15).and_hms_milli(2, 59, 43, 100));
+}
+
+#[test]
+fn test_invalid() {
+    let yaml = indoc!("
+        ---
+        date: 2001-13-14");
+    let result = serde_yaml::from_str::<Release>(yaml);
+    assert_eq!(format!("{}", result.unwrap_err()),
+               "Invalid value: expected a YAML timestamp, found \
+                `2001-13-14`");
+}
+
+#[test]
+fn test_serialize() {
+    let release = Release {
+        date: UTC.ymd(2001, 12, 15).and_hms_milli(2, 59, 43, 100),
+    };
+    let yaml = indoc!("
+        ---
+        date: 2001-12-15T02:59:43.100Z");
+    assert_eq!(yaml, serde_yaml::to_string(&release).unwrap());
+
+    let deserialized: Release = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(release, deserialized);
+}
+
+#[test]
+fn test_json() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct File {
+        #[serde(serialize_with = "serde_yaml::radix::octal::serialize",
+                deserialize_with = "serde_yaml::radix::octal::deserialize")]
+        mode: u32,
+        #[serde(serialize_with = "serde_yaml::timestamp::serialize",
+                deserialize_with = "serde_yaml::timestamp::deserialize")]
+        modified: DateTime<UTC>,
+    }
+    let file = File {
+        mode: 0o644,
+        modified: UTC.ymd(2001, 12, 15).and_hms(2, 59, 43),
+    };
+    let builder = serde_yaml::SerializerBuilder::new().json_compatible(true);
+    let json = builder.to_string(&file).unwrap();
+    assert_eq!(r#"{"mode": "0o644", "modified": "2001-12-15T02:59:43Z"}"#,
+               json);
+    assert_eq!(file, serde_yaml::from_str(&json).unwrap());
+}
@@ -8,9 +8,10 @@
 
 extern crate serde;
 extern crate serde_yaml;
+extern crate yaml_rust;
 
 use std::fmt::Debug;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
 fn test_de<T>(yaml: &str, expected: T)
     where T: serde::Deserialize + PartialEq + Debug,
@@ -36,6 +37,216 @@ fn test_alias() {
     test_de(yaml, expected);
 }
 
+#[test]
+fn test_forward_alias() {
+    let yaml = indoc!("
+        ---
+        first:
+          *alias
+        second:
+          &alias
+          1");
+    let mut expected = BTreeMap::new();
+    {
+        expected.insert(String::from("first"), 1);
+        expected.insert(String::from("second"), 1);
+    }
+    let builder = serde_yaml::DeserializerBuilder::new().forward_aliases(true);
+    let deserialized: BTreeMap<String, i32> = builder.from_str(yaml).unwrap();
+    assert_eq!(expected, deserialized);
+}
+
+#[test]
+fn test_lenient_bool() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Data {
+        a: bool,
+        b: Vec<bool>,
+    }
+    let yaml = indoc!("
+        ---
+        a: yes
+        b:
+          - OFF
+          - On
+          - true
+          - n
+          - Y");
+    let expected = Data {
+        a: true,
+        b: vec![false, true, true, false, true],
+    };
+    let builder = serde_yaml::DeserializerBuilder::new().lenient_bools(true);
+    let deserialized: Data = builder.from_str(yaml).unwrap();
+    assert_eq!(expected, deserialized);
+}
+
+#[test]
+fn test_coerce_scalars() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Data {
+        version: String,
+        enabled: String,
+        count: String,
+        mask: String,
+        name: String,
+        owner: String,
+        group: String,
+    }
+    let yaml = indoc!("
+        ---
+        version: 1.0
+        enabled: true
+        count: 42
+        mask: 0x1F
+        name: app
+        owner: null
+        group:");
+    let expected = Data {
+        version: "1.0".to_owned(),
+        enabled: "true".to_owned(),
+        count: "42".to_owned(),
+        mask: "31".to_owned(),
+        name: "app".to_owned(),
+        owner: "~".to_owned(),
+        group: "~".to_owned(),
+    };
+    let builder = serde_yaml::DeserializerBuilder::new().coerce_scalars(true);
+    let deserialized: Data = builder.from_str(yaml).unwrap();
+    assert_eq!(expected, deserialized);
+}
+
+#[test]
+fn test_scalar_resolution() {
+    use serde_yaml::{DeserializerBuilder, ScalarResolution, Value};
+    let yaml = indoc!("
+        - yes
+        - Off
+        - 01:02
+        - 1:02
+        - -1:30.5
+        - 0x1f
+        - 017
+        - 0b101
+        - 1_000
+        - '1:02'
+        - !!str yes");
+
+    // YAML 1.2 is the default.
+    let expected: Value = serde_yaml::from_str(indoc!("
+        ['yes', 'Off', '01:02', '1:02', '-1:30.5', 31, 17, '0b101', '1_000',
+         '1:02', 'yes']"))
+        .unwrap();
+    assert_eq!(expected, serde_yaml::from_str::<Value>(yaml).unwrap());
+    let builder = DeserializerBuilder::new()
+        .scalar_resolution(ScalarResolution::Yaml12);
+    assert_eq!(expected, builder.from_str::<Value>(yaml).unwrap());
+
+    // A base 60 integer may not start with 0, and quoted or tagged scalars
+    // are strings either way.
+    let expected: Value = serde_yaml::from_str(indoc!("
+        [true, false, '01:02', 62, -90.5, 31, 15, 5, 1000, '1:02', 'yes']"))
+        .unwrap();
+    let builder = DeserializerBuilder::new()
+        .scalar_resolution(ScalarResolution::Yaml11);
+    assert_eq!(expected, builder.from_str::<Value>(yaml).unwrap());
+    assert_eq!(true, builder.from_str::<bool>("on").unwrap());
+}
+
+#[test]
+fn test_case_insensitive_names() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    enum Mode {
+        Fast,
+        Safe,
+        Remote(String),
+    }
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Data {
+        enabled: bool,
+        log_level: String,
+        modes: Vec<Mode>,
+        #[serde(default)]
+        labels: BTreeMap<String, i32>,
+    }
+    let yaml = indoc!("
+        ---
+        Enabled: true
+        Log_Level: debug
+        modes: [fast, SAFE, remote: host]
+        labels: {Enabled: 1}");
+    let mut labels = BTreeMap::new();
+    labels.insert("Enabled".to_owned(), 1);
+    let expected = Data {
+        enabled: true,
+        log_level: "debug".to_owned(),
+        modes: vec![Mode::Fast, Mode::Safe, Mode::Remote("host".to_owned())],
+        labels: labels,
+    };
+    let builder = serde_yaml::DeserializerBuilder::new()
+        .case_insensitive_names(true);
+    let deserialized: Data = builder.from_str(yaml).unwrap();
+    assert_eq!(expected, deserialized);
+
+    // Names are matched exactly by default.
+    assert!(serde_yaml::from_str::<Data>(yaml).is_err());
+    assert!(serde_yaml::from_str::<Mode>("fast").is_err());
+}
+
+#[test]
+fn test_explicit_keys() {
+    let yaml = indoc!("
+        ---
+        ? - 1
+          - 2
+        : first
+        ? [3]
+        : second
+        ? []
+        : empty");
+    let mut expected = BTreeMap::new();
+    expected.insert(vec![1, 2], "first".to_owned());
+    expected.insert(vec![3], "second".to_owned());
+    expected.insert(vec![], "empty".to_owned());
+    test_de(yaml, expected);
+
+    // An explicit key is no different from an implicit one.
+    let yaml = indoc!("
+        ---
+        ? a
+        : 1
+        b: 2
+        ? c
+        : 3");
+    let mut expected = BTreeMap::new();
+    expected.insert("a".to_owned(), 1);
+    expected.insert("b".to_owned(), 2);
+    expected.insert("c".to_owned(), 3);
+    test_de(yaml, expected);
+}
+
+#[test]
+fn test_multi() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Data {
+        a: i32,
+    }
+    let yaml = indoc!("
+        ---
+        a: 1
+        ---
+        a: [1
+        ---
+        a: 3");
+    let docs = serde_yaml::from_str_multi::<Data>(yaml);
+    assert_eq!(3, docs.len());
+    assert_eq!(Data { a: 1 }, *docs[0].as_ref().unwrap());
+    assert_eq!("while parsing a flow sequence, expectd ',' or ']' at line 5 \
+                column 1",
+               format!("{}", docs[1].as_ref().unwrap_err()));
+    assert_eq!(Data { a: 3 }, *docs[2].as_ref().unwrap());
+}
+
 #[test]
 fn test_option() {
     #[derive(Deserialize, PartialEq, Debug)]
@@ -55,3 +266,1100 @@ fn test_option() {
     };
     test_de(yaml, expected);
 }
+
+#[test]
+fn test_option_null_and_empty() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Data {
+        b: Option<String>,
+    }
+    for yaml in &["b:", "b: ~", "b: null", "b: !!null", "{}"] {
+        test_de(yaml, Data { b: None });
+    }
+    for yaml in &["b: \"\"", "b: ''", "b: !!str"] {
+        test_de(yaml, Data { b: Some(String::new()) });
+    }
+    test_de("b: '~'", Data { b: Some(String::from("~")) });
+}
+
+#[test]
+fn test_ignore_tag() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Data {
+        a: String,
+        b: Vec<i32>,
+    }
+    let yaml = indoc!("
+        ---
+        a: !Ref MyResource
+        b: !Ports [80, 443]");
+    let expected = Data {
+        a: String::from("MyResource"),
+        b: vec![80, 443],
+    };
+    test_de(yaml, expected);
+}
+
+#[test]
+fn test_option_map() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Data {
+        a: Option<HashMap<String, i32>>,
+        b: Option<HashMap<String, i32>>,
+        c: Option<HashMap<String, i32>>,
+    }
+    let yaml = indoc!("
+        ---
+        a: null
+        b: {}
+        c:
+          x: 1");
+    let mut populated = HashMap::new();
+    populated.insert(String::from("x"), 1);
+    let expected = Data {
+        a: None,
+        b: Some(HashMap::new()),
+        c: Some(populated),
+    };
+    test_de(yaml, expected);
+}
+
+#[test]
+fn test_from_slice() {
+    let mut expected = BTreeMap::new();
+    expected.insert(String::from("a"), String::from("é"));
+    let deserialized: BTreeMap<String, String> =
+        serde_yaml::from_slice("a: é".as_bytes()).unwrap();
+    assert_eq!(expected, deserialized);
+    let deserialized: BTreeMap<String, String> =
+        serde_yaml::from_slice(b"\xEF\xBB\xBFa: \xC3\xA9").unwrap();
+    assert_eq!(expected, deserialized);
+}
+
+#[test]
+fn test_crlf_line_breaks() {
+    let yaml = "a: \"one \\\r\n  two\"\r\nb: |\r\n  x\r\n  y\r\n";
+    let mut expected = BTreeMap::new();
+    expected.insert(String::from("a"), String::from("one two"));
+    expected.insert(String::from("b"), String::from("x\ny\n"));
+    test_de(yaml, expected);
+}
+
+#[test]
+fn test_bom() {
+    let mut expected = BTreeMap::new();
+    expected.insert(String::from("a"), String::from("é"));
+
+    let deserialized: BTreeMap<String, String> =
+        serde_yaml::from_str("\u{feff}a: é").unwrap();
+    assert_eq!(expected, deserialized);
+
+    let mut utf16_le = vec![0xFF, 0xFE];
+    let mut utf16_be = vec![0xFE, 0xFF];
+    for unit in "a: é".encode_utf16() {
+        utf16_le.push(unit as u8);
+        utf16_le.push((unit >> 8) as u8);
+        utf16_be.push((unit >> 8) as u8);
+        utf16_be.push(unit as u8);
+    }
+    let deserialized: BTreeMap<String, String> =
+        serde_yaml::from_slice(&utf16_le).unwrap();
+    assert_eq!(expected, deserialized);
+    let deserialized: BTreeMap<String, String> =
+        serde_yaml::from_slice(&utf16_be).unwrap();
+    assert_eq!(expected, deserialized);
+}
+
+#[test]
+fn test_internally_tagged() {
+    use serde_yaml::Value;
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Circle {
+        radius: f64,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Square {
+        side: f64,
+    }
+
+    #[derive(PartialEq, Debug)]
+    enum Shape {
+        Circle(Circle),
+        Square(Square),
+    }
+
+    // The derive for serde 0.8 has no `#[serde(tag = "kind")]`, so do what it
+    // would: buffer the node, pick the variant by its `kind` and replay the
+    // rest of the mapping into that variant.
+    impl serde::Deserialize for Shape {
+        fn deserialize<D>(deserializer: &mut D) -> Result<Self, D::Error>
+            where D: serde::Deserializer
+        {
+            use serde::de::Error;
+            let mut value: Value =
+                try!(serde::Deserialize::deserialize(deserializer));
+            let kind = match value.as_mapping_mut() {
+                Some(map) => map.remove(&Value::String(String::from("kind"))),
+                None => None,
+            };
+            let shape = match kind.as_ref().and_then(Value::as_str) {
+                Some("circle") => {
+                    serde_yaml::from_value(value).map(Shape::Circle)
+                }
+                Some("square") => {
+                    serde_yaml::from_value(value).map(Shape::Square)
+                }
+                _ => return Err(D::Error::custom("unknown kind of shape")),
+            };
+            shape.map_err(|err| D::Error::custom(err.to_string()))
+        }
+    }
+
+    let yaml = indoc!("
+        ---
+        - kind: circle
+          radius: 1.5
+        - side: 2.0
+          kind: square");
+    let expected = vec![
+        Shape::Circle(Circle { radius: 1.5 }),
+        Shape::Square(Square { side: 2.0 }),
+    ];
+    test_de(yaml, expected);
+}
+
+// The derive for serde 0.8 has none of `#[serde(untagged)]`,
+// `#[serde(tag = "t", content = "c")]` or `#[serde(flatten)]`, so the types
+// that need one implement by hand what the attribute would generate.
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct Server {
+    pub host: String,
+    pub port: u16,
+}
+
+// Read by `test_untagged` and written by `test_serde::test_untagged_enum`.
+#[derive(PartialEq, Debug)]
+pub enum Address {
+    Name(String),
+    Server(Server),
+}
+
+// As `#[serde(untagged)]` would: buffer the node and try each variant in
+// turn when reading, and write only the payload.
+impl serde::Deserialize for Address {
+    fn deserialize<D>(deserializer: &mut D) -> Result<Self, D::Error>
+        where D: serde::Deserializer
+    {
+        use serde::de::Error;
+        let value: serde_yaml::Value =
+            try!(serde::Deserialize::deserialize(deserializer));
+        if let Ok(name) = serde_yaml::from_value(value.clone()) {
+            return Ok(Address::Name(name));
+        }
+        if let Ok(server) = serde_yaml::from_value(value) {
+            return Ok(Address::Server(server));
+        }
+        Err(D::Error::custom("expected a name or a server"))
+    }
+}
+
+impl serde::Serialize for Address {
+    fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+        where S: serde::Serializer
+    {
+        match *self {
+            Address::Name(ref name) => name.serialize(serializer),
+            Address::Server(ref server) => server.serialize(serializer),
+        }
+    }
+}
+
+#[test]
+fn test_untagged() {
+    let yaml = indoc!("
+        ---
+        - example.com
+        - host: localhost
+          port: 8080");
+    let expected = vec![
+        Address::Name(String::from("example.com")),
+        Address::Server(Server {
+            host: String::from("localhost"),
+            port: 8080,
+        }),
+    ];
+    test_de(yaml, expected);
+}
+
+#[test]
+fn test_adjacently_tagged() {
+    use serde::de::value::ValueDeserializer;
+    use serde_yaml::Value;
+
+    #[derive(PartialEq, Debug)]
+    enum Message {
+        Ping(u32),
+        Text(Vec<String>),
+    }
+
+    // As `#[serde(tag = "t", content = "c")]` would: buffer the node, and
+    // replay its content into the variant its tag names.
+    impl serde::Deserialize for Message {
+        fn deserialize<D>(deserializer: &mut D) -> Result<Self, D::Error>
+            where D: serde::Deserializer
+        {
+            use serde::de::Error;
+            let mut value: Value =
+                try!(serde::Deserialize::deserialize(deserializer));
+            let (tag, content) = match value.as_mapping_mut() {
+                Some(map) => (map.remove(&Value::from("t")),
+                              map.remove(&Value::from("c"))),
+                None => (None, None),
+            };
+            let mut content = content.unwrap_or(Value::Null)
+                .into_deserializer();
+            let message = match tag.as_ref().and_then(Value::as_str) {
+                Some("Ping") => {
+                    serde::Deserialize::deserialize(&mut content)
+                        .map(Message::Ping)
+                }
+                Some("Text") => {
+                    serde::Deserialize::deserialize(&mut content)
+                        .map(Message::Text)
+                }
+                _ => return Err(D::Error::custom("unknown message")),
+            };
+            message.map_err(|err| D::Error::custom(err.to_string()))
+        }
+    }
+
+    let yaml = indoc!("
+        ---
+        - t: Ping
+          c: 7
+        - c: [a, b]
+          t: Text");
+    let expected = vec![
+        Message::Ping(7),
+        Message::Text(vec![String::from("a"), String::from("b")]),
+    ];
+    test_de(yaml, expected);
+
+    let result = serde_yaml::from_str::<Message>("{t: Ping, c: x}");
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_flatten() {
+    use serde_yaml::{Mapping, Value, ValueDeserializer};
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Common {
+        name: String,
+        #[serde(default)]
+        enabled: bool,
+    }
+
+    #[derive(PartialEq, Debug)]
+    struct Plugin {
+        common: Common,
+        extra: Mapping,
+    }
+
+    // As `#[serde(flatten)]` would on both fields: replay the whole mapping
+    // into `Common`, and keep the keys it has no field for.
+    impl serde::Deserialize for Plugin {
+        fn deserialize<D>(deserializer: &mut D) -> Result<Self, D::Error>
+            where D: serde::Deserializer
+        {
+            use serde::de::Error;
+            let value: Value =
+                try!(serde::Deserialize::deserialize(deserializer));
+            let mut de = ValueDeserializer::new(value.clone());
+            let common = try!(serde::Deserialize::deserialize(&mut de)
+                .map_err(|err: serde_yaml::Error| {
+                    D::Error::custom(err.to_string())
+                }));
+            let mut extra = match value {
+                Value::Mapping(map) => map,
+                _ => return Err(D::Error::custom("expected a mapping")),
+            };
+            extra.remove(&Value::from("name"));
+            extra.remove(&Value::from("enabled"));
+            Ok(Plugin {
+                common: common,
+                extra: extra,
+            })
+        }
+    }
+
+    let yaml = indoc!("
+        ---
+        name: cache
+        size: 64
+        enabled: true");
+    let mut extra = Mapping::new();
+    extra.insert(Value::from("size"), Value::from(64));
+    let expected = Plugin {
+        common: Common {
+            name: String::from("cache"),
+            enabled: true,
+        },
+        extra: extra,
+    };
+    test_de(yaml, expected);
+}
+
+#[test]
+fn test_value_deserializer() {
+    use serde_yaml::{Value, ValueDeserializer};
+
+    let value: Value = serde_yaml::from_str("[~, 1, x]").unwrap();
+    let mut de = ValueDeserializer::new(value);
+    let tuple: (Option<u8>, Option<u8>, String) =
+        serde::Deserialize::deserialize(&mut de).unwrap();
+    assert_eq!((None, Some(1), String::from("x")), tuple);
+
+    // The value is moved out by the first read.
+    let result: Result<u8, _> = serde::Deserialize::deserialize(&mut de);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_numeric_field_names() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Data {
+        #[serde(rename = "1")]
+        one: String,
+        #[serde(rename = "2.5")]
+        two_and_a_half: String,
+        #[serde(rename = "true")]
+        yes: String,
+        #[serde(rename = "2024")]
+        year: u32,
+    }
+    let yaml = indoc!("
+        ---
+        1: a
+        2.5: b
+        true: c
+        2024: 366");
+    let expected = Data {
+        one: String::from("a"),
+        two_and_a_half: String::from("b"),
+        yes: String::from("c"),
+        year: 366,
+    };
+    test_de(yaml, expected);
+}
+
+#[test]
+fn test_integer_keys_from_strings() {
+    let yaml = indoc!("
+        ---
+        '1': a
+        \"20\": b
+        300: c");
+    let mut expected = BTreeMap::new();
+    expected.insert(1, String::from("a"));
+    expected.insert(20, String::from("b"));
+    expected.insert(300, String::from("c"));
+    test_de(yaml, expected);
+}
+
+#[test]
+fn test_cow_str() {
+    use std::borrow::Cow;
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Data {
+        plain: Cow<'static, str>,
+        escaped: Cow<'static, str>,
+    }
+    let yaml = indoc!(r#"
+        ---
+        plain: a
+        escaped: "b\tc""#);
+    let data: Data = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!("a", data.plain);
+    assert_eq!("b\tc", data.escaped);
+    // serde 0.8 has no way for a deserializer to lend out its input, so a
+    // `Cow` is always owned.
+    match data.plain {
+        Cow::Owned(_) => {}
+        Cow::Borrowed(_) => panic!("expected an owned string"),
+    }
+}
+
+#[test]
+fn test_set() {
+    use std::collections::HashSet;
+    let yaml = indoc!("
+        ---
+        !!set
+        ? a
+        ? b");
+    let mut expected = HashSet::new();
+    expected.insert(String::from("a"));
+    expected.insert(String::from("b"));
+    test_de(yaml, expected);
+
+    // Without the tag, a mapping is not a set.
+    let result = serde_yaml::from_str::<HashSet<String>>("{a: ~, b: ~}");
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_from_value_after_edit() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Config {
+        ratio: f64,
+        name: String,
+    }
+    let yaml = indoc!("
+        ---
+        ratio: 0.30000000000000004");
+    let mut value: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+    value.as_mapping_mut().unwrap().insert(
+        serde_yaml::Value::String("name".to_owned()),
+        serde_yaml::Value::String("edited".to_owned()));
+    let expected = Config {
+        ratio: 0.1 + 0.2,
+        name: "edited".to_owned(),
+    };
+    assert_eq!(expected, serde_yaml::from_value(value).unwrap());
+}
+
+#[test]
+fn test_parse_and_from_yaml() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+    let yaml = serde_yaml::parse("x: 1\ny: !Tagged 2").unwrap();
+    assert_eq!(yaml["x"], yaml_rust::Yaml::Integer(1));
+    assert_eq!(yaml["y"], yaml_rust::Yaml::Integer(2));
+    let point = Point { x: 1, y: 2 };
+    assert_eq!(point, serde_yaml::from_yaml(&yaml).unwrap());
+
+    let mut ser = serde_yaml::Serializer::new();
+    serde::Serialize::serialize(&point, &mut ser).unwrap();
+    assert_eq!(yaml, ser.take());
+
+    assert!(serde_yaml::parse("--- 1\n--- 2").is_err());
+}
+
+#[test]
+fn test_tuple_and_array() {
+    let expected = (1u8, "x".to_owned(), true);
+    test_de(r#"[1, "x", true]"#, expected.clone());
+    let yaml = indoc!("
+        ---
+        - 1
+        - x
+        - true");
+    test_de(yaml, expected);
+
+    test_de("[1, 2, 3]", [1, 2, 3]);
+    let yaml = indoc!("
+        ---
+        - 1
+        - 2
+        - 3");
+    test_de(yaml, [1, 2, 3]);
+}
+
+#[test]
+fn test_enum_map_or_tag() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    enum Shape {
+        Empty,
+        Circle(u32),
+        Rgb(u8, u8, u8),
+        Point {
+            x: i32,
+            y: i32,
+        },
+    }
+    let expected = vec![
+        Shape::Empty,
+        Shape::Circle(5),
+        Shape::Rgb(255, 0, 0),
+        Shape::Point { x: 1, y: 2 },
+    ];
+    let map_form = indoc!("
+        ---
+        - Empty
+        - Circle: 5
+        - Rgb: [255, 0, 0]
+        - Point: {x: 1, y: 2}");
+    let tag_form = indoc!("
+        ---
+        - !Empty
+        - !Circle 5
+        - !Rgb [255, 0, 0]
+        - !Point {x: 1, y: 2}");
+    let from_map: Vec<Shape> = serde_yaml::from_str(map_form).unwrap();
+    assert_eq!(expected, from_map);
+    test_de(tag_form, from_map);
+}
+
+#[test]
+fn test_default_and_null() {
+    fn default_retries() -> Option<u32> {
+        Some(3)
+    }
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Settings {
+        #[serde(default = "default_retries")]
+        retries: Option<u32>,
+        #[serde(default)]
+        timeout: Option<u32>,
+    }
+
+    // The default applies only when the key is missing.
+    let expected = Settings {
+        retries: Some(3),
+        timeout: None,
+    };
+    test_de("{}", expected);
+
+    // A null value is deserialized like any other value.
+    let yaml = indoc!("
+        ---
+        retries: ~
+        timeout:");
+    let expected = Settings {
+        retries: None,
+        timeout: None,
+    };
+    test_de(yaml, expected);
+
+    let yaml = indoc!("
+        ---
+        retries: 5
+        timeout: 30");
+    let expected = Settings {
+        retries: Some(5),
+        timeout: Some(30),
+    };
+    test_de(yaml, expected);
+}
+
+#[test]
+fn test_empty_document() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Defaults {
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+    for yaml in &["", "# comment\n", "---", "~"] {
+        test_de::<Option<i32>>(yaml, None);
+        test_de::<Vec<i32>>(yaml, Vec::new());
+        test_de(yaml, BTreeMap::<String, i32>::new());
+        test_de(yaml, ());
+        test_de(yaml, serde_yaml::Value::Null);
+    }
+
+    test_de("{}", BTreeMap::<String, i32>::new());
+    test_de("--- {}", BTreeMap::<String, i32>::new());
+    test_de("{}", Some(BTreeMap::<String, i32>::new()));
+    let expected = Defaults {
+        name: String::new(),
+        tags: Vec::new(),
+    };
+    test_de("{}", expected);
+
+    test_de("[]", Vec::<i32>::new());
+    test_de("--- []", Vec::<i32>::new());
+    test_de("[]", Some(Vec::<i32>::new()));
+}
+
+#[test]
+fn test_null_into_empty_collection() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Config {
+        items: Vec<i32>,
+        labels: BTreeMap<String, String>,
+    }
+    let yaml = indoc!("
+        ---
+        items:
+        labels: ~");
+    let expected = Config {
+        items: Vec::new(),
+        labels: BTreeMap::new(),
+    };
+    test_de(yaml, expected);
+
+    let mut expected = BTreeMap::new();
+    expected.insert(String::from("items"), Vec::<i32>::new());
+    test_de("items:", expected);
+}
+
+#[test]
+fn test_empty_collection_into_defaults() {
+    #[derive(Deserialize, PartialEq, Debug, Default)]
+    struct Config {
+        #[serde(default)]
+        port: u16,
+        #[serde(default)]
+        hosts: Vec<String>,
+    }
+    test_de("{}", Config::default());
+    test_de("---\nconfig: {}", {
+        let mut expected = BTreeMap::new();
+        expected.insert(String::from("config"), Config::default());
+        expected
+    });
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Empty();
+    test_de("[]", Vec::<Config>::new());
+    test_de("[]", ());
+    test_de("[]", Empty());
+}
+
+#[test]
+fn test_integer_bounds() {
+    test_de("255", 255u8);
+    test_de("-128", -128i8);
+    test_de("0", 0u64);
+    test_de("-2147483648", i32::min_value());
+}
+
+#[test]
+fn test_pairs() {
+    let expected = vec![(String::from("b"), 2), (String::from("a"), 1)];
+    let yaml = indoc!("
+        ---
+        - [b, 2]
+        - [a, 1]");
+    test_de(yaml, expected.clone());
+    let yaml = indoc!("
+        ---
+        b: 2
+        a: 1");
+    test_de(yaml, expected);
+}
+
+#[test]
+fn test_binary() {
+    use serde::bytes::ByteBuf;
+
+    let yaml = indoc!("
+        ---
+        !!binary |
+          R0lGODlhDAAMAIQAAP//9/X1
+          7unp5WZmZgAAAOfn515eXvPz
+          7Y6OjuDg4J+fn5OTk6enp56e
+          nmleECcgggoBADs=");
+    let bytes: Vec<u8> = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(65, bytes.len());
+    assert_eq!(b"GIF89a", &bytes[..6]);
+    assert_eq!(b';', bytes[64]);
+    test_de(yaml, ByteBuf::from(bytes));
+
+    test_de("!!binary aGVsbG8=", ByteBuf::from(b"hello".to_vec()));
+    test_de("!!binary aGVsbG8=", String::from("aGVsbG8="));
+}
+
+#[test]
+fn test_bytes_from_int_sequence() {
+    use serde::bytes::ByteBuf;
+    test_de("[0, 255, 128]", ByteBuf::from(vec![0, 255, 128]));
+    test_de("[]", ByteBuf::from(vec![]));
+}
+
+#[test]
+fn test_integer_into_float() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Timeouts {
+        connect: f64,
+        read: f32,
+    }
+    let yaml = indoc!("
+        ---
+        connect: 5
+        read: 5.0");
+    let expected = Timeouts {
+        connect: 5.0,
+        read: 5.0,
+    };
+    test_de(yaml, expected);
+    test_de("-3", -3.0f64);
+}
+
+#[test]
+fn test_duplicate_key() {
+    use serde_yaml::{DeserializerBuilder, DuplicateKey};
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+    let yaml = indoc!("
+        ---
+        host: a.example.com
+        port: 80
+        host: b.example.com");
+
+    let builder = DeserializerBuilder::new()
+        .on_duplicate_key(DuplicateKey::FirstWins);
+    let expected = Server {
+        host: "a.example.com".to_owned(),
+        port: 80,
+    };
+    assert_eq!(expected, builder.from_str(yaml).unwrap());
+
+    let builder = DeserializerBuilder::new()
+        .on_duplicate_key(DuplicateKey::LastWins);
+    let expected = Server {
+        host: "b.example.com".to_owned(),
+        port: 80,
+    };
+    assert_eq!(expected, builder.from_str(yaml).unwrap());
+
+    // The key keeps the position of its first occurrence.
+    let map: serde_yaml::Mapping = builder.from_str(yaml).unwrap();
+    let keys: Vec<_> = map.keys().map(|k| k.as_str().unwrap()).collect();
+    assert_eq!(keys, ["host", "port"]);
+}
+
+#[test]
+fn test_from_str_nth() {
+    let yaml = indoc!("
+        ---
+        name: first
+        ---
+        name: second
+        ---
+        name: third
+        ---
+        [unclosed");
+    let doc: BTreeMap<String, String> = serde_yaml::from_str_nth(yaml, 1)
+        .unwrap();
+    assert_eq!(doc["name"], "second");
+    let doc: BTreeMap<String, String> = serde_yaml::from_str_nth(yaml, 2)
+        .unwrap();
+    assert_eq!(doc["name"], "third");
+}
+
+#[test]
+fn test_tag_resolver() {
+    use serde_yaml::{DeserializerBuilder, Value};
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Greeting {
+        name: String,
+        words: Vec<String>,
+    }
+
+    let builder = DeserializerBuilder::new().tag_resolver(|tag, value| {
+        match (tag, value) {
+            ("upper", Value::String(s)) => Ok(Value::String(s.to_uppercase())),
+            ("upper", _) => Err(serde::de::Error::custom("expected a string")),
+            (_, value) => Ok(value),
+        }
+    });
+    let yaml = indoc!("
+        ---
+        name: !upper world
+        words: [!upper hello, !other there]");
+    let expected = Greeting {
+        name: String::from("WORLD"),
+        words: vec![String::from("HELLO"), String::from("there")],
+    };
+    assert_eq!(expected, builder.from_str(yaml).unwrap());
+
+    let result = builder.from_str::<Greeting>("name: !upper [1]\nwords: []");
+    assert_eq!("expected a string", format!("{}", result.unwrap_err()));
+}
+
+#[test]
+fn test_validated_from_str() {
+    use serde_yaml::{Error, Value};
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Server {
+        host: String,
+        #[serde(default)]
+        port: u16,
+    }
+
+    let require_port = |value: &Value| {
+        match value.get("port") {
+            Some(_) => Ok(()),
+            None => Err(Error::Custom(String::from("server needs a port"))),
+        }
+    };
+
+    let yaml = indoc!("
+        ---
+        host: localhost");
+    let result = serde_yaml::validated_from_str::<Server, _>(yaml,
+                                                             &require_port);
+    assert_eq!("server needs a port", format!("{}", result.unwrap_err()));
+
+    let yaml = indoc!("
+        ---
+        host: localhost
+        port: 8080");
+    let expected = Server {
+        host: String::from("localhost"),
+        port: 8080,
+    };
+    let server: Server = serde_yaml::validated_from_str(yaml, &require_port)
+        .unwrap();
+    assert_eq!(expected, server);
+}
+
+#[test]
+fn test_collect_errors_from_str() {
+    use serde_yaml::{Error, Value};
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    let check = |value: &Value, errors: &mut Vec<Error>| {
+        let map = match value.as_mapping() {
+            Some(map) => map,
+            None => {
+                errors.push(Error::Custom(String::from("expected a mapping")));
+                return;
+            }
+        };
+        for key in map.keys() {
+            match key.as_str() {
+                Some("host") | Some("port") => {}
+                _ => {
+                    let msg = format!("unknown field {:?}", key);
+                    errors.push(Error::Custom(msg));
+                }
+            }
+        }
+        if let Some(port) = map.get(&Value::from("port")) {
+            if port.as_u64().map_or(true, |n| n > u16::max_value() as u64) {
+                let msg = format!("port {:?} is not a u16", port);
+                errors.push(Error::Custom(msg));
+            }
+        }
+    };
+
+    let yaml = indoc!("
+        ---
+        host: localhost
+        port: eighty
+        hots: localhost");
+    let errors = serde_yaml::collect_errors_from_str::<Server, _>(yaml, &check)
+        .unwrap_err();
+    let messages: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(vec![
+        r#"unknown field String("hots")"#,
+        r#"port String("eighty") is not a u16"#,
+    ], messages);
+
+    let yaml = indoc!("
+        ---
+        host: localhost
+        port: 8080");
+    let expected = Server {
+        host: String::from("localhost"),
+        port: 8080,
+    };
+    let server: Server = serde_yaml::collect_errors_from_str(yaml, &check)
+        .unwrap();
+    assert_eq!(expected, server);
+
+    let errors = serde_yaml::collect_errors_from_str::<Server, _>("[", &check)
+        .unwrap_err();
+    assert_eq!(1, errors.len());
+}
+
+#[test]
+fn test_yaml_directive() {
+    let yaml = indoc!("
+        %YAML 1.2
+        ---
+        key: value");
+    let mut expected = BTreeMap::new();
+    expected.insert(String::from("key"), String::from("value"));
+    test_de(yaml, expected);
+}
+
+#[test]
+fn test_tag_directive() {
+    use serde_yaml::TaggedValue;
+
+    let yaml = indoc!("
+        %TAG !e! tag:example.com,2000:app/
+        ---
+        !e!foo bar");
+    let tagged: TaggedValue = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(tagged.tag, "<tag:example.com,2000:app/foo>");
+    test_de(yaml, String::from("bar"));
+
+    // A handle may stand for a core tag, which is then resolved as usual.
+    let yaml = indoc!("
+        %TAG !y! tag:yaml.org,2002:
+        ---
+        !y!str 12");
+    test_de(yaml, String::from("12"));
+
+    // The directives only apply to the document right after them.
+    let yaml = indoc!("
+        %TAG !e! !local-
+        --- !e!first 1
+        --- !e!second 2");
+    let docs: Vec<TaggedValue> = serde_yaml::from_str_multi(yaml)
+        .into_iter()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(docs[0].tag, "local-first");
+    assert_eq!(docs[1].tag, "e!second");
+}
+
+#[test]
+fn test_flatten_catch_all() {
+    use serde_yaml::Value;
+
+    // As a struct with `#[serde(flatten)] extra: BTreeMap<String, Value>`
+    // would: the known fields go to the struct and every other key to
+    // `extra`.
+    #[derive(PartialEq, Debug)]
+    struct Service {
+        name: String,
+        port: u16,
+        extra: BTreeMap<String, Value>,
+    }
+
+    impl serde::Deserialize for Service {
+        fn deserialize<D>(deserializer: &mut D) -> Result<Self, D::Error>
+            where D: serde::Deserializer
+        {
+            struct ServiceVisitor;
+
+            impl serde::de::Visitor for ServiceVisitor {
+                type Value = Service;
+
+                fn visit_map<V>(&mut self, mut visitor: V)
+                                -> Result<Service, V::Error>
+                    where V: serde::de::MapVisitor
+                {
+                    let mut name = None;
+                    let mut port = None;
+                    let mut extra = BTreeMap::new();
+                    while let Some(key) = try!(visitor.visit_key::<String>()) {
+                        match &key[..] {
+                            "name" => name = Some(try!(visitor.visit_value())),
+                            "port" => port = Some(try!(visitor.visit_value())),
+                            _ => {
+                                let value = try!(visitor.visit_value());
+                                extra.insert(key, value);
+                            }
+                        }
+                    }
+                    try!(visitor.end());
+                    Ok(Service {
+                        name: match name {
+                            Some(name) => name,
+                            None => try!(visitor.missing_field("name")),
+                        },
+                        port: match port {
+                            Some(port) => port,
+                            None => try!(visitor.missing_field("port")),
+                        },
+                        extra: extra,
+                    })
+                }
+            }
+
+            deserializer.deserialize_map(ServiceVisitor)
+        }
+    }
+
+    let yaml = indoc!("
+        ---
+        replicas: 3
+        name: web
+        labels:
+          tier: frontend
+        port: 8080
+        debug: ~");
+    let mut extra = BTreeMap::new();
+    extra.insert(String::from("replicas"), Value::Number(3.into()));
+    extra.insert(String::from("labels"),
+                 serde_yaml::from_str("tier: frontend").unwrap());
+    extra.insert(String::from("debug"), Value::Null);
+    let expected = Service {
+        name: String::from("web"),
+        port: 8080,
+        extra: extra,
+    };
+    test_de(yaml, expected);
+}
+
+#[test]
+fn test_huge_numbers() {
+    // A float too small to tell from zero is zero, and an integer too big
+    // for any integer type is the nearest float.
+    test_de("1e-400", 0.0f64);
+    test_de("12345678901234567890123456789012345678901234567890",
+            1.2345678901234567e49f64);
+    test_de("inf", std::f64::INFINITY);
+}
+
+#[test]
+fn test_special_floats() {
+    for yaml in &[".inf", ".Inf", ".INF", "+.inf"] {
+        test_de(yaml, std::f64::INFINITY);
+        test_de(yaml, std::f32::INFINITY);
+    }
+    for yaml in &["-.inf", "-.Inf", "-.INF"] {
+        test_de(yaml, std::f64::NEG_INFINITY);
+        test_de(yaml, std::f32::NEG_INFINITY);
+    }
+    for yaml in &[".nan", ".NaN", ".NAN", "!!float .nan"] {
+        // NaN is not equal to itself
+        assert!(serde_yaml::from_str::<f64>(yaml).unwrap().is_nan());
+        assert!(serde_yaml::from_str::<f32>(yaml).unwrap().is_nan());
+    }
+
+    // Quoted, they are strings.
+    test_de("'.inf'", String::from(".inf"));
+    let value: serde_yaml::Value = serde_yaml::from_str("[.inf, '.nan']")
+        .unwrap();
+    assert_eq!(Some(std::f64::INFINITY),
+               value.get(0).and_then(serde_yaml::Value::as_f64));
+    assert_eq!(Some(".nan"), value.get(1).and_then(serde_yaml::Value::as_str));
+}
+
+#[test]
+fn test_from_path() {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Config {
+        name: String,
+        port: u16,
+    }
+    let path = env::temp_dir().join("serde_yaml_test_from_path.yaml");
+    File::create(&path).unwrap().write_all(b"name: app\nport: 8080").unwrap();
+    let config: Config = serde_yaml::from_path(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let expected = Config {
+        name: String::from("app"),
+        port: 8080,
+    };
+    assert_eq!(expected, config);
+}
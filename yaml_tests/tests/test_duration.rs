@@ -0,0 +1,71 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+extern crate serde_yaml;
+
+use std::time::Duration;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Timeouts {
+    #[serde(serialize_with = "serde_yaml::duration::serialize",
+            deserialize_with = "serde_yaml::duration::deserialize")]
+    connect: Duration,
+    #[serde(serialize_with = "serde_yaml::duration::serialize",
+            deserialize_with = "serde_yaml::duration::deserialize")]
+    read: Duration,
+}
+
+fn test_de(yaml: &str, expected: Duration) {
+    let yaml = format!("connect: {}\nread: 0s", yaml);
+    let deserialized: Timeouts = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(expected, deserialized.connect);
+}
+
+#[test]
+fn test_units() {
+    test_de("30s", Duration::from_secs(30));
+    test_de("5m", Duration::from_secs(300));
+    test_de("1h", Duration::from_secs(3600));
+    test_de("250ms", Duration::from_millis(250));
+    test_de("1500ms", Duration::from_millis(1500));
+    test_de("\"10us\"", Duration::new(0, 10_000));
+    test_de("7ns", Duration::new(0, 7));
+}
+
+#[test]
+fn test_combined() {
+    test_de("1h30m", Duration::from_secs(5400));
+    test_de("2s500ms", Duration::from_millis(2500));
+    test_de("1h2m3s4ms", Duration::new(3723, 4_000_000));
+}
+
+#[test]
+fn test_round_trip() {
+    let thing = Timeouts {
+        connect: Duration::new(5403, 250_000_000),
+        read: Duration::new(0, 0),
+    };
+    let yaml = indoc!("
+        ---
+        connect: 1h30m3s250ms
+        read: 0s");
+    assert_eq!(yaml, serde_yaml::to_string(&thing).unwrap());
+    assert_eq!(thing, serde_yaml::from_str(yaml).unwrap());
+}
+
+#[test]
+fn test_invalid() {
+    for yaml in &["1.5s", "30m1h", "1m1m", "1h30", "h", "30 s", "1d"] {
+        let yaml = format!("connect: {}\nread: 0s", yaml);
+        let err = serde_yaml::from_str::<Timeouts>(&yaml).unwrap_err();
+        assert!(err.to_string().contains("expected a duration like `1h30m`"),
+                "{}",
+                err);
+    }
+    assert!(serde_yaml::from_str::<Timeouts>("connect: 30\nread: 0s").is_err());
+}
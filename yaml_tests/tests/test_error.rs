@@ -9,6 +9,7 @@
 extern crate serde;
 extern crate serde_yaml;
 
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 
 fn test_error<T>(yaml: &str, expected: &str)
@@ -23,17 +24,127 @@ fn test_incorrect_type() {
     let yaml = indoc!("
         ---
         str");
-    let expected = "Invalid type. Expected `Str`";
+    let expected = "Invalid type. Expected i16, found string";
     test_error::<i16>(yaml, expected);
 }
 
+#[test]
+fn test_sequence_into_string() {
+    let yaml = indoc!("
+        ---
+        - a
+        - b");
+    let expected = "Invalid type. Expected string, found sequence";
+    test_error::<String>(yaml, expected);
+    let expected = "Invalid type. Expected bool, found mapping";
+    test_error::<bool>("{a: 1}", expected);
+    let expected = "Invalid type. Expected u8, found null";
+    test_error::<u8>("~", expected);
+}
+
+#[test]
+fn test_float_into_integer() {
+    test_error::<i64>("5.5", "Invalid type. Expected i64, found real");
+    test_error::<u8>("5.0", "Invalid type. Expected u8, found real");
+    test_error::<i32>("!!float 1", "Invalid type. Expected i32, found real");
+}
+
+#[test]
+fn test_integer_out_of_range() {
+    test_error::<u8>("300", "YAML integer 300 is out of range for u8");
+    test_error::<u8>("-1", "YAML integer -1 is out of range for u8");
+    test_error::<i8>("128", "YAML integer 128 is out of range for i8");
+    test_error::<u64>("-1", "YAML integer -1 is out of range for u64");
+}
+
+#[test]
+fn test_huge_integer() {
+    let yaml = "12345678901234567890123456789012345678901234567890";
+    let expected = format!("YAML integer {} is out of range for i64", yaml);
+    test_error::<i64>(yaml, &expected);
+    let expected = format!("YAML integer {} is out of range for u64", yaml);
+    test_error::<u64>(yaml, &expected);
+    test_error::<i64>("18446744073709551615",
+                      "YAML integer 18446744073709551615 is out of range for \
+                       i64");
+    // serde 0.8 has no `i128`, so there is no wider integer to read it into.
+}
+
+#[test]
+fn test_float_out_of_range() {
+    test_error::<f64>("1e400", "YAML float 1e400 is out of range for f64");
+    test_error::<f64>("-1e400", "YAML float -1e400 is out of range for f64");
+    test_error::<f32>("1e39", "YAML float 1e39 is out of range for f32");
+    test_error::<serde_yaml::Value>("1e400",
+                                    "YAML float 1e400 is out of range for f64");
+}
+
+#[test]
+fn test_invalid_base64() {
+    let expected = "YAML !!binary scalar is not valid base64";
+    test_error::<Vec<u8>>("!!binary aGVs*G8=", expected);
+    test_error::<Vec<u8>>("!!binary aGVsbG8", expected);
+}
+
+#[test]
+fn test_bytes_out_of_range() {
+    use serde::bytes::ByteBuf;
+    let expected = "YAML integer 256 is out of range for u8";
+    test_error::<ByteBuf>("[0, 256]", expected);
+    let expected = "YAML integer -1 is out of range for u8";
+    test_error::<ByteBuf>("[-1]", expected);
+}
+
+#[test]
+fn test_invalid_hex() {
+    use serde::bytes::ByteBuf;
+    let builder = serde_yaml::DeserializerBuilder::new().hex_bytes(true);
+    let expected = "YAML string is not valid hexadecimal bytes";
+    for yaml in &["0af", "0g", "é0"] {
+        let result = builder.from_str::<ByteBuf>(yaml);
+        assert_eq!(expected, format!("{}", result.unwrap_err()));
+    }
+}
+
+#[test]
+fn test_pairs_wrong_size() {
+    let yaml = indoc!("
+        ---
+        a: 1");
+    let expected = "Expected a YAML sequence of size 3 but was size 2";
+    test_error::<Vec<(String, i32, i32)>>(yaml, expected);
+}
+
 #[test]
 fn test_empty() {
     let yaml = "";
     let expected = "EOF while parsing a value";
+    test_error::<i32>(yaml, expected);
     test_error::<String>(yaml, expected);
 }
 
+#[test]
+fn test_duplicate_key() {
+    let yaml = indoc!("
+        ---
+        a: 1
+        b: 2
+        a: 3");
+    let expected = "Duplicate key `a` in YAML mapping";
+    test_error::<BTreeMap<String, i32>>(yaml, expected);
+    test_error::<serde_yaml::Value>("{[1]: a, [1]: b}",
+                                    "Duplicate key `[...]` in YAML mapping");
+}
+
+#[test]
+fn test_from_str_nth_out_of_range() {
+    let yaml = indoc!("
+        --- 1
+        --- 2");
+    let err = serde_yaml::from_str_nth::<i32>(yaml, 2).unwrap_err();
+    assert_eq!("YAML stream has no document at index 2", err.to_string());
+}
+
 #[test]
 fn test_missing_field() {
     #[derive(Deserialize, Debug)]
@@ -65,10 +176,29 @@ fn test_two_documents() {
         0
         ---
         1");
-    let expected = "Expected a single YAML document but found 2";
+    let expected = "Expected a single YAML document but found 2; use \
+                    from_str_multi to read a stream of documents";
     test_error::<usize>(yaml, expected);
 }
 
+#[test]
+fn test_trailing_content() {
+    let yaml = indoc!("
+        ---
+        \"complete\" garbage");
+    let expected = "Unexpected content after the end of the YAML document at \
+                    line 2";
+    test_error::<String>(yaml, expected);
+
+    let yaml = indoc!("
+        ---
+        [1, 2]
+        garbage");
+    let expected = "Unexpected content after the end of the YAML document at \
+                    line 3";
+    test_error::<Vec<usize>>(yaml, expected);
+}
+
 #[test]
 fn test_variant_map_wrong_size() {
     #[derive(Deserialize, Debug)]
@@ -97,3 +227,478 @@ fn test_variant_not_a_map() {
                     Variant";
     test_error::<Variant>(yaml, expected);
 }
+
+#[test]
+fn test_forward_alias() {
+    let yaml = indoc!("
+        ---
+        first:
+          *alias
+        second:
+          &alias
+          1");
+    let expected = "while parsing node, found unknown anchor at line 3 \
+                    column 3";
+    test_error::<BTreeMap<String, i32>>(yaml, expected);
+}
+
+#[test]
+fn test_recursive_forward_alias() {
+    let yaml = indoc!("
+        ---
+        - &first [*second]
+        - &second [*first]");
+    let builder = serde_yaml::DeserializerBuilder::new().forward_aliases(true);
+    let result = builder.from_str::<Vec<Vec<i32>>>(yaml);
+    assert_eq!("YAML alias refers to its own anchor",
+               format!("{}", result.unwrap_err()));
+}
+
+#[test]
+fn test_duplicate_forward_alias_key() {
+    use serde_yaml::{DeserializerBuilder, DuplicateKey, Value};
+    let yaml = "*b : 1\n&b k: 2";
+    let builder = DeserializerBuilder::new().forward_aliases(true);
+    let result = builder.from_str::<Value>(yaml);
+    assert_eq!("Duplicate key `k` in YAML mapping",
+               format!("{}", result.unwrap_err()));
+
+    let builder = DeserializerBuilder::new()
+        .forward_aliases(true)
+        .on_duplicate_key(DuplicateKey::FirstWins);
+    let value: Value = builder.from_str(yaml).unwrap();
+    assert_eq!(Some(1), value.get("k").and_then(Value::as_i64));
+
+    let builder = DeserializerBuilder::new()
+        .forward_aliases(true)
+        .on_duplicate_key(DuplicateKey::LastWins);
+    let value: Value = builder.from_str(yaml).unwrap();
+    assert_eq!(Some(2), value.get("k").and_then(Value::as_i64));
+}
+
+#[test]
+fn test_alias_inside_own_anchor() {
+    let expected = "YAML alias refers to its own anchor";
+    test_error::<serde_yaml::Value>("a: &x {b: [1, *x]}", expected);
+    test_error::<serde_yaml::Value>("&x [*x]", expected);
+}
+
+#[test]
+fn test_invalid_tagged_scalar() {
+    let yaml = indoc!("
+        ---
+        a: !!int 1
+        b: !!int 2R");
+    let expected = "YAML scalar at line 3 is not a valid !!int";
+    test_error::<serde_yaml::Value>(yaml, expected);
+    let expected = "YAML scalar at line 1 is not a valid !!float";
+    test_error::<serde_yaml::Value>("[!!float x]", expected);
+    let expected = "YAML scalar at line 1 is not a valid !!bool";
+    test_error::<serde_yaml::Value>("{a: !!bool yes}", expected);
+    let expected = "YAML scalar at line 1 is not a valid !!null";
+    test_error::<BTreeMap<String, ()>>("a: !!null x", expected);
+}
+
+#[test]
+fn test_escaped_crlf_at_end() {
+    let result = serde_yaml::from_slice::<serde_yaml::Value>(b"- \"\\\r\n");
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_strict_bool() {
+    let yaml = indoc!("
+        ---
+        yes");
+    let expected = "Invalid type. Expected bool, found string";
+    test_error::<bool>(yaml, expected);
+}
+
+#[test]
+fn test_strict_short_bool() {
+    let yaml = indoc!("
+        ---
+        y");
+    let expected = "Invalid type. Expected bool, found string";
+    test_error::<bool>(yaml, expected);
+}
+
+#[test]
+fn test_lenient_bool() {
+    let yaml = indoc!("
+        ---
+        maybe");
+    let builder = serde_yaml::DeserializerBuilder::new().lenient_bools(true);
+    let result = builder.from_str::<bool>(yaml);
+    assert_eq!("Invalid type. Expected bool, found string",
+               format!("{}", result.unwrap_err()));
+}
+
+#[test]
+fn test_scalar_into_string() {
+    test_error::<String>("1.0", "Invalid type. Expected string, found real");
+    test_error::<String>("true", "Invalid type. Expected string, found bool");
+    test_error::<String>("42", "Invalid type. Expected string, found integer");
+    test_error::<String>("~", "Invalid type. Expected string, found null");
+    test_error::<String>("null", "Invalid type. Expected string, found null");
+
+    let builder = serde_yaml::DeserializerBuilder::new().coerce_scalars(true);
+    let result = builder.from_str::<String>("[1.0]");
+    assert_eq!("Invalid type. Expected string, found sequence",
+               format!("{}", result.unwrap_err()));
+}
+
+#[test]
+fn test_missing_tag() {
+    let yaml = indoc!("
+        ---
+        MyResource");
+    let expected = "Expected a YAML value with a tag";
+    test_error::<serde_yaml::TaggedValue>(yaml, expected);
+}
+
+#[test]
+fn test_invalid_utf8() {
+    let result = serde_yaml::from_slice::<String>(b"a\xFFb");
+    match result.unwrap_err() {
+        serde_yaml::Error::Utf8(err) => assert_eq!(1, err.valid_up_to()),
+        err => panic!("expected a UTF-8 error, found {:?}", err),
+    }
+}
+
+#[test]
+fn test_invalid_utf16() {
+    // BOM followed by `a` and half of another code unit
+    let result = serde_yaml::from_slice::<String>(b"\xFF\xFEa\x00b");
+    match result.unwrap_err() {
+        serde_yaml::Error::FromUtf16(_) => {}
+        err => panic!("expected a UTF-16 error, found {:?}", err),
+    }
+}
+
+#[test]
+fn test_scalar_not_a_struct() {
+    #[derive(Deserialize, Debug)]
+    struct Basic {
+        v: bool,
+    }
+    let yaml = indoc!("
+        ---
+        foo");
+    let expected = "Expected a YAML mapping, found scalar `foo`";
+    test_error::<Basic>(yaml, expected);
+}
+
+#[test]
+fn test_scalar_not_a_seq() {
+    let yaml = indoc!("
+        ---
+        v: 1");
+    let expected = "Expected a YAML sequence, found scalar `1`";
+    test_error::<BTreeMap<String, Vec<i32>>>(yaml, expected);
+}
+
+#[test]
+fn test_recursion_limit() {
+    let depth = 10_000;
+    let yaml = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    let expected = "Recursion limit exceeded";
+    test_error::<serde_yaml::Value>(&yaml, expected);
+
+    let yaml = "[[[[]]]]";
+    let builder = serde_yaml::DeserializerBuilder::new().recursion_limit(3);
+    let result = builder.from_str::<serde_yaml::Value>(yaml);
+    assert_eq!(expected, format!("{}", result.unwrap_err()));
+    let builder = serde_yaml::DeserializerBuilder::new().recursion_limit(4);
+    assert!(builder.from_str::<serde_yaml::Value>(yaml).is_ok());
+}
+
+#[test]
+fn test_max_scalar_bytes() {
+    let builder = serde_yaml::DeserializerBuilder::new().max_scalar_bytes(5);
+    assert!(builder.from_str::<Vec<String>>("[abcde, fghij]").is_ok());
+    let result = builder.from_str::<Vec<String>>("[abcde, fghijk]");
+    let expected = "YAML scalar is longer than 5 bytes";
+    assert_eq!(expected, format!("{}", result.unwrap_err()));
+    let result = builder.from_str::<serde_yaml::Value>("abcdef: 1");
+    assert_eq!(expected, format!("{}", result.unwrap_err()));
+}
+
+#[test]
+fn test_max_total_nodes() {
+    let builder = serde_yaml::DeserializerBuilder::new().max_total_nodes(4);
+    let yaml = "a: [1]";
+    assert!(builder.from_str::<serde_yaml::Value>(yaml).is_ok());
+    let yaml = "a: [1, 2]";
+    let result = builder.from_str::<serde_yaml::Value>(yaml);
+    let expected = "YAML document has more than 4 nodes";
+    assert_eq!(expected, format!("{}", result.unwrap_err()));
+
+    // nodes copied by an alias count too
+    let yaml = "a: &x [1]\nb: *x";
+    let result = builder.from_str::<serde_yaml::Value>(yaml);
+    assert_eq!(expected, format!("{}", result.unwrap_err()));
+}
+
+#[test]
+fn test_max_collection_len() {
+    let builder = serde_yaml::DeserializerBuilder::new().max_collection_len(2);
+    let yaml = "a: [1, 2]\nb: {x: 1, y: 2}";
+    assert!(builder.from_str::<serde_yaml::Value>(yaml).is_ok());
+    let expected = "YAML collection has more than 2 entries";
+    let yaml = "[1, 2, 3]";
+    let result = builder.from_str::<serde_yaml::Value>(yaml);
+    assert_eq!(expected, format!("{}", result.unwrap_err()));
+    let yaml = "a: 1\nb: 2\nc: 3";
+    let result = builder.from_str::<serde_yaml::Value>(yaml);
+    assert_eq!(expected, format!("{}", result.unwrap_err()));
+}
+
+#[test]
+fn test_tab_indentation() {
+    let yaml = "---\nouter:\n\tinner: 1\n";
+    let expected = "Tabs are not allowed for indentation at line 3";
+    test_error::<serde_yaml::Value>(yaml, expected);
+
+    let yaml = "---\nouter:\n  a: 1\n\tb: 2\n";
+    let expected = "Tabs are not allowed for indentation at line 4";
+    test_error::<serde_yaml::Value>(yaml, expected);
+}
+
+#[test]
+fn test_unsupported_yaml_version() {
+    let yaml = indoc!("
+        %YAML 2.0
+        ---
+        key: value");
+    let expected = "YAML version 2.0 is not supported";
+    test_error::<serde_yaml::Value>(yaml, expected);
+}
+
+#[test]
+fn test_billion_laughs() {
+    let yaml = indoc!("
+        ---
+        a: &a [lol, lol, lol, lol, lol, lol, lol, lol, lol]
+        b: &b [*a, *a, *a, *a, *a, *a, *a, *a, *a]
+        c: &c [*b, *b, *b, *b, *b, *b, *b, *b, *b]
+        d: &d [*c, *c, *c, *c, *c, *c, *c, *c, *c]
+        e: &e [*d, *d, *d, *d, *d, *d, *d, *d, *d]
+        f: &f [*e, *e, *e, *e, *e, *e, *e, *e, *e]
+        g: &g [*f, *f, *f, *f, *f, *f, *f, *f, *f]
+        h: &h [*g, *g, *g, *g, *g, *g, *g, *g, *g]
+        i: &i [*h, *h, *h, *h, *h, *h, *h, *h, *h]");
+    let expected = "YAML aliases expand to too many nodes";
+    test_error::<serde_yaml::Value>(yaml, expected);
+}
+
+#[test]
+fn test_alias_limit() {
+    let yaml = indoc!("
+        ---
+        - &a [1, 2]
+        - *a
+        - *a");
+    let builder = serde_yaml::DeserializerBuilder::new().alias_limit(5);
+    let result = builder.from_str::<Vec<Vec<i32>>>(yaml);
+    assert_eq!("YAML aliases expand to too many nodes",
+               format!("{}", result.unwrap_err()));
+    let builder = serde_yaml::DeserializerBuilder::new().alias_limit(6);
+    assert!(builder.from_str::<Vec<Vec<i32>>>(yaml).is_ok());
+
+    let yaml = indoc!("
+        ---
+        - *a
+        - *a
+        - &a [1, 2]");
+    let builder = serde_yaml::DeserializerBuilder::new()
+        .forward_aliases(true)
+        .alias_limit(5);
+    let result = builder.from_str::<Vec<Vec<i32>>>(yaml);
+    assert_eq!("YAML aliases expand to too many nodes",
+               format!("{}", result.unwrap_err()));
+    let builder = serde_yaml::DeserializerBuilder::new()
+        .forward_aliases(true)
+        .alias_limit(6);
+    assert!(builder.from_str::<Vec<Vec<i32>>>(yaml).is_ok());
+}
+
+#[test]
+fn test_map_value_without_key() {
+    struct ValueFirst;
+
+    impl serde::Serialize for ValueFirst {
+        fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+            where S: serde::Serializer
+        {
+            let mut state = try!(serializer.serialize_map(Some(1)));
+            try!(serializer.serialize_map_value(&mut state, 1));
+            serializer.serialize_map_end(state)
+        }
+    }
+
+    match serde_yaml::to_string(&ValueFirst).unwrap_err() {
+        serde_yaml::Error::MapValueWithoutKey => {}
+        err => panic!("expected MapValueWithoutKey, found {:?}", err),
+    }
+}
+
+#[test]
+fn test_map_key_without_value() {
+    struct KeyTwice;
+
+    impl serde::Serialize for KeyTwice {
+        fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+            where S: serde::Serializer
+        {
+            let mut state = try!(serializer.serialize_map(Some(1)));
+            try!(serializer.serialize_map_key(&mut state, "a"));
+            try!(serializer.serialize_map_key(&mut state, "b"));
+            try!(serializer.serialize_map_value(&mut state, 1));
+            serializer.serialize_map_end(state)
+        }
+    }
+
+    struct KeyLast;
+
+    impl serde::Serialize for KeyLast {
+        fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+            where S: serde::Serializer
+        {
+            let mut state = try!(serializer.serialize_map(Some(1)));
+            try!(serializer.serialize_map_key(&mut state, "a"));
+            serializer.serialize_map_end(state)
+        }
+    }
+
+    match serde_yaml::to_string(&KeyTwice).unwrap_err() {
+        serde_yaml::Error::MapKeyWithoutValue => {}
+        err => panic!("expected MapKeyWithoutValue, found {:?}", err),
+    }
+    match serde_yaml::to_string(&KeyLast).unwrap_err() {
+        serde_yaml::Error::MapKeyWithoutValue => {}
+        err => panic!("expected MapKeyWithoutValue, found {:?}", err),
+    }
+}
+
+#[test]
+fn test_write_error() {
+    use std::error::Error;
+    use std::io;
+
+    /// Accepts `limit` bytes and fails every write after that.
+    struct FailingWriter {
+        limit: usize,
+    }
+
+    impl io::Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.limit == 0 {
+                return Err(io::Error::new(io::ErrorKind::Other, "disk full"));
+            }
+            let n = buf.len().min(self.limit);
+            self.limit -= n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut thing = BTreeMap::new();
+    thing.insert("first", vec![1, 2, 3]);
+    thing.insert("second", vec![4, 5, 6]);
+    let mut writer = FailingWriter { limit: 16 };
+    let err = serde_yaml::to_writer(&mut writer, &thing).unwrap_err();
+    let written = match err {
+        serde_yaml::Error::Write(ref io, written) => {
+            assert_eq!(io::ErrorKind::Other, io.kind());
+            written
+        }
+        ref other => panic!("expected a write error, found {:?}", other),
+    };
+    assert!(0 < written && written <= 16);
+    let expected = format!("Failed to write YAML after {} bytes: disk full",
+                           written);
+    assert_eq!(expected, err.to_string());
+    assert_eq!("disk full", err.cause().unwrap().to_string());
+}
+
+#[test]
+fn test_tuple_wrong_length() {
+    let yaml = "[1, 2]";
+    let expected = "Expected a YAML sequence of size 3 but was size 2";
+    test_error::<(i32, i32, i32)>(yaml, expected);
+    test_error::<[i32; 3]>(yaml, expected);
+
+    let yaml = "[1, 2, 3, 4]";
+    let expected = "Expected a YAML sequence of size 3 but was size 4";
+    test_error::<(i32, i32, i32)>(yaml, expected);
+    test_error::<[i32; 3]>(yaml, expected);
+}
+
+#[test]
+fn test_scan_error_cause() {
+    use std::error::Error;
+    let yaml = "key: [unclosed";
+    let err = serde_yaml::from_str::<serde_yaml::Value>(yaml).unwrap_err();
+    let scan = match err {
+        serde_yaml::Error::Scan(ref scan) => scan,
+        ref other => panic!("expected a scan error, found {:?}", other),
+    };
+    assert!(scan.to_string().ends_with("at line 2 column 1"));
+    let cause = err.cause().unwrap();
+    assert_eq!(scan.to_string(), cause.to_string());
+}
+
+#[test]
+fn test_unknown_variant() {
+    #[derive(Deserialize, Debug)]
+    enum Shape {
+        Circle(u32),
+    }
+    let expected = "Unknown variant `Square`";
+    test_error::<Shape>("Square: 5", expected);
+    test_error::<Shape>("!Square 5", expected);
+}
+
+#[test]
+fn test_from_path() {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+
+    let path = env::temp_dir().join("serde_yaml_test_error_from_path.yaml");
+    File::create(&path).unwrap().write_all(b"a: 1\nb: [2\n").unwrap();
+    let result = serde_yaml::from_path::<_, serde_yaml::Value>(&path);
+    fs::remove_file(&path).unwrap();
+    let err = result.unwrap_err();
+    match err {
+        serde_yaml::Error::File(ref file, ref inner) => {
+            assert_eq!(&path, file);
+            match **inner {
+                serde_yaml::Error::Scan(_) => {}
+                ref other => panic!("expected a scan error, found {:?}", other),
+            }
+        }
+        ref other => panic!("expected a file error, found {:?}", other),
+    }
+    let message = err.to_string();
+    let expected = format!("Error in {}: ", path.display());
+    assert!(message.starts_with(&expected), "{}", message);
+    assert!(message.ends_with("at line 3 column 1"), "{}", message);
+
+    let missing = env::temp_dir().join("serde_yaml_test_missing.yaml");
+    let err = serde_yaml::from_path::<_, serde_yaml::Value>(&missing)
+        .unwrap_err();
+    match err {
+        serde_yaml::Error::File(_, ref inner) => {
+            match **inner {
+                serde_yaml::Error::Io(_) => {}
+                ref other => panic!("expected an io error, found {:?}", other),
+            }
+        }
+        ref other => panic!("expected a file error, found {:?}", other),
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+extern crate serde_yaml;
+
+use serde_yaml::{Event, ScalarStyle};
+
+fn scalar(value: &str, style: ScalarStyle) -> Event {
+    Event::Scalar {
+        value: value.to_owned(),
+        style: style,
+        anchor: None,
+        tag: None,
+    }
+}
+
+#[test]
+fn test_events() {
+    let yaml = indoc!("
+        ---
+        name: &name 'serde'
+        tags: !list [yaml, !!str 1]
+        alias: *name");
+    let events: Vec<_> = serde_yaml::events(yaml)
+        .map(Result::unwrap)
+        .collect();
+
+    let expected = vec![
+        Event::StreamStart,
+        Event::DocumentStart,
+        Event::MappingStart {
+            anchor: None,
+            tag: None,
+        },
+        scalar("name", ScalarStyle::Plain),
+        Event::Scalar {
+            value: "serde".to_owned(),
+            style: ScalarStyle::SingleQuoted,
+            anchor: Some(1),
+            tag: None,
+        },
+        scalar("tags", ScalarStyle::Plain),
+        Event::SequenceStart {
+            anchor: None,
+            tag: Some("!list".to_owned()),
+        },
+        scalar("yaml", ScalarStyle::Plain),
+        Event::Scalar {
+            value: "1".to_owned(),
+            style: ScalarStyle::Plain,
+            anchor: None,
+            tag: Some("!!str".to_owned()),
+        },
+        Event::SequenceEnd,
+        scalar("alias", ScalarStyle::Plain),
+        Event::Alias(1),
+        Event::MappingEnd,
+        Event::DocumentEnd,
+        Event::StreamEnd,
+    ];
+    let found: Vec<_> = events.iter().map(|&(ref event, _)| event).collect();
+    assert_eq!(expected.iter().collect::<Vec<_>>(), found);
+
+    let (_, mark) = events[6];
+    assert_eq!((mark.line(), mark.column()), (3, 13));
+}
+
+#[test]
+fn test_events_error() {
+    let events: Vec<_> = serde_yaml::events("a: [1").collect();
+    let (error, before) = events.split_last().unwrap();
+    assert!(error.is_err());
+    let last = before.last().unwrap().as_ref().unwrap();
+    assert_eq!(last.0, scalar("1", ScalarStyle::Plain));
+    assert!(before.iter().all(Result::is_ok));
+}
@@ -0,0 +1,125 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+extern crate serde_json;
+extern crate serde_yaml;
+
+use self::serde_json::Value;
+
+fn test_round_trip(json: &str, yaml: &str) {
+    let value: Value = serde_json::from_str(json).unwrap();
+    assert_eq!(yaml, serde_yaml::to_string(&value).unwrap());
+    assert_eq!(value, serde_yaml::from_str::<Value>(yaml).unwrap());
+}
+
+#[test]
+fn test_object() {
+    let json = r#"{
+        "name": "serde",
+        "tags": ["yaml", "json"],
+        "owner": {"id": 1, "admin": true, "email": null},
+        "empty": {}
+    }"#;
+    let yaml = indoc!("
+        ---
+        empty: {}
+        name: serde
+        owner: 
+          admin: true
+          email: ~
+          id: 1
+        tags: 
+          - yaml
+          - json");
+    test_round_trip(json, yaml);
+}
+
+#[test]
+fn test_numbers() {
+    let json = "[0, -4, 1.0, 3.5, -0.0, 1e300, 9223372036854775807, \
+                18446744073709551615]";
+    let yaml = indoc!("
+        ---
+        - 0
+        - -4
+        - 1.0
+        - 3.5
+        - -0.0
+        - 1.0e+300
+        - 9223372036854775807
+        - 18446744073709551615");
+    test_round_trip(json, yaml);
+}
+
+#[test]
+fn test_strings() {
+    let json = r#"["1", "1.0", "true", "null", "~", "", "a: b", "- x"]"#;
+    let yaml = indoc!(r#"
+        ---
+        - "1"
+        - "1.0"
+        - "true"
+        - "null"
+        - "~"
+        - ""
+        - "a: b"
+        - "- x""#);
+    test_round_trip(json, yaml);
+}
+
+#[test]
+fn test_json_compatible() {
+    use std::collections::BTreeMap;
+    use std::f64;
+
+    #[derive(Serialize)]
+    struct Service {
+        name: String,
+        ports: Vec<u16>,
+        weight: f64,
+        limit: f64,
+        owner: Option<String>,
+        labels: BTreeMap<i32, String>,
+    }
+    let mut labels = BTreeMap::new();
+    labels.insert(1, String::from("yes"));
+    labels.insert(2, String::from("line\nbreak \"quoted\""));
+    let services = vec![
+        Service {
+            name: String::from("web"),
+            ports: vec![80, 443],
+            weight: 0.5,
+            limit: f64::INFINITY,
+            owner: None,
+            labels: labels,
+        },
+        Service {
+            name: String::from("- db: ~"),
+            ports: vec![],
+            weight: 1.0,
+            limit: 1e300,
+            owner: Some(String::from("ops")),
+            labels: BTreeMap::new(),
+        },
+    ];
+    let builder = serde_yaml::SerializerBuilder::new().json_compatible(true);
+    let output = builder.to_string(&services).unwrap();
+    let expected = concat!(
+        r#"[{"name": "web", "ports": [80, 443], "weight": 0.5, "#,
+        r#""limit": null, "owner": null, "#,
+        r#""labels": {"1": "yes", "2": "line\nbreak \"quoted\""}}, "#,
+        r#"{"name": "- db: ~", "ports": [], "weight": 1.0, "#,
+        r#""limit": 1.0e+300, "owner": "ops", "labels": {}}]"#);
+    assert_eq!(expected, output);
+
+    let from_json: Value = serde_json::from_str(&output).unwrap();
+    let from_yaml: Value = serde_yaml::from_str(&output).unwrap();
+    assert_eq!(from_json, from_yaml);
+    assert_eq!(Some("line\nbreak \"quoted\""),
+               from_json.pointer("/0/labels/2").and_then(Value::as_str));
+}
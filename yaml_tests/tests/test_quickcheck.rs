@@ -0,0 +1,144 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+extern crate quickcheck;
+extern crate serde_yaml;
+
+use std::f64;
+
+use self::quickcheck::{Arbitrary, Gen, QuickCheck};
+use serde_yaml::{Mapping, Value};
+
+/// A `Value` tree of bounded depth, generated with a bias towards the
+/// scalars that are hardest to write back faithfully.
+#[derive(Clone, Debug)]
+struct ArbitraryValue(Value);
+
+impl Arbitrary for ArbitraryValue {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        ArbitraryValue(arbitrary_value(g, 3))
+    }
+
+    fn shrink(&self) -> Box<Iterator<Item = Self>> {
+        let children = match self.0 {
+            Value::Sequence(ref seq) => seq.clone(),
+            Value::Mapping(ref map) => {
+                map.iter()
+                    .flat_map(|(k, v)| vec![k.clone(), v.clone()])
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+        Box::new(children.into_iter().map(ArbitraryValue))
+    }
+}
+
+fn arbitrary_value<G: Gen>(g: &mut G, depth: usize) -> Value {
+    let kinds = if depth == 0 { 6 } else { 8 };
+    match g.gen_range(0, kinds) {
+        0 => Value::Null,
+        1 => Value::Bool(g.gen()),
+        2 => Value::Number(i64::arbitrary(g).into()),
+        3 => Value::Number(arbitrary_float(g).into()),
+        4 => Value::String(String::arbitrary(g)),
+        5 => Value::String(arbitrary_plain(g)),
+        6 => {
+            let len = g.gen_range(0, 4);
+            Value::Sequence((0..len)
+                .map(|_| arbitrary_value(g, depth - 1))
+                .collect())
+        }
+        _ => {
+            let len = g.gen_range(0, 4);
+            let mut map = Mapping::new();
+            for _ in 0..len {
+                // keys need not be strings
+                let k = arbitrary_value(g, depth - 1);
+                let v = arbitrary_value(g, depth - 1);
+                map.insert(k, v);
+            }
+            Value::Mapping(map)
+        }
+    }
+}
+
+fn arbitrary_float<G: Gen>(g: &mut G) -> f64 {
+    let special = [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, -0.0, 1e300,
+                   1e-300, 0.1 + 0.2];
+    if g.gen() {
+        f64::arbitrary(g)
+    } else {
+        *g.choose(&special).unwrap()
+    }
+}
+
+/// A short string made of the characters that take part in YAML syntax or
+/// in the plain scalars that resolve to other types, like `1e3` or `~`.
+fn arbitrary_plain<G: Gen>(g: &mut G) -> String {
+    const CHARS: &[char] = &[
+        'a', 'e', 'n', 'o', 'y', 'Y', 'N', 'x', 'b', 'f', 'i', 'l', 'r', 'u',
+        'E', 'T', '0', '1', '9', '.', '-', '+', '_', ':', ' ', '#', '~', '\'',
+        '"', '!', '&', '*', '\n', '\t', '[', '{', ',', '?', '|', '>', '%', '@',
+        '\u{e9}',
+    ];
+    let len = g.gen_range(0, 6);
+    (0..len).map(|_| *g.choose(CHARS).unwrap()).collect()
+}
+
+#[test]
+fn test_value_round_trip() {
+    fn round_trip(value: ArbitraryValue) -> bool {
+        let yaml = serde_yaml::to_string(&value.0).unwrap();
+        serde_yaml::from_str::<Value>(&yaml).ok() == Some(value.0)
+    }
+    QuickCheck::new()
+        .tests(1000)
+        .quickcheck(round_trip as fn(ArbitraryValue) -> bool);
+}
+
+#[test]
+fn test_value_round_trip_canonical() {
+    fn round_trip(value: ArbitraryValue) -> bool {
+        let builder = serde_yaml::SerializerBuilder::new().canonical(true);
+        let yaml = builder.to_string(&value.0).unwrap();
+        serde_yaml::from_str::<Value>(&yaml).ok() == Some(value.0)
+    }
+    QuickCheck::new()
+        .tests(1000)
+        .quickcheck(round_trip as fn(ArbitraryValue) -> bool);
+}
+
+#[test]
+fn test_value_round_trip_line_width() {
+    fn round_trip(value: ArbitraryValue) -> bool {
+        let builder = serde_yaml::SerializerBuilder::new().line_width(8);
+        let yaml = builder.to_string(&value.0).unwrap();
+        serde_yaml::from_str::<Value>(&yaml).ok() == Some(value.0)
+    }
+    QuickCheck::new()
+        .tests(1000)
+        .quickcheck(round_trip as fn(ArbitraryValue) -> bool);
+}
+
+#[test]
+fn test_value_round_trip_quote_styles() {
+    fn round_trip(value: ArbitraryValue) -> bool {
+        use serde_yaml::QuoteStyle;
+        let styles = [QuoteStyle::Single, QuoteStyle::Double,
+                      QuoteStyle::Plain];
+        styles.iter().all(|&style| {
+            let builder = serde_yaml::SerializerBuilder::new()
+                .quote_style(style);
+            let yaml = builder.to_string(&value.0).unwrap();
+            serde_yaml::from_str::<Value>(&yaml).ok().as_ref() == Some(&value.0)
+        })
+    }
+    QuickCheck::new()
+        .tests(1000)
+        .quickcheck(round_trip as fn(ArbitraryValue) -> bool);
+}
@@ -0,0 +1,86 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+extern crate serde_yaml;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct File {
+    #[serde(serialize_with = "serde_yaml::radix::octal::serialize",
+            deserialize_with = "serde_yaml::radix::octal::deserialize")]
+    mode: u32,
+    #[serde(serialize_with = "serde_yaml::radix::hex::serialize",
+            deserialize_with = "serde_yaml::radix::hex::deserialize")]
+    flags: u8,
+}
+
+#[test]
+fn test_round_trip() {
+    let thing = File {
+        mode: 0o644,
+        flags: 0xFF,
+    };
+    let yaml = indoc!("
+        ---
+        mode: 0o644
+        flags: 0xFF");
+    assert_eq!(yaml, serde_yaml::to_string(&thing).unwrap());
+    assert_eq!(thing, serde_yaml::from_str(yaml).unwrap());
+}
+
+#[test]
+fn test_zero() {
+    let thing = File {
+        mode: 0,
+        flags: 0,
+    };
+    let yaml = indoc!("
+        ---
+        mode: 0o0
+        flags: 0x0");
+    assert_eq!(yaml, serde_yaml::to_string(&thing).unwrap());
+    assert_eq!(thing, serde_yaml::from_str(yaml).unwrap());
+}
+
+#[test]
+fn test_any_form() {
+    let yaml = indoc!("
+        ---
+        mode: 420
+        flags: 0o17");
+    let expected = File {
+        mode: 0o644,
+        flags: 0xF,
+    };
+    assert_eq!(expected, serde_yaml::from_str(yaml).unwrap());
+}
+
+#[test]
+fn test_value_round_trip() {
+    let thing = File {
+        mode: 0o644,
+        flags: 0xFF,
+    };
+    let value = serde_yaml::to_value(&thing);
+    assert_eq!(Some(0o644), value.get("mode").and_then(|v| v.as_i64()));
+    assert_eq!(thing, serde_yaml::from_value(value).unwrap());
+}
+
+#[test]
+fn test_from_string() {
+    let yaml = indoc!(r#"
+        ---
+        mode: "0o644"
+        flags: '0xFF'"#);
+    let expected = File {
+        mode: 0o644,
+        flags: 0xFF,
+    };
+    assert_eq!(expected, serde_yaml::from_str(yaml).unwrap());
+    assert!(serde_yaml::from_str::<File>("mode: '644'\nflags: 0").is_err());
+    assert!(serde_yaml::from_str::<File>("mode: 0\nflags: 0x100").is_err());
+}
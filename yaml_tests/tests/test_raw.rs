@@ -0,0 +1,78 @@
+// Copyright 2016 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+extern crate serde_yaml;
+
+use serde_yaml::{RawDocument, Value};
+
+#[test]
+fn test_set_keeps_comments() {
+    let yaml = indoc!("
+        # Server settings
+        server:
+          host: localhost # overridden in production
+          # The port to listen on
+          port: 80
+
+        # Mirrors, in order of preference
+        mirrors:
+          - a.example.com
+          - b.example.com # slow
+        ");
+    let mut doc = RawDocument::parse(yaml).unwrap();
+    doc.set(&["server", "port"], &8080).unwrap();
+    doc.set(&["mirrors", "1"], &"c.example.com").unwrap();
+    let expected = indoc!("
+        # Server settings
+        server:
+          host: localhost # overridden in production
+          # The port to listen on
+          port: 8080
+
+        # Mirrors, in order of preference
+        mirrors:
+          - a.example.com
+          - c.example.com # slow
+        ");
+    assert_eq!(expected, doc.as_str());
+    assert_eq!(Some(Value::Number(8080.into())), doc.get(&["server", "port"]));
+}
+
+#[test]
+fn test_get() {
+    let yaml = indoc!(r#"
+        ---
+        "quoted key": 'it''s' # comment
+        list:
+        - name: first
+          value: true
+        - name: second
+        text: |
+          not: a key
+        after: "a # b"
+        "#);
+    let doc = RawDocument::parse(yaml).unwrap();
+    let string = |s: &str| Some(Value::String(s.to_owned()));
+    assert_eq!(string("it's"), doc.get(&["quoted key"]));
+    assert_eq!(string("first"), doc.get(&["list", "0", "name"]));
+    assert_eq!(Some(Value::Bool(true)), doc.get(&["list", "0", "value"]));
+    assert_eq!(string("second"), doc.get(&["list", "1", "name"]));
+    assert_eq!(None, doc.get(&["text", "not"]));
+    assert_eq!(string("a # b"), doc.get(&["after"]));
+    assert_eq!(None, doc.get(&["missing"]));
+}
+
+#[test]
+fn test_set_errors() {
+    let mut doc = RawDocument::parse("a: 1\n").unwrap();
+    assert_eq!("No scalar found at `b/c`",
+               format!("{}", doc.set(&["b", "c"], &1).unwrap_err()));
+    assert_eq!("Expected a value that serializes as a scalar",
+               format!("{}", doc.set(&["a"], &vec![1, 2]).unwrap_err()));
+    assert_eq!("a: 1\n", doc.as_str());
+}
@@ -6,6 +6,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+extern crate chrono;
 extern crate serde;
 extern crate serde_yaml;
 
@@ -40,6 +41,876 @@ fn test_float() {
     test_serde(thing, yaml);
 }
 
+#[test]
+fn test_float_whole_number() {
+    let thing = 2.0;
+    let yaml = indoc!("
+        ---
+        2.0");
+    test_serde(thing, yaml);
+}
+
+#[test]
+fn test_float_round_trip() {
+    let yaml = serde_yaml::to_string(&1.0f64).unwrap();
+    let token = &yaml["---\n".len()..];
+    assert!(token.contains('.') || token.contains('e'));
+    assert_eq!(1.0, serde_yaml::from_str::<f64>(&yaml).unwrap());
+
+    let thing = 0.1 + 0.2;
+    let yaml = indoc!("
+        ---
+        0.30000000000000004");
+    test_serde(thing, yaml);
+}
+
+#[test]
+fn test_float_exponent() {
+    let thing = vec![1e300, -2.5e-300, 5e-324, 1.7976931348623157e308, 1e16,
+                     1e15, 1e-5, 1e-6];
+    let yaml = indoc!("
+        ---
+        - 1.0e+300
+        - -2.5e-300
+        - 5.0e-324
+        - 1.7976931348623157e+308
+        - 1.0e+16
+        - 1000000000000000.0
+        - 0.00001
+        - 1.0e-6");
+    test_serde(thing, yaml);
+}
+
+#[test]
+fn test_float_special() {
+    use std::f64;
+    let thing = vec![f64::INFINITY, f64::NEG_INFINITY, f64::NAN];
+    let yaml = indoc!("
+        ---
+        - .inf
+        - -.inf
+        - .nan");
+    assert_eq!(yaml, serde_yaml::to_string(&thing).unwrap());
+    let deserialized: Vec<f64> = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(thing[..2], deserialized[..2]);
+    assert!(deserialized[2].is_nan());
+}
+
+#[test]
+fn test_float_whole_number_plain() {
+    use serde_yaml::{SerializerBuilder, WholeFloat};
+    let builder = SerializerBuilder::new()
+        .float_whole_number_style(WholeFloat::Plain);
+    let yaml = indoc!("
+        ---
+        - 2
+        - -3
+        - 0.5");
+    assert_eq!(yaml, builder.to_string(&vec![2.0, -3.0, 0.5]).unwrap());
+}
+
+#[test]
+fn test_float_format() {
+    use serde_yaml::{FloatFormat, SerializerBuilder};
+    let v = 3.14159;
+
+    let builder = SerializerBuilder::new().float_format(FloatFormat::Shortest);
+    let yaml = builder.to_string(&v).unwrap();
+    assert_eq!("---\n3.14159", yaml);
+    assert_eq!(v, serde_yaml::from_str::<f64>(&yaml).unwrap());
+
+    let builder = SerializerBuilder::new().float_format(FloatFormat::Fixed(3));
+    assert_eq!("---\n3.142", builder.to_string(&v).unwrap());
+    assert_eq!("---\n3.140", builder.to_string(&3.14).unwrap());
+    let yaml = builder.to_string(&0.25).unwrap();
+    assert_eq!("---\n0.250", yaml);
+    assert_eq!(0.25, serde_yaml::from_str::<f64>(&yaml).unwrap());
+
+    let builder = SerializerBuilder::new().float_format(FloatFormat::Fixed(0));
+    assert_eq!("---\n3.0", builder.to_string(&v).unwrap());
+
+    let builder = SerializerBuilder::new()
+        .float_format(FloatFormat::Scientific);
+    let yaml = builder.to_string(&vec![v, 2.0, -0.001]).unwrap();
+    let expected = indoc!("
+        ---
+        - 3.14159e+0
+        - 2.0e+0
+        - -1.0e-3");
+    assert_eq!(expected, yaml);
+    assert_eq!(vec![v, 2.0, -0.001],
+               serde_yaml::from_str::<Vec<f64>>(&yaml).unwrap());
+}
+
+#[test]
+fn test_bytes() {
+    use serde::bytes::ByteBuf;
+    let thing = ByteBuf::from(b"hello\xff".to_vec());
+    let yaml = indoc!("
+        ---
+        !!binary aGVsbG//");
+    test_serde(thing, yaml);
+}
+
+#[test]
+fn test_bytes_encoding() {
+    use serde::bytes::ByteBuf;
+    use serde_yaml::{BytesEncoding, DeserializerBuilder, SerializerBuilder};
+    let thing = ByteBuf::from(vec![0, 10, 255]);
+
+    let builder = SerializerBuilder::new()
+        .bytes_encoding(BytesEncoding::IntArray);
+    let yaml = indoc!("
+        ---
+        - 0
+        - 10
+        - 255");
+    assert_eq!(yaml, builder.to_string(&thing).unwrap());
+    assert_eq!(thing, serde_yaml::from_str(yaml).unwrap());
+
+    let builder = SerializerBuilder::new()
+        .bytes_encoding(BytesEncoding::Base64);
+    let yaml = indoc!("
+        ---
+        !!binary AAr/");
+    assert_eq!(yaml, builder.to_string(&thing).unwrap());
+    assert_eq!(thing, serde_yaml::from_str(yaml).unwrap());
+
+    let builder = SerializerBuilder::new().bytes_encoding(BytesEncoding::Hex);
+    let yaml = indoc!("
+        ---
+        000aff");
+    assert_eq!(yaml, builder.to_string(&thing).unwrap());
+    let builder = DeserializerBuilder::new().hex_bytes(true);
+    assert_eq!(thing, builder.from_str(yaml).unwrap());
+}
+
+#[test]
+fn test_char() {
+    let thing = vec!['a', '~', 'y', 'n', ' ', '"', ':'];
+    let yaml = indoc!(r#"
+        ---
+        - a
+        - "~"
+        - "y"
+        - "n"
+        - " "
+        - "\""
+        - ":""#);
+    test_serde(thing, yaml);
+}
+
+#[test]
+fn test_line_ending() {
+    use serde_yaml::{LineEnding, SerializerBuilder, Value};
+    let input = "---\r\na: 1\nb:\r\n  - c\r\n  - d\n";
+    let value: Value = serde_yaml::from_str(input).unwrap();
+    let yaml = indoc!("
+        ---
+        a: 1
+        b: 
+          - c
+          - d");
+    assert_eq!(yaml, serde_yaml::to_string(&value).unwrap());
+
+    let builder = SerializerBuilder::new().line_ending(LineEnding::CrLf);
+    assert_eq!(yaml.replace('\n', "\r\n"), builder.to_string(&value).unwrap());
+}
+
+#[test]
+fn test_indent() {
+    use serde_yaml::{SerializerBuilder, Value};
+    let input = indoc!("
+        ---
+        a:
+          b:
+            c: 1
+          d:
+            - e
+            - f");
+    let value: Value = serde_yaml::from_str(input).unwrap();
+    let yaml = indoc!("
+        ---
+        a: 
+            b: 
+                c: 1
+            d: 
+                - e
+                - f");
+    let builder = SerializerBuilder::new().indent(4);
+    assert_eq!(yaml, builder.to_string(&value).unwrap());
+
+    let yaml = indoc!("
+        ---
+        a: 
+         b: 
+          c: 1
+         d: 
+          - e
+          - f");
+    let builder = SerializerBuilder::new().indent(0);
+    assert_eq!(yaml, builder.to_string(&value).unwrap());
+    assert_eq!(value, serde_yaml::from_str::<Value>(yaml).unwrap());
+}
+
+#[test]
+fn test_omit_empty_collections() {
+    use serde_yaml::SerializerBuilder;
+    #[derive(Serialize)]
+    struct Data {
+        a: Vec<i32>,
+        b: BTreeMap<String, i32>,
+        c: Vec<Vec<i32>>,
+        d: i32,
+    }
+    let thing = Data {
+        a: Vec::new(),
+        b: BTreeMap::new(),
+        c: vec![Vec::new()],
+        d: 1,
+    };
+    let yaml = indoc!("
+        ---
+        c: 
+          - []
+        d: 1");
+    let builder = SerializerBuilder::new().omit_empty_collections(true);
+    assert_eq!(yaml, builder.to_string(&thing).unwrap());
+}
+
+#[test]
+fn test_sort_keys() {
+    use std::collections::HashMap;
+    use serde_yaml::SerializerBuilder;
+    let mut thing = HashMap::new();
+    for (i, key) in "qwertyuiopasdfghjklzxcvbnm".chars().enumerate() {
+        thing.insert(key.to_string(), i);
+    }
+    let builder = SerializerBuilder::new().sort_keys(true);
+    let yaml = builder.to_string(&thing).unwrap();
+    assert!(yaml.starts_with("---\na: 10\nb: 23\nc: 21\n"));
+    assert!(yaml.ends_with("\nz: 19"));
+
+    let mut again = HashMap::new();
+    for (key, value) in thing {
+        again.insert(key, value);
+    }
+    assert_eq!(yaml, builder.to_string(&again).unwrap());
+}
+
+#[test]
+fn test_map_key_order() {
+    use std::collections::HashMap;
+    use serde_yaml::{Mapping, SerializerBuilder, Value};
+    let keys = ["zulu", "alpha", "mike", "bravo"];
+
+    let btree: BTreeMap<&str, usize> =
+        keys.iter().enumerate().map(|(i, k)| (*k, i)).collect();
+    let sorted = indoc!("
+        ---
+        alpha: 1
+        bravo: 3
+        mike: 2
+        zulu: 0");
+    assert_eq!(sorted, serde_yaml::to_string(&btree).unwrap());
+
+    let mut mapping = Mapping::new();
+    for (i, key) in keys.iter().enumerate() {
+        mapping.insert(Value::from(*key), Value::from(i as u64));
+    }
+    let inserted = indoc!("
+        ---
+        zulu: 0
+        alpha: 1
+        mike: 2
+        bravo: 3");
+    assert_eq!(inserted, serde_yaml::to_string(&mapping).unwrap());
+    let read: Mapping = serde_yaml::from_str(inserted).unwrap();
+    let read_keys: Vec<_> = read.keys().map(|k| k.as_str().unwrap()).collect();
+    assert_eq!(keys, &read_keys[..]);
+    assert_eq!(inserted, serde_yaml::to_string(&read).unwrap());
+
+    let builder = SerializerBuilder::new().sort_keys(true);
+    assert_eq!(sorted, builder.to_string(&mapping).unwrap());
+    let hash: HashMap<&str, usize> =
+        keys.iter().enumerate().map(|(i, k)| (*k, i)).collect();
+    assert_eq!(sorted, builder.to_string(&hash).unwrap());
+}
+
+#[test]
+fn test_sort_mixed_keys() {
+    use serde_yaml::{SerializerBuilder, Value};
+    let input = "{b: 1, 2.5: 2, a: 3, 10: 4, ~: 5, true: 6, [1]: 7, false: 8}";
+    let value: Value = serde_yaml::from_str(input).unwrap();
+    let yaml = indoc!("
+        ---
+        ~: 5
+        false: 8
+        true: 6
+        2.5: 2
+        10: 4
+        a: 3
+        b: 1
+        ? [1]
+        : 7");
+    let builder = SerializerBuilder::new().sort_keys(true);
+    assert_eq!(yaml, builder.to_string(&value).unwrap());
+}
+
+#[test]
+fn test_sort_number_keys() {
+    use std::f64;
+    use serde_yaml::{Mapping, SerializerBuilder, Value};
+    let keys = vec![
+        Value::from(f64::NAN),
+        Value::from(u64::max_value()),
+        Value::from(0.5),
+        Value::from(f64::INFINITY),
+        Value::from(u64::max_value() - 1),
+        Value::from(-1),
+    ];
+    let mut mapping = Mapping::new();
+    for (i, key) in keys.into_iter().enumerate() {
+        mapping.insert(key, Value::from(i as u64));
+    }
+    let yaml = indoc!("
+        ---
+        -1: 5
+        0.5: 2
+        18446744073709551614: 4
+        18446744073709551615: 1
+        .inf: 3
+        .nan: 0");
+    let builder = SerializerBuilder::new().sort_keys(true);
+    assert_eq!(yaml, builder.to_string(&mapping).unwrap());
+}
+
+#[test]
+fn test_set() {
+    use std::collections::BTreeSet;
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Data {
+        #[serde(serialize_with = "serde_yaml::set::serialize")]
+        features: BTreeSet<String>,
+        default: BTreeSet<String>,
+    }
+    let mut features = BTreeSet::new();
+    features.insert(String::from("std"));
+    features.insert(String::from("chrono"));
+    let thing = Data {
+        features: features.clone(),
+        default: features,
+    };
+    let yaml = indoc!("
+        ---
+        features: !!set
+          chrono: ~
+          std: ~
+        default: 
+          - chrono
+          - std");
+    test_serde(thing, yaml);
+}
+
+#[test]
+fn test_document_markers() {
+    use serde_yaml::SerializerBuilder;
+    let mut map = BTreeMap::new();
+    map.insert(String::from("a"), 1);
+    let seq = vec![1, 2];
+    let cases = [
+        (true, false, "---\na: 1", "---\n- 1\n- 2"),
+        (false, false, "a: 1", "- 1\n- 2"),
+        (true, true, "---\na: 1\n...", "---\n- 1\n- 2\n..."),
+        (false, true, "a: 1\n...", "- 1\n- 2\n..."),
+    ];
+    for &(start, end, map_yaml, seq_yaml) in &cases {
+        let builder = SerializerBuilder::new()
+            .explicit_start(start)
+            .explicit_end(end);
+        assert_eq!(map_yaml, builder.to_string(&map).unwrap());
+        assert_eq!(seq_yaml, builder.to_string(&seq).unwrap());
+        assert_eq!(map, serde_yaml::from_str(map_yaml).unwrap());
+        assert_eq!(seq, serde_yaml::from_str::<Vec<i32>>(seq_yaml).unwrap());
+    }
+}
+
+#[test]
+fn test_builder_reuse() {
+    use std::collections::HashMap;
+    use serde_yaml::SerializerBuilder;
+    let builder = SerializerBuilder::new()
+        .indent(4)
+        .sort_keys(true)
+        .omit_empty_collections(true)
+        .explicit_start(false)
+        .explicit_end(true);
+
+    let mut inner = HashMap::new();
+    inner.insert(String::from("b"), vec![1, 2]);
+    inner.insert(String::from("a"), Vec::new());
+    let mut outer = HashMap::new();
+    outer.insert(String::from("z"), inner.clone());
+    outer.insert(String::from("y"), HashMap::new());
+
+    let yaml = indoc!("
+        b: 
+            - 1
+            - 2
+        ...");
+    assert_eq!(yaml, builder.to_string(&inner).unwrap());
+
+    let yaml = indoc!("
+        z: 
+            b: 
+                - 1
+                - 2
+        ...");
+    assert_eq!(yaml, builder.to_string(&outer).unwrap());
+    let mut written = Vec::new();
+    builder.to_writer(&mut written, &outer).unwrap();
+    assert_eq!(yaml.as_bytes(), &written[..]);
+
+    let yaml = indoc!("
+        - 
+            b: 
+                - 1
+                - 2
+        - 3
+        ...");
+    let seq = vec![serde_yaml::to_value(&inner), serde_yaml::to_value(&3)];
+    assert_eq!(yaml, builder.to_string(&seq).unwrap());
+    assert_eq!("true\n...", builder.to_string(&true).unwrap());
+}
+
+#[test]
+fn test_canonical() {
+    use self::chrono::{DateTime, TimeZone, UTC};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Server {
+        host: String,
+        port: u16,
+        ratio: f64,
+        debug: bool,
+        backup: Option<String>,
+        tags: Vec<String>,
+        env: BTreeMap<String, String>,
+        #[serde(serialize_with = "serde_yaml::radix::octal::serialize",
+                deserialize_with = "serde_yaml::radix::octal::deserialize")]
+        mode: u32,
+        #[serde(serialize_with = "serde_yaml::timestamp::serialize",
+                deserialize_with = "serde_yaml::timestamp::deserialize")]
+        started: DateTime<UTC>,
+    }
+    let thing = Server {
+        host: "on".to_owned(),
+        port: 8080,
+        ratio: 0.5,
+        debug: true,
+        backup: None,
+        tags: vec!["a".to_owned(), "b c".to_owned()],
+        env: BTreeMap::new(),
+        mode: 0o644,
+        started: UTC.ymd(2001, 12, 15).and_hms(2, 59, 43),
+    };
+    let yaml = indoc!(r#"
+        ---
+        !!map {
+          ? !!str "host"
+          : !!str "on",
+          ? !!str "port"
+          : !!int "8080",
+          ? !!str "ratio"
+          : !!float "0.5",
+          ? !!str "debug"
+          : !!bool "true",
+          ? !!str "backup"
+          : !!null "",
+          ? !!str "tags"
+          : !!seq [
+            !!str "a",
+            !!str "b c",
+          ],
+          ? !!str "env"
+          : !!map {},
+          ? !!str "mode"
+          : !!int "420",
+          ? !!str "started"
+          : !!timestamp "2001-12-15T02:59:43Z",
+        }"#);
+    let builder = serde_yaml::SerializerBuilder::new().canonical(true);
+    assert_eq!(yaml, builder.to_string(&thing).unwrap());
+    assert_eq!(thing, serde_yaml::from_str::<Server>(yaml).unwrap());
+    let value = serde_yaml::to_value(&thing);
+    assert_eq!(value, serde_yaml::from_str::<serde_yaml::Value>(yaml).unwrap());
+
+    let thing = vec![vec![1, 2], vec![]];
+    let yaml = indoc!(r#"
+        ---
+        !!seq [
+          !!seq [
+            !!int "1",
+            !!int "2",
+          ],
+          !!seq [],
+        ]"#);
+    assert_eq!(yaml, builder.to_string(&thing).unwrap());
+    assert_eq!(thing, serde_yaml::from_str::<Vec<Vec<i32>>>(yaml).unwrap());
+}
+
+#[test]
+fn test_tag_strings() {
+    use serde_yaml::SerializerBuilder;
+    let mut thing = BTreeMap::new();
+    thing.insert(String::from("name"), vec![String::from("a"),
+                                            String::from("true")]);
+    thing.insert(String::from("port"), vec![String::from("8080")]);
+    let yaml = indoc!(r#"
+        ---
+        !!str name: 
+          - !!str a
+          - !!str "true"
+        !!str port: 
+          - !!str "8080""#);
+    let builder = SerializerBuilder::new().tag_strings(true);
+    assert_eq!(yaml, builder.to_string(&thing).unwrap());
+    assert_eq!(thing, serde_yaml::from_str(yaml).unwrap());
+}
+
+#[test]
+fn test_tag_ambiguous_strings() {
+    use serde_yaml::SerializerBuilder;
+    let mut thing = BTreeMap::new();
+    thing.insert(String::from("1.10"), vec![String::from("1.10"),
+                                            String::from("007"),
+                                            String::from("true"),
+                                            String::from("a: b"),
+                                            String::from("v1.10")]);
+    let yaml = indoc!(r#"
+        ---
+        !!str "1.10": 
+          - !!str "1.10"
+          - !!str "007"
+          - !!str "true"
+          - "a: b"
+          - v1.10"#);
+    let builder = SerializerBuilder::new().tag_ambiguous_strings(true);
+    assert_eq!(yaml, builder.to_string(&thing).unwrap());
+    assert_eq!(thing, serde_yaml::from_str(yaml).unwrap());
+}
+
+#[test]
+fn test_line_width() {
+    use serde_yaml::SerializerBuilder;
+    let text = String::from("The quick brown fox jumps over the lazy dog, \
+                             then keeps running well past the end of \
+                             any sensible line");
+    let mut thing = BTreeMap::new();
+    thing.insert(String::from("text"), vec![text.clone()]);
+    thing.insert(String::from("short"), vec![String::from("a b c")]);
+    let yaml = indoc!("
+        ---
+        short: 
+          - a b c
+        text: 
+          - >-
+            The quick brown fox jumps over the
+            lazy dog, then keeps running well
+            past the end of any sensible line");
+    let builder = SerializerBuilder::new().line_width(40);
+    let serialized = builder.to_string(&thing).unwrap();
+    assert_eq!(yaml, serialized);
+    assert!(serialized.lines().all(|line| line.chars().count() <= 40));
+    assert_eq!(thing, serde_yaml::from_str(&serialized).unwrap());
+}
+
+#[test]
+fn test_multi_doc_writer() {
+    use serde_yaml::{MultiDocWriter, Value};
+    let mut map = BTreeMap::new();
+    map.insert(String::from("k"), String::from("v"));
+    let mut writer = MultiDocWriter::new(Vec::new());
+    writer.write(&map).unwrap();
+    writer.write(&vec![1, 2]).unwrap();
+    writer.write(&"end").unwrap();
+    let yaml = String::from_utf8(writer.into_inner()).unwrap();
+    let expected = indoc!("
+        ---
+        k: v
+        ---
+        - 1
+        - 2
+        ---
+        end");
+    assert_eq!(expected, yaml);
+
+    let docs: Vec<Value> = serde_yaml::from_str_multi(&yaml)
+        .into_iter()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(docs, vec![serde_yaml::to_value(&map),
+                          serde_yaml::to_value(&vec![1, 2]),
+                          serde_yaml::to_value(&"end")]);
+}
+
+#[test]
+fn test_null_style() {
+    use serde_yaml::{NullStyle, SerializerBuilder};
+    let mut thing = BTreeMap::new();
+    thing.insert(String::from("a"), None);
+    thing.insert(String::from("b"), Some(vec![None, Some(1)]));
+    let expected = [
+        (NullStyle::Tilde, indoc!("
+            ---
+            a: ~
+            b: 
+              - ~
+              - 1")),
+        (NullStyle::Null, indoc!("
+            ---
+            a: null
+            b: 
+              - null
+              - 1")),
+        (NullStyle::Empty, indoc!("
+            ---
+            a:
+            b: 
+              -
+              - 1")),
+    ];
+    for &(style, yaml) in &expected {
+        let builder = SerializerBuilder::new().null_style(style);
+        assert_eq!(yaml, builder.to_string(&thing).unwrap());
+        assert_eq!(thing, serde_yaml::from_str(yaml).unwrap());
+        let unit = builder.to_string(&()).unwrap();
+        serde_yaml::from_str::<()>(&unit).unwrap();
+    }
+}
+
+#[test]
+fn test_quote_style() {
+    use serde_yaml::{DeserializerBuilder, QuoteStyle, ScalarResolution,
+                     SerializerBuilder};
+    let thing = vec!["hello", "it's", "a-b", "true", "two\nlines", "1:30",
+                     "190:20:30", "1:30.5"];
+    let expected = [
+        (QuoteStyle::Auto, indoc!(r#"
+            ---
+            - hello
+            - it's
+            - "a-b"
+            - "true"
+            - "two\nlines"
+            - "1:30"
+            - "190:20:30"
+            - "1:30.5""#)),
+        (QuoteStyle::Single, indoc!(r#"
+            ---
+            - 'hello'
+            - 'it''s'
+            - 'a-b'
+            - 'true'
+            - "two\nlines"
+            - '1:30'
+            - '190:20:30'
+            - '1:30.5'"#)),
+        (QuoteStyle::Double, indoc!(r#"
+            ---
+            - "hello"
+            - "it's"
+            - "a-b"
+            - "true"
+            - "two\nlines"
+            - "1:30"
+            - "190:20:30"
+            - "1:30.5""#)),
+        (QuoteStyle::Plain, indoc!(r#"
+            ---
+            - hello
+            - it's
+            - a-b
+            - "true"
+            - "two\nlines"
+            - "1:30"
+            - "190:20:30"
+            - "1:30.5""#)),
+    ];
+    let yaml11 = DeserializerBuilder::new()
+        .scalar_resolution(ScalarResolution::Yaml11);
+    for &(style, yaml) in &expected {
+        let builder = SerializerBuilder::new().quote_style(style);
+        assert_eq!(yaml, builder.to_string(&thing).unwrap());
+        assert_eq!(thing, serde_yaml::from_str::<Vec<String>>(yaml).unwrap());
+        assert_eq!(thing, yaml11.from_str::<Vec<String>>(yaml).unwrap());
+    }
+}
+
+#[test]
+fn test_paths() {
+    use serde_yaml::{QuoteStyle, SerializerBuilder};
+    use std::path::PathBuf;
+    let thing: Vec<PathBuf> = vec![
+        r"C:\Program Files\app".into(),
+        r"C:\".into(),
+        r"\\server\share\a b".into(),
+        "/usr/local/bin".into(),
+        "/home/me/My Documents/notes: draft.txt".into(),
+        "relative/path with spaces/".into(),
+        "./#hash".into(),
+        "~/config".into(),
+    ];
+    let yaml = indoc!(r#"
+        ---
+        - "C:\\Program Files\\app"
+        - "C:\\"
+        - "\\\\server\\share\\a b"
+        - /usr/local/bin
+        - "/home/me/My Documents/notes: draft.txt"
+        - relative/path with spaces/
+        - "./#hash"
+        - ~/config"#);
+    test_serde(thing.clone(), yaml);
+
+    for &style in &[QuoteStyle::Single, QuoteStyle::Double, QuoteStyle::Plain] {
+        let builder = SerializerBuilder::new().quote_style(style);
+        let yaml = builder.to_string(&thing).unwrap();
+        assert_eq!(thing, serde_yaml::from_str::<Vec<PathBuf>>(&yaml).unwrap());
+    }
+}
+
+#[test]
+fn test_capacity() {
+    use serde_yaml::SerializerBuilder;
+    let thing = vec![1, 2, 3];
+    let vec = SerializerBuilder::new().capacity(4096).to_vec(&thing).unwrap();
+    assert!(vec.capacity() >= 4096);
+    assert_eq!(serde_yaml::to_vec(&thing).unwrap(), vec);
+    let builder = SerializerBuilder::new().capacity(0);
+    assert_eq!(serde_yaml::to_string(&thing).unwrap(),
+               builder.to_string(&thing).unwrap());
+}
+
+#[test]
+fn test_key_transform() {
+    use serde_yaml::SerializerBuilder;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Server {
+        host: String,
+        labels: BTreeMap<String, String>,
+    }
+    let mut labels = BTreeMap::new();
+    labels.insert(String::from("tier"), String::from("web"));
+    let thing = Server {
+        host: String::from("host"),
+        labels: labels,
+    };
+
+    let builder = SerializerBuilder::new()
+        .key_transform(|key| key.to_uppercase());
+    let yaml = indoc!("
+        ---
+        HOST: host
+        LABELS: 
+          TIER: web");
+    assert_eq!(yaml, builder.to_string(&thing).unwrap());
+
+    // The rewritten key is quoted if it needs to be.
+    let builder = SerializerBuilder::new()
+        .key_transform(|key| format!("- {}", key));
+    let yaml = indoc!(r#"
+        ---
+        "- host": host
+        "- labels": 
+          "- tier": web"#);
+    assert_eq!(yaml, builder.to_string(&thing).unwrap());
+    let value: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(Some("host"), value.get("- host").and_then(|v| v.as_str()));
+}
+
+#[test]
+fn test_comments() {
+    use serde_yaml::SerializerBuilder;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Config {
+        name: String,
+        servers: Vec<Server>,
+    }
+    let thing = Config {
+        name: String::from("app"),
+        servers: vec![Server {
+                          host: String::from("a"),
+                          port: 80,
+                      }],
+    };
+
+    let builder = SerializerBuilder::new()
+        .comment(&["name"], "The name of the app")
+        .comment(&["servers", "0", "port"], "Where to listen\n\nfor requests")
+        .comment(&["missing"], "Not written");
+    let yaml = indoc!("
+        ---
+        # The name of the app
+        name: app
+        servers: 
+          - 
+            host: a
+            # Where to listen
+            #
+            # for requests
+            port: 80");
+    let written = builder.to_string(&thing).unwrap();
+    assert_eq!(yaml, written);
+    assert_eq!(thing, serde_yaml::from_str(&written).unwrap());
+
+    // Comments are left out of JSON.
+    let builder = SerializerBuilder::new()
+        .json_compatible(true)
+        .comment(&["name"], "The name of the app");
+    assert!(!builder.to_string(&thing).unwrap().contains('#'));
+}
+
+#[test]
+fn test_indent_sequences() {
+    use serde_yaml::SerializerBuilder;
+    let mut inner = BTreeMap::new();
+    inner.insert(String::from("items"), vec![1, 2]);
+    let mut thing = BTreeMap::new();
+    thing.insert(String::from("outer"), inner);
+
+    let indented = indoc!("
+        ---
+        outer: 
+          items: 
+            - 1
+            - 2");
+    let flush = indoc!("
+        ---
+        outer: 
+          items: 
+          - 1
+          - 2");
+    for &(enabled, yaml, column) in &[(true, indented, 4), (false, flush, 2)] {
+        let builder = SerializerBuilder::new().indent_sequences(enabled);
+        let serialized = builder.to_string(&thing).unwrap();
+        assert_eq!(yaml, serialized);
+        let items = serialized.lines()
+            .filter(|line| line.trim_left().starts_with("- "));
+        for line in items {
+            assert_eq!(line.find('-'), Some(column));
+        }
+        assert_eq!(thing, serde_yaml::from_str(yaml).unwrap());
+    }
+}
+
 #[test]
 fn test_vec() {
     let thing = vec![1, 2, 3];
@@ -51,6 +922,88 @@ fn test_vec() {
     test_serde(thing, yaml);
 }
 
+#[test]
+fn test_empty_vec() {
+    let thing: Vec<i32> = Vec::new();
+    let yaml = indoc!("
+        ---
+        []");
+    test_serde(thing, yaml);
+}
+
+#[test]
+fn test_streamed_vec() {
+    use std::cell::RefCell;
+    use std::io;
+    use std::rc::Rc;
+
+    struct SharedWriter(Rc<RefCell<Vec<u8>>>);
+
+    impl io::Write for SharedWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Serializes as the number of bytes written so far.
+    struct Progress(Rc<RefCell<Vec<u8>>>);
+
+    impl serde::Serialize for Progress {
+        fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+            where S: serde::Serializer
+        {
+            serializer.serialize_usize(self.0.borrow().len())
+        }
+    }
+
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let thing: Vec<_> = (0..3).map(|_| Progress(output.clone())).collect();
+    let mut writer = SharedWriter(output.clone());
+    serde_yaml::to_writer(&mut writer, &thing).unwrap();
+    let yaml = indoc!("
+        ---
+        - 4
+        - 7
+        - 11");
+    assert_eq!(yaml.as_bytes(), &output.borrow()[..]);
+}
+
+#[test]
+fn test_partial_writes() {
+    use std::io;
+
+    /// Accepts at most one byte per call to `write`.
+    struct ByteWriter(Vec<u8>);
+
+    impl io::Write for ByteWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.write(&buf[..buf.len().min(1)])
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut thing = BTreeMap::new();
+    thing.insert(String::from("x"), vec![1, 2]);
+    thing.insert(String::from("y"), vec![3]);
+    let yaml = indoc!("
+        ---
+        x: 
+          - 1
+          - 2
+        y: 
+          - 3");
+    let mut writer = ByteWriter(Vec::new());
+    serde_yaml::to_writer(&mut writer, &thing).unwrap();
+    assert_eq!(yaml.as_bytes(), &writer.0[..]);
+}
+
 #[test]
 fn test_map() {
     let mut thing = BTreeMap::new();
@@ -63,6 +1016,130 @@ fn test_map() {
     test_serde(thing, yaml);
 }
 
+#[test]
+fn test_map_int_keys() {
+    let mut thing = BTreeMap::new();
+    thing.insert(1, String::from("one"));
+    thing.insert(-2, String::from("minus two"));
+    let yaml = indoc!(r#"
+        ---
+        -2: minus two
+        1: one"#);
+    test_serde(thing, yaml);
+}
+
+#[test]
+fn test_map_bool_keys() {
+    let mut thing = BTreeMap::new();
+    thing.insert(false, 0);
+    thing.insert(true, 1);
+    let yaml = indoc!(r#"
+        ---
+        false: 0
+        true: 1"#);
+    test_serde(thing, yaml);
+}
+
+#[test]
+fn test_map_struct_keys() {
+    #[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+    let mut thing = BTreeMap::new();
+    thing.insert(Point { x: 1, y: 2 }, String::from("a"));
+    thing.insert(Point { x: 3, y: 4 }, String::from("b"));
+    let yaml = indoc!(r#"
+        ---
+        ? {x: 1, y: 2}
+        : a
+        ? {x: 3, y: 4}
+        : b"#);
+    test_serde(thing, yaml);
+}
+
+#[test]
+fn test_map_seq_keys() {
+    let mut thing = BTreeMap::new();
+    thing.insert(vec![1, 2], 3);
+    thing.insert(vec![], 0);
+    let yaml = indoc!(r#"
+        ---
+        ? []
+        : 0
+        ? [1, 2]
+        : 3"#);
+    test_serde(thing, yaml);
+}
+
+#[test]
+fn test_map_long_seq_key() {
+    // An implicit key would be limited to 1024 characters.
+    let key: Vec<i32> = (0..1000).collect();
+    let mut thing = BTreeMap::new();
+    thing.insert(key.clone(), String::from("long"));
+    thing.insert(vec![1], String::from("short"));
+    let yaml = serde_yaml::to_string(&thing).unwrap();
+    let key_line = format!("? {:?}", key);
+    assert!(yaml.lines().any(|line| line == key_line));
+    assert_eq!(thing, serde_yaml::from_str(&yaml).unwrap());
+}
+
+#[test]
+fn test_map_long_str_key() {
+    let key = "key ".repeat(49) + "ends";
+    assert_eq!(200, key.len());
+    let mut thing = BTreeMap::new();
+    thing.insert(key.clone(), 1);
+    thing.insert(String::from("x"), 2);
+
+    let yaml = serde_yaml::to_string(&thing).unwrap();
+    assert!(yaml.lines().any(|line| line == format!("{}: 1", key)));
+    assert_eq!(thing, serde_yaml::from_str(&yaml).unwrap());
+
+    let builder = serde_yaml::SerializerBuilder::new()
+        .explicit_key_length(80)
+        .line_width(80);
+    let yaml = builder.to_string(&thing).unwrap();
+    let expected = format!("---\n? {}\n: 1\nx: 2", key);
+    assert_eq!(expected, yaml);
+    assert_eq!(thing, serde_yaml::from_str(&yaml).unwrap());
+
+    // An implicit key would be limited to 1024 characters.
+    let key = "z".repeat(2000);
+    let mut thing = BTreeMap::new();
+    thing.insert(key.clone(), 1);
+    let yaml = serde_yaml::to_string(&thing).unwrap();
+    assert_eq!(format!("---\n? {}\n: 1", key), yaml);
+    assert_eq!(thing, serde_yaml::from_str(&yaml).unwrap());
+}
+
+#[test]
+fn test_map_colliding_keys() {
+    // Serializes like a map from `1_i32` and then `1_u8`, which are different
+    // keys in Rust but the same integer in YAML.
+    struct Colliding;
+
+    impl serde::Serialize for Colliding {
+        fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+            where S: serde::Serializer
+        {
+            let mut state = try!(serializer.serialize_map(Some(2)));
+            try!(serializer.serialize_map_key(&mut state, 1i32));
+            try!(serializer.serialize_map_value(&mut state, "first"));
+            try!(serializer.serialize_map_key(&mut state, 1u8));
+            try!(serializer.serialize_map_value(&mut state, "second"));
+            serializer.serialize_map_end(state)
+        }
+    }
+
+    let yaml = indoc!(r#"
+        ---
+        1: second"#);
+    assert_eq!(yaml, serde_yaml::to_string(&Colliding).unwrap());
+}
+
 #[test]
 fn test_basic_struct() {
     #[derive(Serialize, Deserialize, PartialEq, Debug)]
@@ -84,6 +1161,30 @@ fn test_basic_struct() {
     test_serde(thing, yaml);
 }
 
+#[test]
+fn test_renamed_fields() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Renamed {
+        #[serde(rename = "dashed-name")]
+        dashed: i32,
+        #[serde(rename = "a: b")]
+        colon: i32,
+        #[serde(rename = "# not a comment")]
+        hash: i32,
+    }
+    let thing = Renamed {
+        dashed: 1,
+        colon: 2,
+        hash: 3,
+    };
+    let yaml = indoc!(r##"
+        ---
+        "dashed-name": 1
+        "a: b": 2
+        "# not a comment": 3"##);
+    test_serde(thing, yaml);
+}
+
 #[test]
 fn test_nested_vec() {
     let thing = vec![
@@ -130,20 +1231,65 @@ fn test_option() {
     let thing = vec![Some(1), None, Some(3)];
     let yaml = indoc!("
         ---
-        - 1
+        - 1
+        - ~
+        - 3");
+    test_serde(thing, yaml);
+}
+
+#[test]
+fn test_unit() {
+    let thing = vec![(), ()];
+    let yaml = indoc!("
+        ---
+        - ~
+        - ~");
+    test_serde(thing, yaml);
+}
+
+#[test]
+fn test_unit_struct() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Marker;
+    let thing = vec![Marker, Marker];
+    let yaml = indoc!("
+        ---
         - ~
-        - 3");
+        - ~");
     test_serde(thing, yaml);
+    test_serde((), "---\n~");
 }
 
 #[test]
-fn test_unit() {
-    let thing = vec![(), ()];
+fn test_option_unit() {
+    // Some(()) and None are both written as null, which reads back as None.
     let yaml = indoc!("
         ---
         - ~
         - ~");
-    test_serde(thing, yaml);
+    assert_eq!(yaml, serde_yaml::to_string(&vec![Some(()), None]).unwrap());
+    let deserialized: Vec<Option<()>> = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(vec![None, None], deserialized);
+}
+
+#[test]
+fn test_top_level_documents() {
+    test_serde((), "---\n~");
+    test_serde(None::<i32>, "---\n~");
+    test_serde(String::from("hello"), "---\nhello");
+    test_serde(vec![1, 2, 3], "---\n- 1\n- 2\n- 3");
+
+    // A document end marker at the start of a line would end the document.
+    test_serde(String::from("..."), "---\n\"...\"");
+
+    let builder = serde_yaml::SerializerBuilder::new().explicit_start(false);
+    for s in &["42", "hello", "---", "--- a", "...", "... a", "",
+               "\u{feff}bom"] {
+        let yaml = builder.to_string(s).unwrap();
+        assert_eq!(*s, serde_yaml::from_str::<String>(&yaml).unwrap());
+    }
+    assert_eq!("~", builder.to_string(&()).unwrap());
+    assert_eq!("- 1\n- 2", builder.to_string(&vec![1, 2]).unwrap());
 }
 
 #[test]
@@ -160,6 +1306,46 @@ fn test_unit_variant() {
     test_serde(thing, yaml);
 }
 
+#[test]
+fn test_unit_variant_keyword() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Status {
+        Null,
+        True,
+        Off,
+        Running,
+    }
+    let thing = vec![Status::Null, Status::True, Status::Off, Status::Running];
+    let yaml = indoc!(r#"
+        ---
+        - "Null"
+        - "True"
+        - "Off"
+        - Running"#);
+    test_serde(thing, yaml);
+}
+
+#[test]
+fn test_unit_variant_index() {
+    use serde_yaml::{SerializerBuilder, UnitVariant};
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Variant {
+        First,
+        Second,
+    }
+    let builder = SerializerBuilder::new()
+        .unit_variant_style(UnitVariant::Index);
+    let thing = vec![Variant::Second, Variant::First];
+    let yaml = indoc!("
+        ---
+        - 1
+        - 0");
+    assert_eq!(yaml, builder.to_string(&thing).unwrap());
+
+    let deserialized: Vec<Variant> = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(thing, deserialized);
+}
+
 #[test]
 fn test_newtype_struct() {
     #[derive(Serialize, Deserialize, PartialEq, Debug)]
@@ -177,6 +1363,55 @@ fn test_newtype_struct() {
     test_serde(thing, yaml);
 }
 
+#[test]
+fn test_newtype_scalar_strings() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Id(String);
+    let thing = vec![
+        Id("12345".to_owned()),
+        Id("1_000".to_owned()),
+        Id("0b101".to_owned()),
+        Id("1:30".to_owned()),
+        Id("1h30m".to_owned()),
+    ];
+    let yaml = indoc!(r#"
+        ---
+        - "12345"
+        - "1_000"
+        - "0b101"
+        - "1:30"
+        - 1h30m"#);
+    test_serde(thing, yaml);
+}
+
+#[test]
+fn test_ip_addr() {
+    use std::net::IpAddr;
+    let thing: Vec<IpAddr> = vec![
+        "127.0.0.1".parse().unwrap(),
+        "::1".parse().unwrap(),
+        "fe80::1".parse().unwrap(),
+    ];
+    let yaml = indoc!(r#"
+        ---
+        - 127.0.0.1
+        - "::1"
+        - "fe80::1""#);
+    test_serde(thing, yaml);
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn test_uuid() {
+    extern crate uuid;
+    let thing =
+        uuid::Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+    let yaml = indoc!(r#"
+        ---
+        "67e55044-10b1-426f-9247-bb680e5fe0c8""#);
+    test_serde(thing, yaml);
+}
+
 #[test]
 fn test_newtype_variant() {
     #[derive(Serialize, Deserialize, PartialEq, Debug)]
@@ -230,6 +1465,133 @@ fn test_struct_variant() {
     test_serde(thing, yaml);
 }
 
+#[test]
+fn test_tag_variants() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Shape {
+        Empty,
+        Circle(u32),
+        Rgb(u8, u8, u8),
+        Point {
+            x: i32,
+            y: i32,
+        },
+    }
+    let thing = vec![
+        Shape::Empty,
+        Shape::Circle(5),
+        Shape::Rgb(255, 0, 0),
+        Shape::Point { x: 1, y: 2 },
+    ];
+    let yaml = indoc!(r#"
+        ---
+        - Empty
+        - !Circle 5
+        - !Rgb
+          - 255
+          - 0
+          - 0
+        - !Point
+          x: 1
+          y: 2"#);
+    let builder = serde_yaml::SerializerBuilder::new().tag_variants(true);
+    assert_eq!(yaml, builder.to_string(&thing).unwrap());
+    assert_eq!(thing, serde_yaml::from_str::<Vec<Shape>>(yaml).unwrap());
+
+    let mut map = BTreeMap::new();
+    map.insert("shape", Shape::Point { x: 1, y: 2 });
+    let yaml = indoc!(r#"
+        ---
+        shape: !Point
+          x: 1
+          y: 2"#);
+    assert_eq!(yaml, builder.to_string(&map).unwrap());
+    let deserialized: BTreeMap<String, Shape> =
+        serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(Shape::Point { x: 1, y: 2 }, deserialized["shape"]);
+}
+
+#[test]
+fn test_singleton_map() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Color {
+        Named(String),
+        Rgb(u8, u8, u8),
+    }
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Theme {
+        #[serde(serialize_with = "serde_yaml::singleton_map::serialize",
+                deserialize_with = "serde_yaml::singleton_map::deserialize")]
+        background: Color,
+        foreground: Color,
+    }
+    let thing = Theme {
+        background: Color::Rgb(255, 0, 0),
+        foreground: Color::Named("white".to_owned()),
+    };
+    let yaml = indoc!(r#"
+        ---
+        background: 
+          Rgb: 
+            - 255
+            - 0
+            - 0
+        foreground: !Named white"#);
+    let builder = serde_yaml::SerializerBuilder::new().tag_variants(true);
+    assert_eq!(yaml, builder.to_string(&thing).unwrap());
+    assert_eq!(thing, serde_yaml::from_str::<Theme>(yaml).unwrap());
+
+    let yaml = indoc!(r#"
+        ---
+        background: 
+          Rgb: 
+            - 255
+            - 0
+            - 0
+        foreground: 
+          Named: white"#);
+    assert_eq!(yaml, serde_yaml::to_string(&thing).unwrap());
+    assert_eq!(thing, serde_yaml::from_str::<Theme>(yaml).unwrap());
+}
+
+#[test]
+fn test_untagged_enum() {
+    use super::test_de::{Address, Server};
+
+    let thing = vec![
+        Address::Name(String::from("example.com")),
+        Address::Server(Server {
+            host: String::from("localhost"),
+            port: 8080,
+        }),
+    ];
+    let yaml = indoc!("
+        ---
+        - example.com
+        - 
+          host: localhost
+          port: 8080");
+    assert_eq!(yaml, serde_yaml::to_string(&thing).unwrap());
+}
+
+#[test]
+fn test_number() {
+    use serde_yaml::{Number, Value};
+    let int: Number = serde_yaml::from_str("1").unwrap();
+    assert!(int.is_i64() && !int.is_f64());
+    assert_eq!(Some(1), int.as_i64());
+    let float: Number = serde_yaml::from_str("1.0").unwrap();
+    assert!(float.is_f64() && !float.is_i64());
+    assert_eq!(Some(1.0), float.as_f64());
+
+    let thing = vec![Value::Number(1.into()), Value::Number(1.0.into())];
+    let yaml = indoc!("
+        ---
+        - 1
+        - 1.0");
+    test_serde(thing, yaml);
+}
+
 #[test]
 fn test_value() {
     use serde_yaml::{Mapping, Value};
@@ -244,8 +1606,8 @@ fn test_value() {
         config: Value::Sequence(vec![
             Value::Null,
             Value::Bool(true),
-            Value::I64(65535),
-            Value::F64(0.54321),
+            Value::Number(65535.into()),
+            Value::Number(0.54321.into()),
             Value::String("s".into()),
             Value::Mapping(Mapping::new()),
         ]),
@@ -262,3 +1624,674 @@ fn test_value() {
           - {}"#);
     test_serde(thing, yaml);
 }
+
+#[test]
+fn test_tagged_scalar() {
+    use serde_yaml::{TaggedValue, Value};
+    let thing = TaggedValue {
+        tag: "Ref".to_owned(),
+        value: Value::String("MyResource".to_owned()),
+    };
+    let yaml = indoc!("
+        ---
+        !Ref MyResource");
+    test_serde(thing, yaml);
+}
+
+#[test]
+fn test_tagged_mapping() {
+    use serde_yaml::{Mapping, TaggedValue, Value};
+    let mut point = Mapping::new();
+    point.insert(Value::String("x".to_owned()), Value::Number(1.into()));
+    point.insert(Value::String("y".to_owned()), Value::Number(2.into()));
+    let thing = TaggedValue {
+        tag: "Point".to_owned(),
+        value: Value::Mapping(point),
+    };
+    let yaml = indoc!("
+        ---
+        !Point
+        x: 1
+        y: 2");
+    test_serde(thing, yaml);
+}
+
+#[test]
+fn test_nested_tagged() {
+    use serde_yaml::{TaggedValue, Value};
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Stack {
+        bucket: TaggedValue,
+        outputs: Vec<TaggedValue>,
+    }
+    let thing = Stack {
+        bucket: TaggedValue {
+            tag: "Sub".to_owned(),
+            value: Value::Sequence(vec![Value::String("a".to_owned()),
+                                        Value::String("b".to_owned())]),
+        },
+        outputs: vec![
+            TaggedValue {
+                tag: "GetAtt".to_owned(),
+                value: Value::String("Bucket.Arn".to_owned()),
+            },
+            TaggedValue {
+                tag: "Port".to_owned(),
+                value: Value::Number(80.into()),
+            },
+        ],
+    };
+    let yaml = indoc!("
+        ---
+        bucket: !Sub
+          - a
+          - b
+        outputs: 
+          - !GetAtt Bucket.Arn
+          - !Port 80");
+    test_serde(thing, yaml);
+}
+
+#[test]
+fn test_tag_directive() {
+    use serde_yaml::{SerializerBuilder, TaggedValue, Value};
+    let tagged = |tag: &str, value: &str| {
+        TaggedValue {
+            tag: tag.to_owned(),
+            value: Value::String(value.to_owned()),
+        }
+    };
+    let thing = vec![
+        tagged("<tag:example.com,2000:app/color>", "red"),
+        tagged("<tag:example.com,2000:app/color>", "blue"),
+        tagged("my-size", "large"),
+        tagged("Other", "unchanged"),
+    ];
+    let builder = SerializerBuilder::new()
+        .tag_directive("!e!", "tag:example.com,2000:app/")
+        .tag_directive("!m!", "!my-");
+    let yaml = indoc!("
+        %TAG !e! tag:example.com,2000:app/
+        %TAG !m! !my-
+        ---
+        - !e!color red
+        - !e!color blue
+        - !m!size large
+        - !Other unchanged");
+    assert_eq!(yaml, builder.to_string(&thing).unwrap());
+    assert_eq!(thing, serde_yaml::from_str::<Vec<TaggedValue>>(yaml).unwrap());
+
+    // Each document of a stream declares the directives again.
+    let mut writer = builder.multi_doc_writer(Vec::new());
+    writer.write(&thing[0]).unwrap();
+    writer.write(&thing[2]).unwrap();
+    let yaml = String::from_utf8(writer.into_inner()).unwrap();
+    let docs: Vec<TaggedValue> = serde_yaml::from_str_multi(&yaml)
+        .into_iter()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(vec![thing[0].clone(), thing[2].clone()], docs);
+}
+
+#[test]
+fn test_value_into_iter() {
+    use serde_yaml::Value;
+    let seq: Value = serde_yaml::from_str("[a, b]").unwrap();
+    let elems: Vec<Value> = seq.into_sequence().unwrap().into_iter().collect();
+    assert_eq!(vec![Value::String("a".to_owned()),
+                    Value::String("b".to_owned())],
+               elems);
+
+    let map: Value = serde_yaml::from_str("{b: 1, a: 2}").unwrap();
+    let entries: Vec<(Value, Value)> =
+        map.into_mapping().unwrap().into_iter().collect();
+    assert_eq!(vec![(Value::String("b".to_owned()), Value::Number(1.into())),
+                    (Value::String("a".to_owned()), Value::Number(2.into()))],
+               entries);
+
+    assert!(Value::Null.into_sequence().is_none());
+    assert!(Value::Null.into_mapping().is_none());
+}
+
+#[test]
+fn test_value_round_trip_unchanged() {
+    use serde_yaml::Value;
+    // Written the way the emitter writes, so that a no-op round trip through
+    // Value reproduces it exactly. Mapping order is kept by Mapping.
+    let yaml = indoc!(r#"
+        ---
+        name: serde
+        version: 0.5
+        keywords: 
+          - "yes"
+          - "null"
+          - "1.0"
+        nested: 
+          quoted: "a: b"
+          empty: []
+          none: ~
+        last: true"#);
+    let value: Value = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(yaml, serde_yaml::to_string(&value).unwrap());
+}
+
+#[test]
+fn test_value_get() {
+    use serde_yaml::Value;
+    let yaml = indoc!("
+        ---
+        database:
+          hosts:
+            - name: a
+            - name: b
+          port: 5432
+          a/b~c: slash
+        1: one");
+    let value: Value = serde_yaml::from_str(yaml).unwrap();
+    let string = |s: &str| Value::String(s.to_owned());
+
+    let database = value.get("database").unwrap();
+    assert_eq!(Some(&Value::Number(5432.into())), database.get("port"));
+    let hosts = database.get(String::from("hosts")).unwrap();
+    assert_eq!(Some(&string("b")), hosts.get(1).and_then(|h| h.get("name")));
+    assert_eq!(None, hosts.get(2));
+    assert_eq!(None, hosts.get("name"));
+    assert_eq!(None, database.get(0));
+    assert_eq!(None, database.get("port").unwrap().get("x"));
+
+    assert_eq!(Some(&value), value.pointer(""));
+    assert_eq!(Some(&string("a")), value.pointer("/database/hosts/0/name"));
+    assert_eq!(Some(&string("slash")), value.pointer("/database/a~1b~0c"));
+    assert_eq!(Some(&string("one")), value.pointer("/1"));
+    assert_eq!(None, value.pointer("/database/hosts/x"));
+    assert_eq!(None, value.pointer("/database/port/0"));
+    assert_eq!(None, value.pointer("/missing"));
+    assert_eq!(None, value.pointer("database"));
+}
+
+#[test]
+fn test_value_from_str() {
+    use serde_yaml::Value;
+    let yaml = indoc!("
+        ---
+        a: 1
+        b: [x, y]");
+    let parsed: Value = yaml.parse().unwrap();
+    let deserialized: Value = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(deserialized, parsed);
+    assert!("[unclosed".parse::<Value>().is_err());
+}
+
+#[test]
+fn test_value_accessors() {
+    use serde_yaml::Value;
+    let yaml = indoc!("
+        ---
+        - ~
+        - true
+        - -1
+        - 2.5
+        - text
+        - [x]
+        - {k: v}");
+    let mut value: Value = serde_yaml::from_str(yaml).unwrap();
+    let values = value.as_sequence().unwrap().clone();
+
+    let nulls: Vec<_> = values.iter().map(Value::is_null).collect();
+    assert_eq!(nulls, [true, false, false, false, false, false, false]);
+    assert_eq!(Some(true), values[1].as_bool());
+    assert_eq!(Some(-1), values[2].as_i64());
+    assert_eq!(None, values[2].as_u64());
+    assert_eq!(Some(2.5), values[3].as_f64());
+    assert_eq!(Some("text"), values[4].as_str());
+    assert_eq!(Some(&vec![Value::String("x".to_owned())]),
+               values[5].as_sequence());
+    assert_eq!(Some(1), values[6].as_mapping().map(|m| m.len()));
+    assert_eq!(Some(2), Value::Number(2.into()).as_u64());
+
+    for (i, v) in values.iter().enumerate() {
+        assert_eq!(i == 1, v.is_bool());
+        assert_eq!(i == 2 || i == 3, v.is_number());
+        assert_eq!(i == 2, v.is_i64());
+        assert_eq!(i == 3, v.is_f64());
+        assert_eq!(i == 4, v.is_string());
+        assert_eq!(i == 5, v.is_sequence());
+        assert_eq!(i == 6, v.is_mapping());
+    }
+    assert_eq!(None, values[0].as_bool());
+    assert_eq!(None, values[4].as_i64());
+    assert_eq!(None, values[2].as_f64());
+    assert_eq!(None, values[1].as_str());
+    assert_eq!(None, values[6].as_sequence());
+    assert_eq!(None, values[5].as_mapping());
+
+    value.as_sequence_mut().unwrap().truncate(1);
+    assert_eq!(Some(1), value.as_sequence().map(|s| s.len()));
+    assert_eq!(None, value.as_mapping_mut());
+    assert_eq!(Some(vec![Value::Null]), value.into_sequence());
+
+    let mut values = values;
+    assert_eq!(Some("text".to_owned()), values[4].take().into_string());
+    assert!(values[4].is_null());
+    assert_eq!(None, values[3].take().into_string());
+    assert_eq!(None, values[4].take().into_sequence());
+    assert_eq!(None, values[5].clone().into_mapping());
+    assert_eq!(Some(1), values[6].take().into_mapping().map(|m| m.len()));
+    assert_eq!(None, values[6].take().into_mapping());
+    assert_eq!(Value::Bool(true), Value::Bool(true).take());
+}
+
+#[test]
+fn test_value_apply_merge() {
+    use serde_yaml::Value;
+    let yaml = indoc!("
+        ---
+        base: &base
+          a: 1
+          b: 2
+        other: &other
+          b: 20
+          c: 30
+        single:
+          <<: *base
+          b: 3
+        multiple:
+          <<: [*other, *base]
+          d: 4
+        nested:
+          - inner:
+              <<: {x: 1}");
+    let mut value: Value = serde_yaml::from_str(yaml).unwrap();
+    value.apply_merge().unwrap();
+
+    let expected = indoc!("
+        ---
+        base:
+          a: 1
+          b: 2
+        other:
+          b: 20
+          c: 30
+        single:
+          b: 3
+          a: 1
+        multiple:
+          d: 4
+          b: 20
+          c: 30
+          a: 1
+        nested:
+          - inner:
+              x: 1");
+    let expected: Value = serde_yaml::from_str(expected).unwrap();
+    assert_eq!(expected, value);
+
+    let mut value: Value = serde_yaml::from_str("<<: [{a: 1}, 2]").unwrap();
+    assert!(value.apply_merge().is_err());
+}
+
+#[test]
+fn test_value_merge() {
+    use serde_yaml::{SequenceMerge, Value};
+    let base = indoc!("
+        ---
+        name: app
+        db:
+          host: localhost
+          port: 5432
+          options: {ssl: false}
+        hosts: [a, b]");
+    let overrides = indoc!("
+        ---
+        db:
+          host: db.example.com
+          options: {timeout: 30}
+        hosts: [c]
+        debug: true");
+    let base: Value = serde_yaml::from_str(base).unwrap();
+    let overrides: Value = serde_yaml::from_str(overrides).unwrap();
+
+    let mut value = base.clone();
+    value.merge(overrides.clone());
+    let expected = indoc!("
+        ---
+        name: app
+        db:
+          host: db.example.com
+          port: 5432
+          options: {ssl: false, timeout: 30}
+        hosts: [c]
+        debug: true");
+    let expected: Value = serde_yaml::from_str(expected).unwrap();
+    assert_eq!(expected, value);
+
+    let mut value = base.clone();
+    value.merge_with(overrides, SequenceMerge::Append);
+    let hosts: Value = serde_yaml::from_str("[a, b, c]").unwrap();
+    assert_eq!(Some(&hosts), value.get("hosts"));
+
+    // A scalar or a sequence replaces a mapping, and the other way around.
+    let mut value = base;
+    value.merge(Value::String(String::from("scalar")));
+    assert_eq!(Value::String(String::from("scalar")), value);
+    value.merge(Value::Sequence(vec![Value::Null]));
+    value.merge_with(Value::Sequence(vec![Value::Bool(true)]),
+                     SequenceMerge::Append);
+    assert_eq!(Value::Sequence(vec![Value::Null, Value::Bool(true)]), value);
+}
+
+#[test]
+fn test_value_ord() {
+    use std::f64;
+    use serde_yaml::{Mapping, Value};
+    let int = |i: i64| Value::Number(i.into());
+    let float = |f: f64| Value::Number(f.into());
+    let string = |s: &str| Value::String(s.to_owned());
+
+    let mut map = Mapping::new();
+    map.insert(string("k"), Value::Null);
+    let ordered = vec![
+        Value::Null,
+        Value::Bool(false),
+        Value::Bool(true),
+        float(f64::NEG_INFINITY),
+        int(i64::min_value()),
+        int(-1),
+        float(-0.5),
+        int(0),
+        float(0.0),
+        float(0.5),
+        int(1),
+        float(1.0),
+        int(i64::max_value()),
+        float(9223372036854775808.0),
+        Value::Number(u64::max_value().into()),
+        float(f64::INFINITY),
+        float(f64::NAN),
+        string(""),
+        string("a"),
+        string("b"),
+        Value::Sequence(vec![]),
+        Value::Sequence(vec![int(1)]),
+        Value::Sequence(vec![int(1), int(2)]),
+        Value::Sequence(vec![int(2)]),
+        Value::Mapping(Mapping::new()),
+        Value::Mapping(map),
+    ];
+    for (i, a) in ordered.iter().enumerate() {
+        for (j, b) in ordered.iter().enumerate() {
+            assert_eq!(i.cmp(&j), a.cmp(b), "{:?} against {:?}", a, b);
+        }
+    }
+
+    let mut reversed = ordered.clone();
+    reversed.reverse();
+    reversed.sort();
+    assert_eq!(ordered, reversed);
+
+    let mut map = BTreeMap::new();
+    map.insert(string("b"), int(2));
+    map.insert(int(10), string("ten"));
+    map.insert(Value::Null, Value::Null);
+    map.insert(string("b"), int(3));
+    let keys: Vec<_> = map.keys().cloned().collect();
+    assert_eq!(vec![Value::Null, int(10), string("b")], keys);
+    assert_eq!(Some(&int(3)), map.get(&string("b")));
+}
+
+#[test]
+fn test_value_sort_keys() {
+    use serde_yaml::Value;
+    let yaml = indoc!("
+        ---
+        zeta: 1
+        10: ten
+        2: two
+        1.5: one and a half
+        true: yes
+        ~: null
+        alpha:
+          c: [3, {y: 1, x: 2}]
+          b: 2");
+    let mut value: Value = serde_yaml::from_str(yaml).unwrap();
+    value.sort_keys();
+    let expected = indoc!(r#"
+        ---
+        ~: ~
+        true: "yes"
+        1.5: one and a half
+        2: two
+        10: ten
+        alpha: 
+          b: 2
+          c: [3, {x: 2, y: 1}]
+        zeta: 1"#);
+    let expected: Value = serde_yaml::from_str(expected).unwrap();
+    assert_eq!(expected, value);
+    let sorted = serde_yaml::to_string(&value).unwrap();
+    assert!(sorted.starts_with("---\n~: ~\ntrue: \"yes\"\n1.5:"));
+}
+
+#[test]
+fn test_value_collect() {
+    use serde_yaml::{Mapping, Value};
+    let servers: Value = vec!["a.example.com", "b.example.com"]
+        .into_iter()
+        .collect();
+    let value: Value = vec![
+        ("name", Value::from("app")),
+        ("port", Value::from(8080)),
+        ("debug", Value::from(false)),
+        ("ratio", Value::from(0.5)),
+        ("servers", servers),
+        ("ports", Value::from(vec![80u16, 443])),
+    ].into_iter().collect();
+    let yaml = indoc!("
+        ---
+        name: app
+        port: 8080
+        debug: false
+        ratio: 0.5
+        servers: 
+          - a.example.com
+          - b.example.com
+        ports: 
+          - 80
+          - 443");
+    assert_eq!(yaml, serde_yaml::to_string(&value).unwrap());
+    assert_eq!(value, serde_yaml::from_str::<Value>(yaml).unwrap());
+
+    let mapping: Mapping = vec![(Value::from("a"), Value::from(1))]
+        .into_iter()
+        .collect();
+    assert_eq!(Value::Mapping(mapping), vec![("a", 1)].into_iter().collect());
+}
+
+#[test]
+fn test_mapping_entry() {
+    use serde_yaml::{Mapping, MappingExt, Value};
+    let mut config: Mapping = serde_yaml::from_str("a: 1\nb: 2").unwrap();
+
+    // An existing key keeps its value and its place.
+    assert_eq!(&Value::from(1), config.entry("a".into()).or_insert(10.into()));
+    *config.entry("b".into()).or_insert_with(|| panic!()) = 20.into();
+
+    // An absent key is added at the end.
+    config.entry("c".into()).or_insert(3.into());
+    let entry = config.entry("d".into());
+    assert_eq!(&Value::from("d"), entry.key());
+    let list = entry.or_insert_with(|| Value::Sequence(vec![]));
+    list.as_sequence_mut().unwrap().push(4.into());
+
+    let expected = indoc!("
+        ---
+        a: 1
+        b: 20
+        c: 3
+        d: 
+          - 4");
+    assert_eq!(expected, serde_yaml::to_string(&config).unwrap());
+}
+
+#[test]
+fn test_value_numeric_forms() {
+    use serde_yaml::Value;
+    let yaml = indoc!("
+        ---
+        x: 1.0
+        y: 1
+        z: -0.0
+        big: 18446744073709551615
+        exp: 1.0e+20");
+    let value: Value = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(yaml, serde_yaml::to_string(&value).unwrap());
+    assert!(value.get("x").unwrap().is_f64());
+    assert!(value.get("y").unwrap().is_i64());
+    let big = value.get("big").unwrap();
+    assert_eq!(Some(18446744073709551615), big.as_u64());
+    assert!(!big.is_f64());
+}
+
+#[test]
+fn test_value_float_access() {
+    use serde_yaml::Value;
+    let yaml = indoc!("
+        ---
+        pi: 3.14
+        rate: 0.5");
+    let value: Value = serde_yaml::from_str(yaml).unwrap();
+    let pi = value.get("pi").and_then(Value::as_f64).unwrap();
+    let rate = value.get("rate").and_then(Value::as_f64).unwrap();
+    assert_eq!(3.14, pi);
+    assert_eq!(1.57, pi * rate);
+    assert_eq!(yaml, serde_yaml::to_string(&value).unwrap());
+}
+
+#[test]
+fn test_unsized_seq() {
+    /// Serializes the numbers below it without giving the length up front,
+    /// as a sequence built from an iterator adapter would.
+    struct Counter(u32);
+
+    impl serde::Serialize for Counter {
+        fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+            where S: serde::Serializer
+        {
+            let mut state = try!(serializer.serialize_seq(None));
+            for i in (0..self.0).filter(|_| true) {
+                try!(serializer.serialize_seq_elt(&mut state, i));
+            }
+            serializer.serialize_seq_end(state)
+        }
+    }
+
+    let n = 100_000;
+    let sized: Vec<u32> = (0..n).collect();
+
+    // A top-level sequence is written as its elements arrive.
+    let yaml = serde_yaml::to_string(&Counter(n)).unwrap();
+    assert_eq!(serde_yaml::to_string(&sized).unwrap(), yaml);
+
+    // A nested one is collected first, and is still in block style.
+    let mut thing = BTreeMap::new();
+    thing.insert("counter", Counter(n));
+    let yaml = serde_yaml::to_string(&thing).unwrap();
+    assert!(yaml.starts_with("---\ncounter: \n  - 0\n  - 1\n  - 2\n"));
+    assert!(yaml.ends_with("\n  - 99999"));
+    let mut expected = BTreeMap::new();
+    expected.insert("counter", sized);
+    assert_eq!(serde_yaml::to_string(&expected).unwrap(), yaml);
+    let read: BTreeMap<String, Vec<u32>> = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(expected["counter"], read["counter"]);
+}
+
+#[test]
+fn test_to_fmt_writer() {
+    use std::fmt;
+
+    let mut thing = BTreeMap::new();
+    thing.insert(String::from("x"), vec![1, 2]);
+    thing.insert(String::from("y"), vec![3]);
+    let yaml = serde_yaml::to_string(&thing).unwrap();
+
+    let mut out = String::from("# header\n");
+    serde_yaml::to_fmt_writer(&mut out, &thing).unwrap();
+    assert_eq!(format!("# header\n{}", yaml), out);
+
+    // A top-level sequence is streamed into the writer too.
+    let mut out = String::new();
+    serde_yaml::to_fmt_writer(&mut out, &vec!["a", "b"]).unwrap();
+    assert_eq!("---\n- a\n- b", out);
+
+    /// Fails every write.
+    struct Full;
+
+    impl fmt::Write for Full {
+        fn write_str(&mut self, _: &str) -> fmt::Result {
+            Err(fmt::Error)
+        }
+    }
+
+    assert!(serde_yaml::to_fmt_writer(&mut Full, &thing).is_err());
+}
+
+#[test]
+fn test_scalar_styles() {
+    use serde_yaml::{DeserializerBuilder, ScalarStyle, SerializerBuilder,
+                     Value};
+    let yaml = indoc!(r#"
+        ---
+        name: app
+        title: 'My "app"'
+        greeting: "Hello\tworld"
+        script: |
+          make
+          make install
+        description: >-
+          A long
+
+          description
+        kept: |+
+          kept
+
+        items: 
+          - plain
+          - 'single'
+          - "double"
+          - |-
+            literal
+            block
+        nested: 
+          inner: >
+            folded text
+        "#);
+    let (value, styles): (Value, _) = DeserializerBuilder::new()
+        .from_str_with_styles(yaml)
+        .unwrap();
+    assert_eq!(Some(ScalarStyle::Literal), styles.get(&["script"]));
+    assert_eq!(Some(ScalarStyle::Folded), styles.get(&["nested", "inner"]));
+    assert_eq!(Some(ScalarStyle::SingleQuoted), styles.get(&["items", "1"]));
+    assert_eq!(None, styles.get(&["items", "0"]));
+    assert_eq!(Some("kept\n\n"), value.get("kept").and_then(Value::as_str));
+    assert_eq!(Some("A long\ndescription"),
+               value.get("description").and_then(Value::as_str));
+
+    let builder = SerializerBuilder::new().scalar_styles(styles);
+    let written = builder.to_string(&value).unwrap();
+    assert_eq!(yaml, written);
+    assert_eq!(value, serde_yaml::from_str::<Value>(&written).unwrap());
+
+    // A string the style cannot hold is written as usual.
+    let mut styles = serde_yaml::ScalarStyles::new();
+    styles.insert(&["0"], ScalarStyle::SingleQuoted);
+    styles.insert(&["1"], ScalarStyle::Literal);
+    styles.insert(&["2"], ScalarStyle::Folded);
+    let builder = SerializerBuilder::new().scalar_styles(styles);
+    let thing = vec!["two\nlines", "  indented", "a\n  b"];
+    let yaml = indoc!(r#"
+        ---
+        - "two\nlines"
+        - "  indented"
+        - "a\n  b""#);
+    assert_eq!(yaml, builder.to_string(&thing).unwrap());
+}